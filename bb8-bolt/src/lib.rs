@@ -1,21 +1,48 @@
 use std::collections::HashMap;
 use std::convert::TryFrom;
+use std::iter::FromIterator;
 use std::net::{SocketAddr, ToSocketAddrs};
+use std::time::Duration;
 
 use bb8::ManageConnection;
 use thiserror::Error;
 
 use async_trait::async_trait;
+use bolt_client::config::ConnectConfig;
 use bolt_client::*;
+use bolt_proto::version::VersionRange;
 use bolt_proto::*;
 
-// TODO: Support Bolt v4
-const SUPPORTED_VERSIONS: &[u32; 4] = &[3, 2, 1, 0];
+const DEFAULT_VERSIONS: &[VersionRange] = &[
+    VersionRange::new(ProtocolVersion::new(4, 4), 4),
+    VersionRange::exact(ProtocolVersion::new(3, 0)),
+    VersionRange::exact(ProtocolVersion::new(2, 0)),
+    VersionRange::exact(ProtocolVersion::new(1, 0)),
+];
+
+/// How the pool checks that a connection is still usable before handing it out.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum HealthCheck {
+    /// Send a `RESET` message and wait for the server to acknowledge it. This is the default.
+    #[default]
+    Reset,
+    /// Run `RETURN 1;` using the messages appropriate for the connection's protocol version. This is slower than a
+    /// `RESET`, but also checks that the server is able to run queries.
+    Query,
+    /// Skip the check if the connection sent or received a message within the given duration, and send a `RESET`
+    /// otherwise.
+    SkipIfUsedWithin(Duration),
+}
 
 pub struct BoltConnectionManager {
     addr: SocketAddr,
     domain: Option<String>,
+    // Used instead of the other settings to open and initialize connections, if the manager was created from a config
+    config: Option<ConnectConfig>,
     metadata: HashMap<String, Value>,
+    versions: Vec<VersionRange>,
+    routing_context: Option<HashMap<String, Value>>,
+    health_check: HealthCheck,
 }
 
 impl BoltConnectionManager {
@@ -30,9 +57,51 @@ impl BoltConnectionManager {
                 .next()
                 .ok_or_else(|| Error::InvalidAddress)?,
             domain,
+            config: None,
             metadata: metadata.into_iter().map(|(k, v)| (k, v.into())).collect(),
+            versions: DEFAULT_VERSIONS.to_vec(),
+            routing_context: None,
+            health_check: HealthCheck::default(),
         })
     }
+
+    /// Create a connection manager from a [`ConnectConfig`](bolt_client::config::ConnectConfig), e.g. one parsed from
+    /// a connection URI. Connections are opened and initialized with
+    /// [`ConnectConfig::connect`](bolt_client::config::ConnectConfig::connect), so the configured address, TLS
+    /// options, user agent, authentication details, protocol versions, routing context and timeouts are used for
+    /// every connection, and [`with_versions`](BoltConnectionManager::with_versions) and
+    /// [`with_routing_context`](BoltConnectionManager::with_routing_context) have no effect.
+    ///
+    /// Pooled connections don't select the configured database, since Bolt selects a database for each transaction.
+    /// Pass [`database`](bolt_client::config::ConnectConfig::database) in the metadata of each `BEGIN` or `RUN`
+    /// message instead.
+    pub fn from_config(config: &ConnectConfig) -> Result<Self, Error> {
+        let domain = config.tls().map(|_| config.host().to_string());
+        let mut manager = Self::new(config.address(), domain, config.metadata())?;
+        manager.config = Some(config.clone());
+        Ok(manager)
+    }
+
+    /// Set the protocol versions proposed when opening connections, in order of preference. By default, versions 4.4
+    /// through 4.0 are proposed, followed by versions 3, 2 and 1.
+    pub fn with_versions(mut self, versions: &[VersionRange]) -> Self {
+        self.versions = versions.to_vec();
+        self
+    }
+
+    /// Set how connections are checked before being handed out by the pool. See [`HealthCheck`](HealthCheck) for the
+    /// available strategies.
+    pub fn with_health_check(mut self, health_check: HealthCheck) -> Self {
+        self.health_check = health_check;
+        self
+    }
+
+    /// Set the routing context sent in the `HELLO` message of each connection (Bolt v4.1+). This indicates to the
+    /// server that the connection belongs to a routing driver. No routing context is sent by default.
+    pub fn with_routing_context(mut self, context: HashMap<String, impl Into<Value>>) -> Self {
+        self.routing_context = Some(context.into_iter().map(|(k, v)| (k, v.into())).collect());
+        self
+    }
 }
 
 #[derive(Debug, Error)]
@@ -42,7 +111,7 @@ pub enum Error {
     #[error("Invalid host address.")]
     InvalidAddress,
     #[error("Invalid client version: {0}")]
-    InvalidClientVersion(u32),
+    InvalidClientVersion(ProtocolVersion),
     #[error("Initialization of client failed: {0}")]
     ClientInitFailed(String),
     #[error(transparent)]
@@ -57,10 +126,13 @@ impl ManageConnection for BoltConnectionManager {
     type Error = Error;
 
     async fn connect(&self) -> Result<Self::Connection, Self::Error> {
+        if let Some(config) = &self.config {
+            return Ok(config.connect().await?);
+        }
         let mut client = Client::new(self.addr, self.domain.as_deref()).await?;
-        client.handshake(SUPPORTED_VERSIONS).await?;
+        client.handshake(&self.versions).await?;
         let version = client.version().unwrap(); // ok to unwrap if handshake succeeds
-        let response = match version {
+        let response = match version.major() {
             1 | 2 => {
                 let mut metadata = self.metadata.clone();
                 let user_agent = metadata.remove("user_agent").ok_or_else(|| {
@@ -69,6 +141,15 @@ impl ManageConnection for BoltConnectionManager {
                 client.init(String::try_from(user_agent)?, metadata).await?
             }
             3 => client.hello(self.metadata.clone()).await?,
+            4 => {
+                let mut metadata = self.metadata.clone();
+                if let Some(context) = &self.routing_context {
+                    if version >= ProtocolVersion::new(4, 1) {
+                        metadata.insert("routing".to_string(), Value::from(context.clone()));
+                    }
+                }
+                client.hello(metadata).await?
+            }
             _ => return Err(Error::InvalidClientVersion(version)),
         };
 
@@ -78,103 +159,403 @@ impl ManageConnection for BoltConnectionManager {
         }
     }
 
-    async fn is_valid(&self, mut conn: Self::Connection) -> Result<Self::Connection, Self::Error> {
-        let response = conn.run("RETURN 1;".to_string(), None).await?;
-        message::Success::try_from(response)?;
-        let (response, _records) = conn.pull_all().await?;
-        message::Success::try_from(response)?;
-        Ok(conn)
+    async fn is_valid(&self, conn: Self::Connection) -> Result<Self::Connection, Self::Error> {
+        match self.health_check {
+            HealthCheck::Reset => check_with_reset(conn).await,
+            HealthCheck::Query => check_with_query(conn).await,
+            HealthCheck::SkipIfUsedWithin(threshold) if conn.idle_time() < threshold => Ok(conn),
+            HealthCheck::SkipIfUsedWithin(_) => check_with_reset(conn).await,
+        }
     }
 
-    fn has_broken(&self, _conn: &mut Self::Connection) -> bool {
-        // There's no good/fast way to check if a tokio TcpStream is still healthy. However, given that the TcpStream
-        // is shut down when the connection object is dropped, we can assume existing connections aren't broken.
-        false
+    fn has_broken(&self, conn: &mut Self::Connection) -> bool {
+        // A connection that saw an I/O error is unusable. One that was returned with a pending response, an open
+        // result or transaction, or an unacknowledged failure would surprise its next user, so it's dropped as well.
+        conn.is_defunct() || conn.is_dirty()
     }
 }
 
+async fn check_with_reset(mut conn: Client) -> Result<Client, Error> {
+    let response = conn.reset().await?;
+    message::Success::try_from(response)?;
+    Ok(conn)
+}
+
+async fn check_with_query(mut conn: Client) -> Result<Client, Error> {
+    let version = conn.version().unwrap();
+    let response = match version.major() {
+        1 | 2 => conn.run("RETURN 1;".to_string(), None).await?,
+        _ => {
+            conn.run_with_metadata("RETURN 1;".to_string(), None, None)
+                .await?
+        }
+    };
+    message::Success::try_from(response)?;
+    let (response, _records) = match version.major() {
+        1..=3 => conn.pull_all().await?,
+        _ => {
+            conn.pull(Some(HashMap::from_iter(vec![(
+                "n".to_string(),
+                Value::from(-1_i8),
+            )])))
+            .await?
+        }
+    };
+    message::Success::try_from(response)?;
+    Ok(conn)
+}
+
 #[cfg(test)]
 mod tests {
-    use std::env;
-    use std::iter::FromIterator;
+    use std::convert::TryInto;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
 
     use bb8::*;
+    use bolt_test_support::{MockServer, Script};
 
     use super::*;
 
-    fn get_connection_manager() -> BoltConnectionManager {
+    fn get_connection_manager(server: &MockServer, password: &str) -> BoltConnectionManager {
         BoltConnectionManager::new(
-            env::var("BOLT_TEST_ADDR").unwrap(),
-            env::var("BOLT_TEST_DOMAIN").ok(),
+            server.address(),
+            None,
             HashMap::from_iter(vec![
-                ("user_agent".to_string(), "bolt-client/X.Y.Z".to_string()),
-                ("scheme".to_string(), "basic".to_string()),
-                (
-                    "principal".to_string(),
-                    env::var("BOLT_TEST_USERNAME").unwrap(),
-                ),
-                (
-                    "credentials".to_string(),
-                    env::var("BOLT_TEST_PASSWORD").unwrap(),
-                ),
+                ("user_agent".to_string(), "bolt-client/X.Y.Z"),
+                ("scheme".to_string(), "basic"),
+                ("principal".to_string(), "neo4j"),
+                ("credentials".to_string(), password),
             ]),
         )
         .unwrap()
     }
 
+    fn auth_token(password: &str) -> HashMap<String, Value> {
+        HashMap::from_iter(vec![
+            ("scheme".to_string(), Value::from("basic")),
+            ("principal".to_string(), Value::from("neo4j")),
+            ("credentials".to_string(), Value::from(password)),
+        ])
+    }
+
+    fn success() -> Message {
+        Message::Success(message::Success::new(HashMap::new()))
+    }
+
+    fn hello(metadata: HashMap<String, Value>) -> Message {
+        let mut metadata = metadata;
+        metadata.insert("user_agent".to_string(), Value::from("bolt-client/X.Y.Z"));
+        Message::Hello(message::Hello::new(metadata))
+    }
+
+    // A server that accepts any number of `RETURN 1` queries from each pooled connection
+    async fn pool_server(version: ProtocolVersion) -> MockServer {
+        let init = if version.major() < 3 {
+            Message::Init(message::Init::new(
+                "bolt-client/X.Y.Z".to_string(),
+                auth_token("test"),
+            ))
+        } else {
+            hello(auth_token("test"))
+        };
+        let script = Script::new(version)
+            .expect(init, vec![success()])
+            .then_respond_with(|message| match message {
+                Message::Run(_) | Message::RunWithMetadata(_) => Some(vec![success()]),
+                Message::PullAll | Message::Pull(_) => Some(vec![
+                    Message::Record(message::Record::new(vec![Value::from(1_i8)])),
+                    success(),
+                ]),
+                Message::Goodbye => Some(vec![]),
+                _ => None,
+            });
+        MockServer::start(script).await
+    }
+
+    async fn run_query(client: &mut Client) -> Vec<message::Record> {
+        let version = client.version().unwrap();
+        let (response, records) = if version.major() < 3 {
+            client
+                .run("RETURN 1 as num;".to_string(), None)
+                .await
+                .unwrap();
+            client.pull_all().await.unwrap()
+        } else {
+            client
+                .run_with_metadata("RETURN 1 as num;".to_string(), None, None)
+                .await
+                .unwrap();
+            if version.major() < 4 {
+                client.pull_all().await.unwrap()
+            } else {
+                client.pull(None).await.unwrap()
+            }
+        };
+        assert!(message::Success::try_from(response).is_ok());
+        records
+    }
+
     #[tokio::test]
-    // TODO: This really only tests Bolt v3 clients, in practice. Find a way to test pools of specific client versions!
     async fn basic_pool() {
-        let manager = get_connection_manager();
-        let pool = Pool::builder().max_size(15).build(manager).await.unwrap();
-
-        let mut tasks = Vec::with_capacity(50);
-        for i in 1..=tasks.capacity() {
-            let pool = pool.clone();
-            tasks.push(tokio::spawn(async move {
-                let mut client = pool.get().await.unwrap();
-                let statement = format!("RETURN {} as num;", i);
-                let version = client.version().unwrap();
-                let (response, records) = match version {
-                    1 | 2 => {
-                        client.run(statement, None).await.unwrap();
-                        client.pull_all().await.unwrap()
+        let versions = [
+            ProtocolVersion::new(1, 0),
+            ProtocolVersion::new(2, 0),
+            ProtocolVersion::new(3, 0),
+            ProtocolVersion::new(4, 0),
+            ProtocolVersion::new(4, 4),
+        ];
+        for &version in &versions {
+            let server = pool_server(version).await;
+            let manager = get_connection_manager(&server, "test");
+            let pool = Pool::builder().max_size(15).build(manager).await.unwrap();
+
+            let mut tasks = Vec::with_capacity(50);
+            for _ in 1..=tasks.capacity() {
+                let pool = pool.clone();
+                tasks.push(tokio::spawn(async move {
+                    let mut client = pool.get().await.unwrap();
+                    assert_eq!(client.version(), Some(version));
+                    let records = run_query(&mut client).await;
+                    assert_eq!(records[0].fields(), &[Value::from(1_i8)]);
+                }));
+            }
+            for result in futures::future::join_all(tasks).await {
+                result.unwrap();
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn configured_versions() {
+        let server = pool_server(ProtocolVersion::new(4, 0)).await;
+        let manager = get_connection_manager(&server, "test")
+            .with_versions(&[VersionRange::exact(ProtocolVersion::new(3, 0))]);
+        let pool = Pool::builder().max_size(1).build_unchecked(manager);
+        let conn = pool.dedicated_connection().await;
+        assert!(matches!(
+            conn,
+            Err(Error::ClientError(
+                bolt_client::error::Error::HandshakeFailed
+            ))
+        ));
+    }
+
+    #[tokio::test]
+    async fn routing_context() {
+        let mut metadata = auth_token("test");
+        metadata.insert(
+            "routing".to_string(),
+            Value::from(HashMap::from_iter(vec![(
+                "region".to_string(),
+                Value::from("eu"),
+            )])),
+        );
+        let script =
+            Script::new(ProtocolVersion::new(4, 1)).expect(hello(metadata), vec![success()]);
+        let server = MockServer::start(script).await;
+        let manager = get_connection_manager(&server, "test")
+            .with_routing_context(HashMap::from_iter(vec![("region".to_string(), "eu")]));
+        let pool = Pool::builder().max_size(1).build_unchecked(manager);
+        let conn = pool.dedicated_connection().await.unwrap();
+        assert_eq!(conn.version(), Some(ProtocolVersion::new(4, 1)));
+    }
+
+    #[tokio::test]
+    async fn from_config() {
+        let server = pool_server(ProtocolVersion::new(4, 0)).await;
+        let config = ConnectConfig::from_uri(&format!("bolt://neo4j:test@{}", server.address()))
+            .unwrap()
+            .with_user_agent("bolt-client/X.Y.Z");
+        let manager = BoltConnectionManager::from_config(&config).unwrap();
+        let pool = Pool::builder().max_size(1).build(manager).await.unwrap();
+        let mut conn = pool.get().await.unwrap();
+        let records = run_query(&mut conn).await;
+        assert_eq!(records[0].fields(), &[Value::from(1_i8)]);
+        drop(conn);
+        server.verify();
+
+        // The routing context includes the address of the server, which isn't known until it starts
+        let script = Script::new(ProtocolVersion::new(4, 1)).expect_with(
+            "HELLO with routing context",
+            |message| match message {
+                Message::Hello(hello) => {
+                    let mut metadata = hello.metadata().clone();
+                    let context: Option<HashMap<String, Value>> = metadata
+                        .remove("routing")
+                        .and_then(|context| context.try_into().ok());
+                    metadata.remove("user_agent");
+                    metadata == auth_token("test")
+                        && matches!(context, Some(context)
+                            if context.get("region") == Some(&Value::from("eu"))
+                                && context.contains_key("address"))
+                }
+                _ => false,
+            },
+            vec![success()],
+        );
+        let server = MockServer::start(script).await;
+        let config = ConnectConfig::from_uri(&format!(
+            "neo4j://neo4j:test@{}?region=eu",
+            server.address()
+        ))
+        .unwrap()
+        .with_user_agent("bolt-client/X.Y.Z");
+        let manager = BoltConnectionManager::from_config(&config).unwrap();
+        let pool = Pool::builder().max_size(1).build_unchecked(manager);
+        let conn = pool.dedicated_connection().await.unwrap();
+        assert_eq!(conn.version(), Some(ProtocolVersion::new(4, 1)));
+        server.verify();
+    }
+
+    // A v3 server that counts the connections made to it, and fails queries containing "FAIL"
+    async fn counting_server(connections: Arc<AtomicUsize>) -> MockServer {
+        let script =
+            Script::new(ProtocolVersion::new(3, 0)).then_respond_with(
+                move |message| match message {
+                    Message::Hello(_) => {
+                        connections.fetch_add(1, Ordering::SeqCst);
+                        Some(vec![success()])
                     }
-                    3 => {
-                        client
-                            .run_with_metadata(statement, None, None)
-                            .await
-                            .unwrap();
-                        client.pull_all().await.unwrap()
+                    Message::RunWithMetadata(run) if run.statement().contains("FAIL") => {
+                        Some(vec![Message::Failure(message::Failure::new(
+                            HashMap::from_iter(vec![(
+                                "code".to_string(),
+                                Value::from("Neo.ClientError.Statement.SyntaxError"),
+                            )]),
+                        ))])
                     }
-                    _ => panic!("Unsupported client version: {}", version),
-                };
-                assert!(message::Success::try_from(response).is_ok());
-                assert_eq!(records[0].fields(), &[Value::from(i as i8)]);
-            }));
+                    Message::RunWithMetadata(_) => Some(vec![success()]),
+                    Message::PullAll => Some(vec![success()]),
+                    Message::Goodbye => Some(vec![]),
+                    _ => None,
+                },
+            );
+        MockServer::start(script).await
+    }
+
+    #[tokio::test]
+    async fn broken_connections_are_replaced() {
+        let connections = Arc::new(AtomicUsize::new(0));
+        let server = counting_server(Arc::clone(&connections)).await;
+        let manager = get_connection_manager(&server, "test");
+        let pool = Pool::builder()
+            .max_size(1)
+            .test_on_check_out(false)
+            .build(manager)
+            .await
+            .unwrap();
+
+        // A clean connection is reused
+        run_query(&mut pool.get().await.unwrap()).await;
+        run_query(&mut pool.get().await.unwrap()).await;
+        assert_eq!(connections.load(Ordering::SeqCst), 1);
+
+        // A connection with an unacknowledged failure is dropped
+        {
+            let mut client = pool.get().await.unwrap();
+            client
+                .run_with_metadata("FAIL".to_string(), None, None)
+                .await
+                .unwrap();
+            assert!(client.is_dirty());
         }
-        tokio::join!(futures::future::join_all(tasks));
+        run_query(&mut pool.get().await.unwrap()).await;
+        assert_eq!(connections.load(Ordering::SeqCst), 2);
+
+        // So is a connection with an unfinished result
+        {
+            let mut client = pool.get().await.unwrap();
+            client
+                .run_with_metadata("RETURN 1;".to_string(), None, None)
+                .await
+                .unwrap();
+        }
+        run_query(&mut pool.get().await.unwrap()).await;
+        assert_eq!(connections.load(Ordering::SeqCst), 3);
+    }
+
+    // Get a connection from a pool using the given health check twice, and return the messages sent to the server
+    async fn health_check_messages(health_check: HealthCheck) -> Vec<Message> {
+        let messages = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let server_messages = Arc::clone(&messages);
+        let script = Script::new(ProtocolVersion::new(4, 0)).then_respond_with(move |message| {
+            server_messages.lock().unwrap().push(message.clone());
+            match message {
+                Message::Hello(_) | Message::Reset | Message::RunWithMetadata(_) => {
+                    Some(vec![success()])
+                }
+                Message::Pull(_) => Some(vec![
+                    Message::Record(message::Record::new(vec![Value::from(1_i8)])),
+                    success(),
+                ]),
+                Message::Goodbye => Some(vec![]),
+                _ => None,
+            }
+        });
+        let server = MockServer::start(script).await;
+        let manager = get_connection_manager(&server, "test").with_health_check(health_check);
+        let pool = Pool::builder()
+            .max_size(1)
+            .test_on_check_out(true)
+            .build(manager)
+            .await
+            .unwrap();
+        drop(pool.get().await.unwrap());
+        drop(pool.get().await.unwrap());
+        let messages = messages.lock().unwrap().clone();
+        assert!(matches!(messages[0], Message::Hello(_)));
+        messages[1..].to_vec()
+    }
+
+    #[tokio::test]
+    async fn health_checks() {
+        let messages = health_check_messages(HealthCheck::Reset).await;
+        assert!(!messages.is_empty());
+        assert!(messages.iter().all(|m| *m == Message::Reset));
+
+        let messages = health_check_messages(HealthCheck::Query).await;
+        assert!(!messages.is_empty());
+        for pair in messages.chunks(2) {
+            assert!(matches!(
+                pair,
+                [Message::RunWithMetadata(_), Message::Pull(_)]
+            ));
+        }
+
+        let threshold = Duration::from_secs(3600);
+        let messages = health_check_messages(HealthCheck::SkipIfUsedWithin(threshold)).await;
+        assert!(messages.is_empty());
+
+        let threshold = Duration::from_secs(0);
+        let messages = health_check_messages(HealthCheck::SkipIfUsedWithin(threshold)).await;
+        assert!(!messages.is_empty());
+        assert!(messages.iter().all(|m| *m == Message::Reset));
     }
 
     #[tokio::test]
     async fn invalid_init_fails() {
-        let invalid_manager = BoltConnectionManager::new(
-            "127.0.0.1:7687",
-            None,
-            HashMap::from_iter(vec![
-                ("user_agent".to_string(), "bolt-client/X.Y.Z"),
-                ("scheme".to_string(), "basic"),
-                ("principal".to_string(), "neo4j"),
-                ("credentials".to_string(), "invalid"),
-            ]),
-        )
-        .unwrap();
+        let hello = hello(auth_token("invalid"));
+        let failure = Message::Failure(message::Failure::new(HashMap::from_iter(vec![
+            (
+                "code".to_string(),
+                Value::from("Neo.ClientError.Security.Unauthorized"),
+            ),
+            (
+                "message".to_string(),
+                Value::from("The client is unauthorized due to authentication failure."),
+            ),
+        ])));
+        let script = Script::new(ProtocolVersion::new(3, 0))
+            .expect(hello, vec![failure])
+            .close();
+        let server = MockServer::start(script).await;
+        let invalid_manager = get_connection_manager(&server, "invalid");
         let pool = Pool::builder()
             .max_size(2)
             .build(invalid_manager)
             .await
             .unwrap();
         let conn = pool.dedicated_connection().await;
-        assert!(conn.is_err());
+        assert!(matches!(conn, Err(Error::ClientInitFailed(_))));
     }
 }
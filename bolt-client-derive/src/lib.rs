@@ -0,0 +1,228 @@
+//! Derive macros for the `FromRecord` and `FromNode` traits in `bolt_client::mapping`. See the documentation of that
+//! module for the supported attributes.
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+
+use syn::spanned::Spanned;
+use syn::*;
+
+use quote::quote;
+
+#[proc_macro_derive(FromRecord, attributes(bolt))]
+pub fn from_record_derive(input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as DeriveInput);
+    impl_from_record(&ast)
+        .unwrap_or_else(|error| error.to_compile_error())
+        .into()
+}
+
+#[proc_macro_derive(FromNode, attributes(bolt))]
+pub fn from_node_derive(input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as DeriveInput);
+    impl_from_node(&ast)
+        .unwrap_or_else(|error| error.to_compile_error())
+        .into()
+}
+
+// Options given to a field with #[bolt(...)]
+#[derive(Default)]
+struct FieldOptions {
+    // The record column or node property to read from, if different from the field name
+    rename: Option<String>,
+    // Use Default::default() if the column or property is missing
+    default: bool,
+    // Convert the column with FromNode instead of TryFrom<Value> (records only)
+    node: bool,
+    // Use the node identity (nodes only)
+    id: bool,
+    // Use the node labels (nodes only)
+    labels: bool,
+}
+
+fn bolt_attributes(attrs: &[Attribute]) -> Result<Vec<NestedMeta>> {
+    let mut items = Vec::new();
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("bolt")) {
+        match attr.parse_meta()? {
+            Meta::List(list) => items.extend(list.nested),
+            meta => return Err(Error::new(meta.span(), "expected #[bolt(...)]")),
+        }
+    }
+    Ok(items)
+}
+
+fn string_value(lit: &Lit) -> Result<String> {
+    match lit {
+        Lit::Str(lit_str) => Ok(lit_str.value()),
+        _ => Err(Error::new(lit.span(), "expected a string literal")),
+    }
+}
+
+fn field_options(field: &Field) -> Result<FieldOptions> {
+    let mut options = FieldOptions::default();
+    for item in bolt_attributes(&field.attrs)? {
+        match &item {
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("rename") => {
+                options.rename = Some(string_value(&nv.lit)?)
+            }
+            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("default") => options.default = true,
+            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("node") => options.node = true,
+            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("id") => options.id = true,
+            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("labels") => options.labels = true,
+            _ => {
+                return Err(Error::new(
+                    item.span(),
+                    "unknown attribute, expected one of `rename = \"...\"`, `default`, `node`, `id` or `labels`",
+                ))
+            }
+        }
+    }
+    Ok(options)
+}
+
+fn named_fields<'a>(ast: &'a DeriveInput, derive: &str) -> Result<Vec<&'a Field>> {
+    match &ast.data {
+        Data::Struct(DataStruct {
+            fields: Fields::Named(fields),
+            ..
+        }) => Ok(fields.named.iter().collect()),
+        _ => Err(Error::new(
+            ast.ident.span(),
+            format!(
+                "{} can only be derived for structs with named fields",
+                derive
+            ),
+        )),
+    }
+}
+
+fn is_option(ty: &Type) -> bool {
+    match ty {
+        Type::Path(TypePath { path, .. }) => {
+            matches!(path.segments.last(), Some(segment) if segment.ident == "Option")
+        }
+        _ => false,
+    }
+}
+
+// Generate an expression taking the value for the given field out of `__values`, a HashMap<String, Value>
+fn take_value(field: &Field, options: &FieldOptions) -> TokenStream2 {
+    let key = options
+        .rename
+        .clone()
+        .unwrap_or_else(|| field.ident.as_ref().unwrap().to_string());
+    let convert = if options.node {
+        quote!(::bolt_client::mapping::__private::convert_node(__value, #key)?)
+    } else {
+        quote!(::bolt_client::mapping::__private::convert(__value, #key)?)
+    };
+    let missing = if options.default {
+        quote!(::std::default::Default::default())
+    } else if is_option(&field.ty) {
+        quote!(::std::option::Option::None)
+    } else {
+        quote!(return ::std::result::Result::Err(::bolt_client::mapping::__private::missing(#key)))
+    };
+    quote! {
+        match __values.remove(#key) {
+            ::std::option::Option::Some(__value) => #convert,
+            ::std::option::Option::None => #missing,
+        }
+    }
+}
+
+fn impl_from_record(ast: &DeriveInput) -> Result<TokenStream2> {
+    let name = &ast.ident;
+    let (impl_generics, type_generics, where_clause) = ast.generics.split_for_impl();
+    let fields = named_fields(ast, "FromRecord")?;
+
+    let mut initializers = Vec::new();
+    for field in fields {
+        let options = field_options(field)?;
+        if options.id || options.labels {
+            return Err(Error::new(
+                field.span(),
+                "`id` and `labels` can only be used when deriving FromNode",
+            ));
+        }
+        let ident = &field.ident;
+        let value = take_value(field, &options);
+        initializers.push(quote!(#ident: #value));
+    }
+
+    Ok(quote! {
+        impl #impl_generics ::bolt_client::mapping::FromRecord for #name #type_generics #where_clause {
+            fn from_record(
+                record: ::bolt_client::mapping::__private::Record,
+                fields: &[::std::string::String],
+            ) -> ::bolt_client::error::Result<Self> {
+                let mut __values = ::bolt_client::mapping::__private::columns(record, fields);
+                ::std::result::Result::Ok(Self {
+                    #(#initializers,)*
+                })
+            }
+        }
+    })
+}
+
+fn impl_from_node(ast: &DeriveInput) -> Result<TokenStream2> {
+    let name = &ast.ident;
+    let (impl_generics, type_generics, where_clause) = ast.generics.split_for_impl();
+    let fields = named_fields(ast, "FromNode")?;
+
+    let mut labels = Vec::new();
+    for item in bolt_attributes(&ast.attrs)? {
+        match &item {
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("label") => {
+                labels.push(string_value(&nv.lit)?)
+            }
+            _ => {
+                return Err(Error::new(
+                    item.span(),
+                    "unknown attribute, expected `label = \"...\"`",
+                ))
+            }
+        }
+    }
+
+    let mut initializers = Vec::new();
+    for field in fields {
+        let options = field_options(field)?;
+        if options.node {
+            return Err(Error::new(
+                field.span(),
+                "`node` can only be used when deriving FromRecord",
+            ));
+        }
+        let ident = &field.ident;
+        let key = ident.as_ref().unwrap().to_string();
+        let value = if options.id {
+            quote! {
+                ::bolt_client::mapping::__private::convert(
+                    ::bolt_client::mapping::__private::Value::from(node.node_identity()),
+                    #key,
+                )?
+            }
+        } else if options.labels {
+            quote!(node.labels().iter().cloned().collect())
+        } else {
+            take_value(field, &options)
+        };
+        initializers.push(quote!(#ident: #value));
+    }
+
+    Ok(quote! {
+        impl #impl_generics ::bolt_client::mapping::FromNode for #name #type_generics #where_clause {
+            fn from_node(
+                node: ::bolt_client::mapping::__private::Node,
+            ) -> ::bolt_client::error::Result<Self> {
+                #(::bolt_client::mapping::__private::check_label(&node, #labels)?;)*
+                let mut __values = node.properties().clone();
+                ::std::result::Result::Ok(Self {
+                    #(#initializers,)*
+                })
+            }
+        }
+    })
+}
@@ -6,25 +6,49 @@ use syn::*;
 
 use quote::quote;
 
+type Version = (u8, u8);
+
+// A version requirement is either a major version (e.g. `4`), which allows any minor version, or a major and minor
+// version (e.g. `4.3`), which allows that minor version or later within the same major version.
+fn parse_version(lit: &Lit) -> Version {
+    match lit {
+        Lit::Int(lit_int) => (
+            lit_int
+                .base10_parse::<u8>()
+                .expect("couldn't parse version"),
+            0,
+        ),
+        Lit::Float(lit_float) => {
+            let digits = lit_float.base10_digits();
+            let mut parts = digits.splitn(2, '.').map(|part| {
+                part.parse::<u8>()
+                    .unwrap_or_else(|_| panic!("couldn't parse version: {}", digits))
+            });
+            (parts.next().unwrap(), parts.next().unwrap_or(0))
+        }
+        _ => panic!("Invalid version token: {:?}", lit),
+    }
+}
+
 fn get_fn_info(
     func: &ItemFn,
     args: AttributeArgs,
-) -> (&Vec<Attribute>, &Visibility, &Signature, Vec<u32>, &Block) {
+) -> (
+    &Vec<Attribute>,
+    &Visibility,
+    &Signature,
+    Vec<Version>,
+    &Block,
+) {
     let signature = &func.sig;
     let function_body = &func.block;
     let attributes = &func.attrs;
     let visibility = &func.vis;
-    let versions: Vec<u32> = args
+    let versions: Vec<Version> = args
         .into_iter()
         .map(|item| {
             if let NestedMeta::Lit(lit) = item {
-                if let Lit::Int(lit_int) = lit {
-                    lit_int
-                        .base10_parse::<u32>()
-                        .expect("couldn't parse version")
-                } else {
-                    panic!("Invalid version token: {:?}", lit);
-                }
+                parse_version(&lit)
             } else {
                 panic!("Invalid version token: {:?}", item);
             }
@@ -38,14 +62,20 @@ pub fn bolt_version(attr_args: TokenStream, item: TokenStream) -> TokenStream {
     let func = syn::parse_macro_input!(item as syn::ItemFn);
     let args = syn::parse_macro_input!(attr_args as syn::AttributeArgs);
     let (attributes, visibility, signature, versions, function_body) = get_fn_info(&func, args);
+    let conditions = versions.iter().map(|(major, minor)| {
+        if *minor == 0 {
+            quote!((version.major() == #major))
+        } else {
+            quote!((version.major() == #major && version.minor() >= #minor))
+        }
+    });
 
     let gen = quote! {
         #(#attributes)*
         #visibility #signature {
-            if self.version.is_some() && [#(#versions),*].contains(&self.version.unwrap()) {
-                #function_body
-            } else {
-                Err(crate::error::Error::UnsupportedOperation(self.version))
+            match self.version {
+                Some(version) if #(#conditions)||* => #function_body
+                _ => Err(crate::error::Error::UnsupportedOperation(self.version)),
             }
         }
     };
@@ -6,6 +6,7 @@ use criterion::*;
 use tokio::runtime::Runtime;
 
 use bolt_client::*;
+use bolt_proto::version::*;
 
 async fn get_initialized_client() -> Result<Client, Box<dyn std::error::Error>> {
     let mut client = Client::new(
@@ -13,7 +14,14 @@ async fn get_initialized_client() -> Result<Client, Box<dyn std::error::Error>>
         env::var("BOLT_TEST_DOMAIN").ok().as_deref(),
     )
     .await?;
-    client.handshake(&[3, 2, 1, 0]).await?; // TODO: Should we benchmark multiple client versions?
+    // TODO: Should we benchmark multiple client versions?
+    client
+        .handshake(&[
+            VersionRange::exact(ProtocolVersion::new(3, 0)),
+            VersionRange::exact(ProtocolVersion::new(2, 0)),
+            VersionRange::exact(ProtocolVersion::new(1, 0)),
+        ])
+        .await?;
     client
         .hello(HashMap::from_iter(vec![
             ("user_agent".to_string(), "bolt-client/X.Y.Z".to_string()),
@@ -0,0 +1,382 @@
+//! A synchronous client, for programs that don't otherwise use an async runtime. Requires the `blocking` feature.
+//!
+//! [`blocking::Client`](Client) wraps an asynchronous [`Client`](crate::Client) together with a single-threaded
+//! runtime that drives it, blocking the current thread until each operation has completed. Its methods mirror those of
+//! the asynchronous client, and are subject to the same protocol version restrictions.
+//!
+//! Since the blocking client runs its own runtime, it must not be used from within an asynchronous context.
+//!
+//! # Example
+//! ```no_run
+//! use std::collections::HashMap;
+//! use std::iter::FromIterator;
+//!
+//! use bolt_client::blocking::Client;
+//! use bolt_proto::version::*;
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut client = Client::new("127.0.0.1:7687", None)?;
+//! client.handshake(&[VersionRange::exact(ProtocolVersion::new(4, 0))])?;
+//! client.hello(HashMap::from_iter(vec![
+//!     ("user_agent".to_string(), "my-client-name/1.0".to_string()),
+//!     ("scheme".to_string(), "basic".to_string()),
+//!     ("principal".to_string(), "neo4j".to_string()),
+//!     ("credentials".to_string(), "neo4j".to_string()),
+//! ]))?;
+//!
+//! client.begin(HashMap::<String, String>::new())?;
+//! client.run_with_metadata("RETURN 1 as num;".to_string(), None, None)?;
+//! let (response, records) = client.pull(None)?;
+//! client.commit()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+#[cfg(unix)]
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::net::ToSocketAddrs;
+use tokio::runtime::{Builder, Runtime};
+use tokio_rustls::rustls::ClientConfig;
+
+use bolt_proto::message::Record;
+use bolt_proto::version::VersionRange;
+use bolt_proto::{Message, ProtocolVersion, Value};
+
+use crate::client::{PipelineResponse, ServerState};
+use crate::config::ConnectConfig;
+use crate::error::*;
+
+/// A synchronous Bolt client. See the [module documentation](self) for details.
+#[derive(Debug)]
+pub struct Client {
+    // Declared before the runtime, so the connection is dropped while the runtime is still available
+    client: crate::Client,
+    runtime: Runtime,
+}
+
+impl Client {
+    /// Create a new client pointing to the provided server address. See [`Client::new`](crate::Client::new).
+    pub fn new(addr: impl ToSocketAddrs, domain: Option<&str>) -> Result<Self> {
+        let mut runtime = new_runtime()?;
+        let client = runtime.block_on(crate::Client::new(addr, domain))?;
+        Ok(Self { client, runtime })
+    }
+
+    /// Create a new client pointing to the provided server address, connecting over TLS with the given
+    /// configuration. See [`Client::with_tls`](crate::Client::with_tls).
+    pub fn with_tls(
+        addr: impl ToSocketAddrs,
+        domain: &str,
+        config: impl Into<Arc<ClientConfig>>,
+    ) -> Result<Self> {
+        let mut runtime = new_runtime()?;
+        let client = runtime.block_on(crate::Client::with_tls(addr, domain, config))?;
+        Ok(Self { client, runtime })
+    }
+
+    /// Create a new client connected to the Unix domain socket at the given path.
+    #[cfg(unix)]
+    pub fn connect_unix(path: impl AsRef<Path>) -> Result<Self> {
+        let mut runtime = new_runtime()?;
+        let client = runtime.block_on(crate::Client::connect_unix(path))?;
+        Ok(Self { client, runtime })
+    }
+
+    /// Create a new client from the given configuration, performing the handshake and initializing the connection.
+    /// See [`ConnectConfig::connect`](crate::config::ConnectConfig::connect).
+    pub fn connect(config: &ConnectConfig) -> Result<Self> {
+        let mut runtime = new_runtime()?;
+        let client = runtime.block_on(config.connect())?;
+        Ok(Self { client, runtime })
+    }
+
+    /// Get the protocol version negotiated by this client, or `None` if no handshake has been performed yet.
+    pub fn version(&self) -> Option<ProtocolVersion> {
+        self.client.version()
+    }
+
+    /// Get the current state of the server's side of the connection. See
+    /// [`Client::server_state`](crate::Client::server_state).
+    pub fn server_state(&self) -> ServerState {
+        self.client.server_state()
+    }
+
+    /// Whether the connection has failed or been closed. A defunct client can no longer be used.
+    pub fn is_defunct(&self) -> bool {
+        self.client.is_defunct()
+    }
+
+    /// Whether the connection has unfinished work. See [`Client::is_dirty`](crate::Client::is_dirty).
+    pub fn is_dirty(&self) -> bool {
+        self.client.is_dirty()
+    }
+
+    /// Get the time elapsed since a message was last sent or received by this client.
+    pub fn idle_time(&self) -> Duration {
+        self.client.idle_time()
+    }
+
+    /// See [`Client::set_handshake_timeout`](crate::Client::set_handshake_timeout).
+    pub fn set_handshake_timeout(&mut self, timeout: Option<Duration>) {
+        self.client.set_handshake_timeout(timeout)
+    }
+
+    /// See [`Client::set_read_timeout`](crate::Client::set_read_timeout).
+    pub fn set_read_timeout(&mut self, timeout: Option<Duration>) {
+        self.client.set_read_timeout(timeout)
+    }
+
+    /// See [`Client::set_write_timeout`](crate::Client::set_write_timeout).
+    pub fn set_write_timeout(&mut self, timeout: Option<Duration>) {
+        self.client.set_write_timeout(timeout)
+    }
+
+    pub fn handshake_timeout(&self) -> Option<Duration> {
+        self.client.handshake_timeout()
+    }
+
+    pub fn read_timeout(&self) -> Option<Duration> {
+        self.client.read_timeout()
+    }
+
+    pub fn write_timeout(&self) -> Option<Duration> {
+        self.client.write_timeout()
+    }
+
+    /// Perform a handshake with the Bolt server. See [`Client::handshake`](crate::Client::handshake).
+    pub fn handshake(&mut self, preferred_versions: &[VersionRange]) -> Result<()> {
+        self.runtime
+            .block_on(self.client.handshake(preferred_versions))
+    }
+
+    /// Send multiple messages to the server without waiting for a response. See
+    /// [`Client::pipeline`](crate::Client::pipeline).
+    pub fn pipeline(&mut self, messages: Vec<Message>) -> Result<Vec<PipelineResponse>> {
+        self.runtime.block_on(self.client.pipeline(messages))
+    }
+
+    /// Send an `INIT` message to the server. See [`Client::init`](crate::Client::init).
+    pub fn init(
+        &mut self,
+        client_name: String,
+        auth_token: HashMap<String, impl Into<Value>>,
+    ) -> Result<Message> {
+        self.runtime
+            .block_on(self.client.init(client_name, auth_token))
+    }
+
+    /// Send a `RUN` message to the server. See [`Client::run`](crate::Client::run).
+    pub fn run(
+        &mut self,
+        statement: String,
+        parameters: Option<HashMap<String, Value>>,
+    ) -> Result<Message> {
+        self.runtime
+            .block_on(self.client.run(statement, parameters))
+    }
+
+    /// Send a `DISCARD_ALL` message to the server. See [`Client::discard_all`](crate::Client::discard_all).
+    pub fn discard_all(&mut self) -> Result<Message> {
+        self.runtime.block_on(self.client.discard_all())
+    }
+
+    /// Send a `PULL_ALL` message to the server. See [`Client::pull_all`](crate::Client::pull_all).
+    pub fn pull_all(&mut self) -> Result<(Message, Vec<Record>)> {
+        self.runtime.block_on(self.client.pull_all())
+    }
+
+    /// Send an `ACK_FAILURE` message to the server. See [`Client::ack_failure`](crate::Client::ack_failure).
+    pub fn ack_failure(&mut self) -> Result<Message> {
+        self.runtime.block_on(self.client.ack_failure())
+    }
+
+    /// Send a `RESET` message to the server. See [`Client::reset`](crate::Client::reset).
+    pub fn reset(&mut self) -> Result<Message> {
+        self.runtime.block_on(self.client.reset())
+    }
+
+    /// Send a `HELLO` message to the server. See [`Client::hello`](crate::Client::hello).
+    pub fn hello(&mut self, metadata: HashMap<String, impl Into<Value>>) -> Result<Message> {
+        self.runtime.block_on(self.client.hello(metadata))
+    }
+
+    /// Send a `GOODBYE` message to the server. See [`Client::goodbye`](crate::Client::goodbye).
+    pub fn goodbye(&mut self) -> Result<()> {
+        self.runtime.block_on(self.client.goodbye())
+    }
+
+    /// Send a `RUN_WITH_METADATA` message to the server. See
+    /// [`Client::run_with_metadata`](crate::Client::run_with_metadata).
+    pub fn run_with_metadata(
+        &mut self,
+        statement: String,
+        parameters: Option<HashMap<String, Value>>,
+        metadata: Option<HashMap<String, Value>>,
+    ) -> Result<Message> {
+        self.runtime.block_on(
+            self.client
+                .run_with_metadata(statement, parameters, metadata),
+        )
+    }
+
+    /// Send a `BEGIN` message to the server. See [`Client::begin`](crate::Client::begin).
+    pub fn begin(&mut self, metadata: HashMap<String, impl Into<Value>>) -> Result<Message> {
+        self.runtime.block_on(self.client.begin(metadata))
+    }
+
+    /// Send a `COMMIT` message to the server. See [`Client::commit`](crate::Client::commit).
+    pub fn commit(&mut self) -> Result<Message> {
+        self.runtime.block_on(self.client.commit())
+    }
+
+    /// Send a `ROLLBACK` message to the server. See [`Client::rollback`](crate::Client::rollback).
+    pub fn rollback(&mut self) -> Result<Message> {
+        self.runtime.block_on(self.client.rollback())
+    }
+
+    /// Send a `DISCARD` message to the server. See [`Client::discard`](crate::Client::discard).
+    pub fn discard(&mut self, metadata: Option<HashMap<String, Value>>) -> Result<Message> {
+        self.runtime.block_on(self.client.discard(metadata))
+    }
+
+    /// Send a `PULL` message to the server. See [`Client::pull`](crate::Client::pull).
+    pub fn pull(
+        &mut self,
+        metadata: Option<HashMap<String, Value>>,
+    ) -> Result<(Message, Vec<Record>)> {
+        self.runtime.block_on(self.client.pull(metadata))
+    }
+
+    /// Send a `ROUTE` message to the server. See [`Client::route`](crate::Client::route).
+    pub fn route(
+        &mut self,
+        context: HashMap<String, impl Into<Value>>,
+        bookmarks: Vec<String>,
+        db: Option<String>,
+    ) -> Result<Message> {
+        self.runtime
+            .block_on(self.client.route(context, bookmarks, db))
+    }
+}
+
+fn new_runtime() -> Result<Runtime> {
+    Ok(Builder::new().basic_scheduler().enable_all().build()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+    use std::iter::FromIterator;
+
+    use bolt_proto::message::*;
+    use bolt_test_support::{MockServer, Script};
+
+    use crate::client::v1::tests::*;
+
+    use super::*;
+
+    // Start a mock server on a runtime of its own, which must be kept alive for the duration of the test
+    fn start(script: Script) -> (Runtime, MockServer) {
+        let mut runtime = Runtime::new().unwrap();
+        let server = runtime.block_on(MockServer::start(script));
+        (runtime, server)
+    }
+
+    fn run_msg() -> Message {
+        Message::RunWithMetadata(RunWithMetadata::new(
+            "RETURN 1 as n;".to_string(),
+            HashMap::new(),
+            HashMap::new(),
+        ))
+    }
+
+    fn hello(client: &mut Client) -> Result<Message> {
+        client.hello(HashMap::from_iter(vec![
+            ("user_agent".to_string(), USER_AGENT.to_string()),
+            ("scheme".to_string(), "basic".to_string()),
+            ("principal".to_string(), USERNAME.to_string()),
+            ("credentials".to_string(), PASSWORD.to_string()),
+        ]))
+    }
+
+    #[test]
+    fn auto_commit() {
+        let script = expect_all(
+            initialized(4),
+            &[run_msg(), Message::Pull(Pull::new(HashMap::new()))],
+            vec![
+                vec![fields_success()],
+                vec![record(vec![Value::from(1_i8)]), success(vec![])],
+            ],
+        );
+        let (_runtime, server) = start(script);
+
+        let mut client = Client::new(server.address(), None).unwrap();
+        client
+            .handshake(&[VersionRange::exact(ProtocolVersion::new(4, 0))])
+            .unwrap();
+        assert_eq!(client.version(), Some(ProtocolVersion::new(4, 0)));
+        assert!(Success::try_from(hello(&mut client).unwrap()).is_ok());
+        let response = client
+            .run_with_metadata("RETURN 1 as n;".to_string(), None, None)
+            .unwrap();
+        assert!(Success::try_from(response).is_ok());
+        let (response, records) = client.pull(None).unwrap();
+        assert!(Success::try_from(response).is_ok());
+        assert_eq!(records[0].fields(), &[Value::from(1_i8)]);
+        assert_eq!(client.server_state(), ServerState::Ready);
+    }
+
+    #[test]
+    fn transaction() {
+        let script = expect_all(
+            initialized(3),
+            &[
+                Message::Begin(Begin::new(HashMap::new())),
+                run_msg(),
+                Message::PullAll,
+                Message::Commit,
+            ],
+            vec![
+                vec![success(vec![])],
+                vec![fields_success()],
+                vec![record(vec![Value::from(1_i8)]), success(vec![])],
+                vec![success(vec![("bookmark", Value::from("bookmark:1"))])],
+            ],
+        );
+        let (_runtime, server) = start(script);
+
+        let mut client = Client::new(server.address(), None).unwrap();
+        client
+            .handshake(&[VersionRange::exact(ProtocolVersion::new(3, 0))])
+            .unwrap();
+        assert!(Success::try_from(hello(&mut client).unwrap()).is_ok());
+        assert!(Success::try_from(client.begin(HashMap::<String, Value>::new()).unwrap()).is_ok());
+        client
+            .run_with_metadata("RETURN 1 as n;".to_string(), None, None)
+            .unwrap();
+        let (_, records) = client.pull_all().unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(client.server_state(), ServerState::TxReady);
+        assert!(Success::try_from(client.commit().unwrap()).is_ok());
+        assert_eq!(client.server_state(), ServerState::Ready);
+    }
+
+    #[test]
+    fn unsupported_operation() {
+        let (_runtime, server) = start(Script::new(ProtocolVersion::new(1, 0)));
+
+        let mut client = Client::new(server.address(), None).unwrap();
+        client
+            .handshake(&[VersionRange::exact(ProtocolVersion::new(1, 0))])
+            .unwrap();
+        assert!(matches!(
+            client.commit(),
+            Err(Error::UnsupportedOperation(_))
+        ));
+    }
+}
@@ -0,0 +1,79 @@
+//! Bookmarks for causal consistency across connections.
+//!
+//! When a transaction is committed, the server responds with a bookmark identifying it. Passing that bookmark along
+//! when beginning a later transaction makes the server wait until it has caught up with the bookmarked transaction,
+//! so reads observe earlier writes even if they happen on a different connection or cluster member.
+//!
+//! Each [`Session`](crate::session::Session) keeps track of the bookmark of its last committed transaction. To chain
+//! transactions across several sessions, give them a shared [`BookmarkManager`](BookmarkManager).
+
+use std::collections::BTreeSet;
+use std::sync::{Arc, Mutex};
+
+/// Combines the bookmarks of the sessions it is shared by. Cloning a `BookmarkManager` yields a handle to the same set
+/// of bookmarks.
+#[derive(Debug, Clone, Default)]
+pub struct BookmarkManager {
+    bookmarks: Arc<Mutex<BTreeSet<String>>>,
+}
+
+impl BookmarkManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start with the given bookmarks, such as ones saved by another process.
+    pub fn with_bookmarks(self, bookmarks: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.bookmarks
+            .lock()
+            .unwrap()
+            .extend(bookmarks.into_iter().map(Into::into));
+        self
+    }
+
+    /// Get the current bookmarks, which are sent when a session using this manager begins a transaction.
+    pub fn bookmarks(&self) -> Vec<String> {
+        self.bookmarks.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Record the bookmark of a newly committed transaction, replacing the bookmarks it was started with, since the
+    /// new bookmark implies them.
+    pub fn update(&self, previous: &[String], bookmark: impl Into<String>) {
+        let mut bookmarks = self.bookmarks.lock().unwrap();
+        for previous in previous {
+            bookmarks.remove(previous);
+        }
+        bookmarks.insert(bookmark.into());
+    }
+
+    /// Forget all bookmarks.
+    pub fn clear(&self) {
+        self.bookmarks.lock().unwrap().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn update() {
+        let manager = BookmarkManager::new().with_bookmarks(vec!["bm:1", "bm:2"]);
+        assert_eq!(manager.bookmarks(), vec!["bm:1", "bm:2"]);
+
+        // Bookmarks added by other sessions in the meantime are kept
+        manager.update(&["bm:1".to_string()], "bm:3");
+        assert_eq!(manager.bookmarks(), vec!["bm:2", "bm:3"]);
+
+        manager.clear();
+        assert!(manager.bookmarks().is_empty());
+    }
+
+    #[test]
+    fn clones_are_shared() {
+        let manager = BookmarkManager::new();
+        let clone = manager.clone();
+        clone.update(&[], "bm:1");
+        assert_eq!(manager.bookmarks(), vec!["bm:1"]);
+    }
+}
@@ -5,109 +5,377 @@
 // License. To view a copy of this license, visit http://creativecommons.org/licenses/by-sa/3.0/ or send a letter to
 // Creative Commons, PO Box 1866, Mountain View, CA 94042, USA.
 
-use std::convert::TryInto;
+use std::collections::{HashMap, VecDeque};
+use std::convert::{TryFrom, TryInto};
+use std::future::Future;
+use std::io::Cursor;
+#[cfg(unix)]
+use std::path::Path;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use bytes::*;
 use tokio::io::BufStream;
+#[cfg(unix)]
+use tokio::net::UnixStream;
 use tokio::net::{TcpStream, ToSocketAddrs};
 use tokio::prelude::*;
+use tokio_rustls::client::TlsStream;
 use tokio_rustls::rustls::ClientConfig;
 use tokio_rustls::webpki::DNSNameRef;
-use tokio_rustls::{webpki, TlsConnector};
-use webpki_roots::TLS_SERVER_ROOTS;
+use tokio_rustls::TlsConnector;
 
-use bolt_proto::Message;
+use bolt_proto::message::Success;
+use bolt_proto::version::VersionRange;
+use bolt_proto::{Message, ProtocolVersion, Value};
 
 use crate::error::*;
 use crate::stream::Stream;
+use crate::tls::TlsOptions;
 
-mod v1;
+pub use self::pipeline::PipelineResponse;
+use self::state::Request;
+pub use self::state::ServerState;
+
+mod pipeline;
+mod state;
+pub(crate) mod v1;
 mod v2;
 mod v3;
 mod v4;
 
 const PREAMBLE: [u8; 4] = [0x60, 0x60, 0xB0, 0x17];
+const MAX_PROPOSED_VERSIONS: usize = 4;
 
 #[derive(Debug)]
 pub struct Client {
     pub(crate) stream: BufStream<Stream>,
-    pub(crate) version: Option<u32>,
+    pub(crate) version: Option<ProtocolVersion>,
+    state: ServerState,
+    // Requests that have been sent, but whose summary response has not been received yet
+    pending: VecDeque<Request>,
+    last_activity: Instant,
+    handshake_timeout: Option<Duration>,
+    read_timeout: Option<Duration>,
+    write_timeout: Option<Duration>,
 }
 
 impl Client {
     /// Create a new client pointing to the provided server address. If a server domain is provided, the Client will
-    /// attempt to connect to the server over a connection secured with TLS.
+    /// attempt to connect to the server over a connection secured with TLS, trusting the bundled root certificates.
+    /// Use [`Client::with_tls`](Client::with_tls) to customize the TLS configuration.
     pub async fn new(addr: impl ToSocketAddrs, domain: Option<&str>) -> Result<Self> {
-        let stream = match domain {
+        match domain {
+            Some(domain) => Client::with_tls(addr, domain, TlsOptions::new()).await,
+            None => Ok(Client::from_stream(Stream::Tcp(
+                TcpStream::connect(addr).await?,
+            ))),
+        }
+    }
+
+    /// Create a new client pointing to the provided server address, connecting over TLS with the given
+    /// configuration. The server's certificate is verified against the given domain. The configuration can be a
+    /// [`TlsOptions`](crate::tls::TlsOptions) or a rustls [`ClientConfig`](tokio_rustls::rustls::ClientConfig).
+    pub async fn with_tls(
+        addr: impl ToSocketAddrs,
+        domain: &str,
+        config: impl Into<Arc<ClientConfig>>,
+    ) -> Result<Self> {
+        let stream = connect_tls(addr, domain, config.into()).await?;
+        Ok(Client::from_stream(Stream::SecureTcp(Box::new(stream))))
+    }
+
+    /// Create a new client pointing to the provided server address, communicating over a WebSocket that carries Bolt
+    /// chunks in binary frames. If a server domain is provided, the WebSocket will be secured with TLS, trusting the
+    /// bundled root certificates. Requires the `websocket` feature.
+    #[cfg(feature = "websocket")]
+    pub async fn connect_websocket(addr: &str, domain: Option<&str>) -> Result<Self> {
+        match domain {
             Some(domain) => {
-                let tls_connector = Client::configure_tls_connector(&TLS_SERVER_ROOTS);
-                let dns_name_ref = DNSNameRef::try_from_ascii_str(&domain)
-                    .map_err(|_| Error::InvalidDNSName(domain.to_string()))?;
+                Client::connect_websocket_with_tls(addr, domain, TlsOptions::new()).await
+            }
+            None => {
                 let stream = TcpStream::connect(addr).await?;
-                Stream::SecureTcp(Box::new(tls_connector.connect(dns_name_ref, stream).await?))
+                crate::websocket::connect(&format!("ws://{}/", addr), stream).await
             }
-            None => Stream::Tcp(TcpStream::connect(addr).await?),
-        };
-        Ok(Client {
+        }
+    }
+
+    /// Create a new client pointing to the provided server address, communicating over a WebSocket secured with TLS
+    /// using the given configuration. Requires the `websocket` feature.
+    #[cfg(feature = "websocket")]
+    pub async fn connect_websocket_with_tls(
+        addr: &str,
+        domain: &str,
+        config: impl Into<Arc<ClientConfig>>,
+    ) -> Result<Self> {
+        let stream = connect_tls(addr, domain, config.into()).await?;
+        crate::websocket::connect(&format!("wss://{}/", addr), stream).await
+    }
+
+    /// Create a new client connected to the Unix domain socket at the given path.
+    #[cfg(unix)]
+    pub async fn connect_unix(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(Client::from_stream(Stream::Unix(
+            UnixStream::connect(path).await?,
+        )))
+    }
+
+    /// Create a new client that communicates with the server over the given transport, such as an in-memory stream
+    /// or a connection established by other means. Any security the transport needs must already be in place.
+    pub fn from_transport(transport: impl AsyncRead + AsyncWrite + Unpin + Send + 'static) -> Self {
+        Client::from_stream(Stream::Custom(Box::new(transport)))
+    }
+
+    fn from_stream(stream: Stream) -> Self {
+        Client {
             stream: BufStream::new(stream),
             version: None,
-        })
+            state: ServerState::Connected,
+            pending: VecDeque::new(),
+            last_activity: Instant::now(),
+            handshake_timeout: None,
+            read_timeout: None,
+            write_timeout: None,
+        }
+    }
+
+    /// Get the protocol version negotiated by this client, or `None` if no handshake has been performed yet.
+    pub fn version(&self) -> Option<ProtocolVersion> {
+        self.version
+    }
+
+    /// Get the current state of the server's side of the connection, based on the responses received so far. A client
+    /// whose connection failed or was closed is [`Defunct`](ServerState::Defunct), and can no longer be used.
+    pub fn server_state(&self) -> ServerState {
+        self.state
+    }
+
+    /// Whether the connection has failed or been closed. A defunct client can no longer be used.
+    pub fn is_defunct(&self) -> bool {
+        self.state == ServerState::Defunct
+    }
+
+    /// Whether the connection has unfinished work, and so can't be reused for an unrelated purpose without being reset
+    /// first. This is the case while responses are pending, a result or transaction is open, or a failure hasn't been
+    /// acknowledged.
+    pub fn is_dirty(&self) -> bool {
+        !self.pending.is_empty() || self.state != ServerState::Ready
     }
 
-    /// Get the current version of this client.
-    pub fn version(&self) -> &Option<u32> {
-        &self.version
+    /// Get the time elapsed since a message was last sent or received by this client.
+    pub fn idle_time(&self) -> Duration {
+        self.last_activity.elapsed()
     }
 
-    fn configure_tls_connector(root_certs: &webpki::TLSServerTrustAnchors) -> TlsConnector {
-        let mut config = ClientConfig::new();
-        config.root_store.add_server_trust_anchors(root_certs);
-        TlsConnector::from(Arc::new(config))
+    /// Set the maximum time to wait for the server's response to the handshake. If no response arrives in time, the
+    /// handshake fails with [`Error::Timeout`](crate::error::Error::Timeout) and the client becomes defunct. By
+    /// default, there is no limit.
+    pub fn set_handshake_timeout(&mut self, timeout: Option<Duration>) {
+        self.handshake_timeout = timeout;
+    }
+
+    /// Set the maximum time to wait for each message from the server. If a message doesn't arrive in time, the
+    /// operation fails with [`Error::Timeout`](crate::error::Error::Timeout) and the client becomes defunct. By
+    /// default, there is no limit.
+    ///
+    /// Servers using Bolt v4.3+ may suggest a read timeout when the connection is initialized, which is used instead
+    /// if it is shorter.
+    pub fn set_read_timeout(&mut self, timeout: Option<Duration>) {
+        self.read_timeout = timeout;
+    }
+
+    /// Set the maximum time to wait for each message to be sent to the server. If a message can't be sent in time,
+    /// the operation fails with [`Error::Timeout`](crate::error::Error::Timeout) and the client becomes defunct. By
+    /// default, there is no limit.
+    pub fn set_write_timeout(&mut self, timeout: Option<Duration>) {
+        self.write_timeout = timeout;
+    }
+
+    pub fn handshake_timeout(&self) -> Option<Duration> {
+        self.handshake_timeout
+    }
+
+    pub fn read_timeout(&self) -> Option<Duration> {
+        self.read_timeout
+    }
+
+    pub fn write_timeout(&self) -> Option<Duration> {
+        self.write_timeout
+    }
+
+    // Apply the connection hints sent by Bolt v4.3+ servers in response to HELLO
+    pub(crate) fn apply_hints(&mut self, success: &Success) {
+        if self.version < Some(ProtocolVersion::new(4, 3)) {
+            return;
+        }
+        let hints: HashMap<String, Value> = match success.metadata().get("hints") {
+            Some(hints) => match hints.clone().try_into() {
+                Ok(hints) => hints,
+                Err(_) => return,
+            },
+            None => return,
+        };
+        // The server expects the connection to be considered broken if nothing is received for this long
+        if let Some(Ok(seconds)) = hints
+            .get("connection.recv_timeout_seconds")
+            .map(|seconds| i64::try_from(seconds.clone()))
+        {
+            if seconds > 0 {
+                let hint = Duration::from_secs(seconds as u64);
+                self.read_timeout =
+                    Some(self.read_timeout.map_or(hint, |timeout| timeout.min(hint)));
+            }
+        }
+    }
+
+    // The state the server will be in once it has successfully processed all pending requests
+    fn expected_state(&self) -> ServerState {
+        let success = Message::Success(Success::new(HashMap::new()));
+        self.pending
+            .iter()
+            .fold(self.state, |state, &request| state.after(request, &success))
+    }
+
+    // Check that the server will accept the given message, and track it as a pending request if so
+    fn track_request(&mut self, message: &Message) -> Result<()> {
+        let request = match Request::of(message) {
+            Some(request) => request,
+            None => return Ok(()),
+        };
+        let state = self.expected_state();
+        if !state.allows(request, self.version.map_or(0, |v| v.major())) {
+            return Err(Error::InvalidState {
+                request: request.name(),
+                state,
+            });
+        }
+        match request {
+            // No response is sent for GOODBYE
+            Request::Goodbye => self.state = ServerState::Defunct,
+            // The server interrupts pending requests as soon as it receives a RESET
+            Request::Reset => {
+                self.state = ServerState::Interrupted;
+                self.pending.push_back(request);
+            }
+            _ => self.pending.push_back(request),
+        }
+        Ok(())
     }
 
-    /// Perform a handshake with the Bolt server and agree upon a protocol version to use for the client.
-    pub async fn handshake(&mut self, supported_versions: &[u32; 4]) -> Result<()> {
+    fn track_response(&mut self, message: &Message) {
+        if let Message::Record(_) = message {
+            return;
+        }
+        if let Some(request) = self.pending.pop_front() {
+            self.state = self.state.after(request, message);
+        }
+    }
+
+    // The connection can't be used after an I/O error, since we can't tell how much of a message was transferred
+    fn check_io<T>(&mut self, result: Result<T>) -> Result<T> {
+        if result.is_err() {
+            self.state = ServerState::Defunct;
+            self.pending.clear();
+        }
+        result
+    }
+
+    /// Perform a handshake with the Bolt server and agree upon a protocol version to use for the client. Up to four
+    /// version proposals may be given, in order of preference. Each proposal may cover a range of minor versions, see
+    /// [`VersionRange`](bolt_proto::version::VersionRange) for details.
+    pub async fn handshake(&mut self, preferred_versions: &[VersionRange]) -> Result<()> {
+        if preferred_versions.len() > MAX_PROPOSED_VERSIONS {
+            return Err(Error::TooManyVersions(preferred_versions.len()));
+        }
+        let result = with_timeout(
+            self.handshake_timeout,
+            self.perform_handshake(preferred_versions),
+        )
+        .await;
+        self.check_io(result)
+    }
+
+    async fn perform_handshake(&mut self, preferred_versions: &[VersionRange]) -> Result<()> {
         let mut allowed_versions = BytesMut::with_capacity(16);
-        supported_versions
+        preferred_versions
             .iter()
-            .for_each(|&v| allowed_versions.put_u32(v));
-        self.stream.write(&PREAMBLE).await?;
-        self.stream.write_buf(&mut allowed_versions).await?;
+            .for_each(|&v| allowed_versions.put_u32(v.into()));
+        // Unused proposals are filled with zeros
+        allowed_versions.resize(16, 0);
+        self.stream.write_all(&PREAMBLE).await?;
+        self.stream.write_all(&allowed_versions).await?;
         self.stream.flush().await?;
 
-        let version = self.stream.read_u32().await?;
-        if supported_versions.contains(&version) && version > 0 {
-            self.version = Some(version);
-            Ok(())
-        } else {
-            Err(Error::HandshakeFailed)
+        let response = self.stream.read_u32().await?;
+        match ProtocolVersion::try_from(response) {
+            Ok(version) if preferred_versions.iter().any(|v| v.contains(version)) => {
+                self.version = Some(version);
+                Ok(())
+            }
+            _ => Err(Error::HandshakeFailed),
         }
     }
 
     pub(crate) async fn read_message(&mut self) -> Result<Message> {
-        let message = Message::from_stream(&mut self.stream).await?;
+        let result = self.read_next_message().await;
+        let message = self.check_io(result)?;
 
         #[cfg(test)]
         println!("<<< {:?}\n", message);
 
+        self.track_response(&message);
+        self.last_activity = Instant::now();
         Ok(message)
     }
 
+    async fn read_next_message(&mut self) -> Result<Message> {
+        // Bolt v4.1+ servers may send empty chunks (NOOPs) between messages to keep the connection alive, so the read
+        // timeout applies to each chunk header rather than to the whole wait for a message
+        let header = loop {
+            let header = with_timeout(self.read_timeout, async {
+                // Reported like any other failure to read a message
+                let header = self.stream.read_u16().await;
+                Ok(header.map_err(bolt_proto::error::Error::from)?)
+            })
+            .await?;
+            if header != 0 {
+                break header;
+            }
+            self.last_activity = Instant::now();
+        };
+        let mut stream = Cursor::new(header.to_be_bytes()).chain(&mut self.stream);
+        with_timeout(self.read_timeout, async {
+            Ok(Message::from_stream(&mut stream).await?)
+        })
+        .await
+    }
+
     pub(crate) async fn send_message(&mut self, message: Message) -> Result<()> {
         #[cfg(test)]
         println!(">>> {:?}", message);
 
+        self.track_request(&message)?;
         let chunks: Vec<Bytes> = message.try_into()?;
-        for mut chunk in chunks {
-            self.stream.write_buf(&mut chunk).await?;
-        }
-        self.stream.flush().await?;
-        Ok(())
+        let result = self.write_chunks(chunks).await;
+        self.last_activity = Instant::now();
+        self.check_io(result)
     }
 
-    /// Send multiple messages to the server without waiting for a response. Returns a Vec containing the server's
-    /// response messages for each of the sent messages, in the order they were provided.
+    async fn write_chunks(&mut self, chunks: Vec<Bytes>) -> Result<()> {
+        with_timeout(self.write_timeout, async {
+            for chunk in chunks {
+                self.stream.write_all(&chunk).await?;
+            }
+            self.stream.flush().await?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Send multiple messages to the server without waiting for a response, then collect the server's responses.
+    /// Returns a [`PipelineResponse`](PipelineResponse) for each message except a trailing `GOODBYE`, in the order the
+    /// messages were provided.
     ///
     /// # Description
     /// The client is not required to wait for a response before sending more messages. Sending multiple messages
@@ -126,30 +394,192 @@ impl Client {
     /// acknowledge the `FAILURE` message by sending a `RESET` (Bolt v3+) or `ACK_FAILURE` (Bolt v1-2) message to the
     /// server. Until the server receives the `RESET`/`ACK_FAILURE` message, it will send an `IGNORED` message in
     /// response to any other message from the client, including messages that were sent in a pipeline.
-    // TODO: Test pipelining on v4
-    pub async fn pipeline(&mut self, messages: Vec<Message>) -> Result<Vec<Message>> {
-        // This Vec is too small if we're expecting some RECORD messages, so there's no "good" size
-        let mut responses = Vec::with_capacity(messages.len());
-
-        for message in messages {
+    ///
+    /// # Response
+    /// A [`PipelineResponse`](PipelineResponse) for each message, in the order the messages were sent, pairing the
+    /// message with the records and summary message sent in response. Requests that were `IGNORED` after an earlier
+    /// request in the pipeline failed refer back to the failed request. Since no response is sent for `GOODBYE`, it
+    /// can only be the last message in a pipeline, and has no corresponding `PipelineResponse`.
+    pub async fn pipeline(&mut self, messages: Vec<Message>) -> Result<Vec<PipelineResponse>> {
+        // Check the whole pipeline before sending any of it
+        let (state, pending) = (self.state, self.pending.clone());
+        let mut chunks: Vec<Bytes> = Vec::new();
+        for message in &messages {
             #[cfg(test)]
             println!(">>> {:?}", message);
 
-            let chunks: Vec<Bytes> = message.try_into()?;
-            for mut chunk in chunks {
-                self.stream.write_buf(&mut chunk).await?;
+            let result = self.track_request(message).and_then(|_| {
+                let message_chunks: Vec<Bytes> = message.clone().try_into()?;
+                Ok(message_chunks)
+            });
+            match result {
+                Ok(message_chunks) => chunks.extend(message_chunks),
+                Err(error) => {
+                    self.state = state;
+                    self.pending = pending;
+                    return Err(error);
+                }
             }
         }
-        self.stream.flush().await?;
+        let result = self.write_chunks(chunks).await;
+        self.last_activity = Instant::now();
+        self.check_io(result)?;
 
-        for _ in 0..responses.capacity() {
-            let mut response = self.read_message().await?;
-            while let Message::Record(_) = response {
-                responses.push(response);
-                response = self.read_message().await?;
+        let mut responses = Vec::with_capacity(messages.len());
+        // The request whose failure the server is currently ignoring requests for, if it was in this pipeline
+        let mut failed_request = None;
+        for (index, request) in messages.into_iter().enumerate() {
+            if request == Message::Goodbye {
+                break;
             }
-            responses.push(response);
+            let mut records = vec![];
+            let summary = loop {
+                match self.read_message().await? {
+                    Message::Record(record) => records.push(record),
+                    other => break other,
+                }
+            };
+            let cause = match summary {
+                Message::Failure(_) => {
+                    failed_request = Some(index);
+                    None
+                }
+                Message::Ignored => failed_request,
+                _ => {
+                    failed_request = None;
+                    None
+                }
+            };
+            responses.push(PipelineResponse::new(request, records, summary, cause));
         }
         Ok(responses)
     }
 }
+
+// Run the future, failing with Error::Timeout if it doesn't complete within the timeout (if any)
+pub(crate) async fn with_timeout<T>(
+    timeout: Option<Duration>,
+    future: impl Future<Output = Result<T>>,
+) -> Result<T> {
+    match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, future)
+            .await
+            .map_err(|_| Error::Timeout)?,
+        None => future.await,
+    }
+}
+
+// Open a TCP connection and secure it with TLS, verifying the server's certificate against the given domain
+async fn connect_tls(
+    addr: impl ToSocketAddrs,
+    domain: &str,
+    config: Arc<ClientConfig>,
+) -> Result<TlsStream<TcpStream>> {
+    let tls_connector = TlsConnector::from(config);
+    let dns_name_ref = DNSNameRef::try_from_ascii_str(domain)
+        .map_err(|_| Error::InvalidDNSName(domain.to_string()))?;
+    let stream = TcpStream::connect(addr).await?;
+    Ok(tls_connector.connect(dns_name_ref, stream).await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use bolt_proto::message::*;
+    use bolt_test_support::MockServer;
+    use tokio::net::TcpListener;
+    #[cfg(unix)]
+    use tokio::net::UnixListener;
+
+    use crate::client::v1::tests::*;
+
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_millis(100);
+
+    // Connections to the listener are queued by the OS, but nothing is ever read or written
+    async fn unresponsive_server() -> (TcpListener, String) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        (listener, address)
+    }
+
+    #[tokio::test]
+    async fn handshake_timeout() {
+        let (_listener, address) = unresponsive_server().await;
+        let mut client = Client::new(address, None).await.unwrap();
+        client.set_handshake_timeout(Some(TIMEOUT));
+        assert!(matches!(
+            client
+                .handshake(&[VersionRange::exact(ProtocolVersion::new(4, 0))])
+                .await,
+            Err(Error::Timeout)
+        ));
+        assert!(client.is_defunct());
+    }
+
+    #[tokio::test]
+    async fn read_timeout() {
+        // The server never responds to the query
+        let script = initialized(3).expect(run_with_metadata_msg("RETURN 1;"), vec![]);
+        let server = MockServer::start(script).await;
+        let mut client = get_initialized_client(&server, 3).await.unwrap();
+        client.set_read_timeout(Some(TIMEOUT));
+        assert!(matches!(
+            client
+                .run_with_metadata("RETURN 1;".to_string(), None, None)
+                .await,
+            Err(Error::Timeout)
+        ));
+        assert!(client.is_defunct());
+    }
+
+    #[tokio::test]
+    async fn write_timeout() {
+        // The message is far larger than the socket buffers, so it can't be written without the server reading it
+        let (_listener, address) = unresponsive_server().await;
+        let mut client = Client::new(address, None).await.unwrap();
+        client.set_write_timeout(Some(TIMEOUT));
+        let init = Message::Init(Init::new("a".repeat(32 << 20), HashMap::new()));
+        assert!(matches!(
+            client.pipeline(vec![init]).await,
+            Err(Error::Timeout)
+        ));
+        assert!(client.is_defunct());
+    }
+
+    #[tokio::test]
+    async fn custom_transport() {
+        let server = MockServer::start(initialized(3)).await;
+        let stream = TcpStream::connect(server.address()).await.unwrap();
+        let mut client = Client::from_transport(stream);
+        client
+            .handshake(&[VersionRange::exact(ProtocolVersion::new(3, 0))])
+            .await
+            .unwrap();
+        let response = initialize_client(&mut client, true).await.unwrap();
+        assert!(Success::try_from(response).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn unix_socket() {
+        let path = std::env::temp_dir().join(format!("bolt-client-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut listener = UnixListener::bind(&path).unwrap();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            initialized(3).play(stream).await
+        });
+
+        let mut client = Client::connect_unix(&path).await.unwrap();
+        client
+            .handshake(&[VersionRange::exact(ProtocolVersion::new(3, 0))])
+            .await
+            .unwrap();
+        let response = initialize_client(&mut client, true).await.unwrap();
+        assert!(Success::try_from(response).is_ok());
+        drop(client);
+        server.await.unwrap().unwrap();
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,76 @@
+use bolt_proto::message::{Failure, Record, Success};
+use bolt_proto::Message;
+
+/// The server's response to one of the requests sent with [`Client::pipeline`](crate::Client::pipeline), consisting
+/// of any records returned for the request followed by a summary message (`SUCCESS`, `FAILURE`, or `IGNORED`).
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PipelineResponse {
+    request: Message,
+    records: Vec<Record>,
+    summary: Message,
+    failed_request: Option<usize>,
+}
+
+impl PipelineResponse {
+    pub(crate) fn new(
+        request: Message,
+        records: Vec<Record>,
+        summary: Message,
+        failed_request: Option<usize>,
+    ) -> Self {
+        Self {
+            request,
+            records,
+            summary,
+            failed_request,
+        }
+    }
+
+    /// Get the request this is a response to.
+    pub fn request(&self) -> &Message {
+        &self.request
+    }
+
+    /// Get the records returned for the request, if any.
+    pub fn records(&self) -> &[Record] {
+        &self.records
+    }
+
+    /// Get the summary message that ended the response.
+    pub fn summary(&self) -> &Message {
+        &self.summary
+    }
+
+    /// Get the summary message if the request succeeded.
+    pub fn success(&self) -> Option<&Success> {
+        match &self.summary {
+            Message::Success(success) => Some(success),
+            _ => None,
+        }
+    }
+
+    /// Get the summary message if the request failed.
+    pub fn failure(&self) -> Option<&Failure> {
+        match &self.summary {
+            Message::Failure(failure) => Some(failure),
+            _ => None,
+        }
+    }
+
+    /// Whether the server ignored the request, because an earlier request failed or the connection was reset.
+    pub fn is_ignored(&self) -> bool {
+        self.summary == Message::Ignored
+    }
+
+    /// If the request was ignored because an earlier request in the same pipeline failed, get the index of the
+    /// failed request. Requests are ignored until the failure is acknowledged with `RESET` or `ACK_FAILURE`, so this
+    /// points to the same request for every response in the cascade.
+    pub fn failed_request(&self) -> Option<usize> {
+        self.failed_request
+    }
+
+    /// Split the response into the request, the records, and the summary message.
+    pub fn into_parts(self) -> (Message, Vec<Record>, Message) {
+        (self.request, self.records, self.summary)
+    }
+}
@@ -0,0 +1,233 @@
+use std::fmt;
+
+use bolt_proto::{Message, Value};
+
+/// The state of the server's side of a connection, as tracked by the client.
+///
+/// The client updates its state as it sends requests and receives their responses, and refuses to send requests that
+/// are not allowed in the state the server will be in when it receives them.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum ServerState {
+    /// The connection has been opened, but has not been initialized with `INIT` or `HELLO` yet.
+    Connected,
+    /// The connection is ready to accept a new query or transaction.
+    Ready,
+    /// A result is available to be pulled or discarded.
+    Streaming,
+    /// An explicit transaction has been started (Bolt v3+).
+    TxReady,
+    /// A result is available to be pulled or discarded inside an explicit transaction (Bolt v3+).
+    TxStreaming,
+    /// A request failed, and further requests will be `IGNORED` until the failure is acknowledged or the connection
+    /// is reset.
+    Failed,
+    /// A `RESET` has been sent, and requests sent before it will be `IGNORED`.
+    Interrupted,
+    /// The connection has been closed or is no longer usable.
+    Defunct,
+}
+
+impl fmt::Display for ServerState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ServerState::Connected => "CONNECTED",
+            ServerState::Ready => "READY",
+            ServerState::Streaming => "STREAMING",
+            ServerState::TxReady => "TX_READY",
+            ServerState::TxStreaming => "TX_STREAMING",
+            ServerState::Failed => "FAILED",
+            ServerState::Interrupted => "INTERRUPTED",
+            ServerState::Defunct => "DEFUNCT",
+        };
+        f.write_str(name)
+    }
+}
+
+// The kinds of request that affect the server state
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum Request {
+    Init,
+    Run,
+    PullAll,
+    Pull,
+    Begin,
+    Commit,
+    Rollback,
+    Reset,
+    AckFailure,
+    Route,
+    Goodbye,
+}
+
+impl Request {
+    pub(crate) fn of(message: &Message) -> Option<Self> {
+        match message {
+            Message::Init(_) | Message::Hello(_) => Some(Request::Init),
+            Message::Run(_) | Message::RunWithMetadata(_) => Some(Request::Run),
+            Message::PullAll | Message::DiscardAll => Some(Request::PullAll),
+            Message::Pull(_) | Message::Discard(_) => Some(Request::Pull),
+            Message::Begin(_) => Some(Request::Begin),
+            Message::Commit => Some(Request::Commit),
+            Message::Rollback => Some(Request::Rollback),
+            Message::Reset => Some(Request::Reset),
+            Message::AckFailure => Some(Request::AckFailure),
+            Message::Route(_) | Message::RouteWithMetadata(_) => Some(Request::Route),
+            Message::Goodbye => Some(Request::Goodbye),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Request::Init => "INIT/HELLO",
+            Request::Run => "RUN",
+            Request::PullAll => "PULL_ALL/DISCARD_ALL",
+            Request::Pull => "PULL/DISCARD",
+            Request::Begin => "BEGIN",
+            Request::Commit => "COMMIT",
+            Request::Rollback => "ROLLBACK",
+            Request::Reset => "RESET",
+            Request::AckFailure => "ACK_FAILURE",
+            Request::Route => "ROUTE",
+            Request::Goodbye => "GOODBYE",
+        }
+    }
+}
+
+impl ServerState {
+    // Whether the server accepts the given request in this state. Requests sent in the FAILED and INTERRUPTED states
+    // are allowed, since the server will simply ignore them.
+    pub(crate) fn allows(self, request: Request, major_version: u8) -> bool {
+        use Request::*;
+        use ServerState::*;
+
+        match (self, request) {
+            (Defunct, _) => false,
+            (_, Goodbye) => true,
+            (Connected, Init) => true,
+            (Connected, _) | (_, Init) => false,
+            (Failed, _) | (Interrupted, _) | (_, Reset) => true,
+            (Ready, Run) | (Ready, Begin) | (Ready, Route) | (Ready, AckFailure) => true,
+            (Streaming, PullAll) | (Streaming, Pull) => true,
+            (TxReady, Run) | (TxReady, Commit) | (TxReady, Rollback) => true,
+            (TxStreaming, PullAll) | (TxStreaming, Pull) => true,
+            // Bolt v4 allows multiple results to be open in a transaction
+            (TxStreaming, Run) => major_version >= 4,
+            _ => false,
+        }
+    }
+
+    // The state after the server responds to the given request with the given summary message.
+    pub(crate) fn after(self, request: Request, response: &Message) -> Self {
+        use Request::*;
+        use ServerState::*;
+
+        match (self, request, response) {
+            (Defunct, _, _) => Defunct,
+            (_, Goodbye, _) => Defunct,
+            (Interrupted, Reset, Message::Success(_)) => Ready,
+            (_, Reset, Message::Failure(_)) | (_, Init, Message::Failure(_)) => Defunct,
+            (Interrupted, _, _) => Interrupted,
+            (_, _, Message::Failure(_)) => Failed,
+            (state, _, Message::Success(success)) => match (state, request) {
+                (_, Init) | (_, Reset) | (_, AckFailure) | (_, Commit) | (_, Rollback) => Ready,
+                (Ready, Run) => Streaming,
+                (TxReady, Run) | (TxStreaming, Run) => TxStreaming,
+                (Ready, Begin) => TxReady,
+                (Streaming, Pull) | (TxStreaming, Pull)
+                    if success.metadata().get("has_more") == Some(&Value::from(true)) =>
+                {
+                    state
+                }
+                (Streaming, PullAll) | (Streaming, Pull) => Ready,
+                (TxStreaming, PullAll) | (TxStreaming, Pull) => TxReady,
+                (state, _) => state,
+            },
+            // IGNORED leaves the state unchanged
+            (state, _, _) => state,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::iter::FromIterator;
+
+    use bolt_proto::message::{Failure, Success};
+
+    use super::Request::*;
+    use super::ServerState::*;
+    use super::*;
+
+    fn success() -> Message {
+        Message::Success(Success::new(HashMap::new()))
+    }
+
+    fn has_more() -> Message {
+        Message::Success(Success::new(HashMap::from_iter(vec![(
+            "has_more".to_string(),
+            Value::from(true),
+        )])))
+    }
+
+    fn failure() -> Message {
+        Message::Failure(Failure::new(HashMap::new()))
+    }
+
+    #[test]
+    fn auto_commit() {
+        assert_eq!(Connected.after(Init, &success()), Ready);
+        assert_eq!(Ready.after(Run, &success()), Streaming);
+        assert_eq!(Streaming.after(Pull, &has_more()), Streaming);
+        assert_eq!(Streaming.after(Pull, &success()), Ready);
+        assert_eq!(Streaming.after(PullAll, &has_more()), Ready);
+    }
+
+    #[test]
+    fn explicit_transaction() {
+        assert_eq!(Ready.after(Begin, &success()), TxReady);
+        assert_eq!(TxReady.after(Run, &success()), TxStreaming);
+        assert_eq!(TxStreaming.after(Pull, &has_more()), TxStreaming);
+        assert_eq!(TxStreaming.after(Pull, &success()), TxReady);
+        assert_eq!(TxReady.after(Commit, &success()), Ready);
+        assert_eq!(TxReady.after(Rollback, &success()), Ready);
+    }
+
+    #[test]
+    fn failures() {
+        assert_eq!(Connected.after(Init, &failure()), Defunct);
+        assert_eq!(Ready.after(Run, &failure()), Failed);
+        assert_eq!(TxStreaming.after(Pull, &failure()), Failed);
+        assert_eq!(Failed.after(Run, &Message::Ignored), Failed);
+        assert_eq!(Failed.after(AckFailure, &success()), Ready);
+        assert_eq!(Failed.after(Reset, &failure()), Defunct);
+    }
+
+    #[test]
+    fn interrupted() {
+        assert_eq!(Interrupted.after(Run, &Message::Ignored), Interrupted);
+        assert_eq!(Interrupted.after(Run, &success()), Interrupted);
+        assert_eq!(Interrupted.after(Reset, &success()), Ready);
+    }
+
+    #[test]
+    fn allowed_requests() {
+        assert!(Connected.allows(Init, 4));
+        assert!(!Connected.allows(Run, 4));
+        assert!(!Ready.allows(Init, 4));
+        assert!(!Ready.allows(Pull, 4));
+        assert!(!Ready.allows(Commit, 4));
+        assert!(!Streaming.allows(Run, 4));
+        assert!(!Streaming.allows(Begin, 4));
+        assert!(Streaming.allows(Reset, 4));
+        assert!(!TxReady.allows(Begin, 4));
+        assert!(TxStreaming.allows(Run, 4));
+        assert!(!TxStreaming.allows(Run, 3));
+        assert!(!TxStreaming.allows(Commit, 4));
+        assert!(Failed.allows(Run, 4));
+        assert!(Interrupted.allows(Pull, 4));
+        assert!(!Defunct.allows(Goodbye, 4));
+        assert!(!Defunct.allows(Reset, 4));
+    }
+}
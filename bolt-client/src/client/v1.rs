@@ -5,7 +5,7 @@ use bolt_proto::message::*;
 use bolt_proto::{Message, Value};
 
 use crate::error::*;
-use crate::Client;
+use crate::{Client, RecordStream};
 
 impl Client {
     /// Send an `INIT` message to the server.
@@ -121,6 +121,17 @@ impl Client {
         }
     }
 
+    /// Send a `PULL_ALL` message to the server. Returns a [`RecordStream`](crate::RecordStream) that yields each record
+    /// as it is received, rather than collecting the entire result stream in memory before returning.
+    ///
+    /// The summary message (`SUCCESS` or `FAILURE`) is available from the stream once it has ended. See
+    /// [`pull_all`](Client::pull_all) for a description of the `PULL_ALL` message.
+    #[bolt_version(1, 2, 3)]
+    pub async fn pull_all_stream(&mut self) -> Result<RecordStream<'_>> {
+        self.send_message(Message::PullAll).await?;
+        Ok(RecordStream::new(self, None))
+    }
+
     /// Send an `ACK_FAILURE` message to the server.
     ///
     /// # Description
@@ -170,68 +181,179 @@ impl Client {
 
 #[cfg(test)]
 pub(crate) mod tests {
-    use std::collections::HashMap;
     use std::convert::TryFrom;
-    use std::env;
     use std::iter::FromIterator;
 
     use bolt_proto::message::*;
     use bolt_proto::value::*;
+    use bolt_proto::version::VersionRange;
+    use bolt_proto::ProtocolVersion;
+    use bolt_test_support::{MockServer, Script};
+    use tokio::stream::StreamExt;
 
-    use crate::skip_if_handshake_failed;
+    use crate::client::ServerState;
 
     use super::*;
 
-    pub(crate) async fn new_client(version: u32) -> Result<Client> {
-        let mut client = Client::new(
-            env::var("BOLT_TEST_ADDR").unwrap(),
-            env::var("BOLT_TEST_DOMAIN").ok().as_deref(),
+    pub(crate) const USER_AGENT: &str = "bolt-client/X.Y.Z";
+    pub(crate) const USERNAME: &str = "neo4j";
+    pub(crate) const PASSWORD: &str = "test";
+
+    pub(crate) fn success(metadata: Vec<(&str, Value)>) -> Message {
+        Message::Success(Success::new(
+            metadata
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v))
+                .collect(),
+        ))
+    }
+
+    pub(crate) fn failure(code: &str, message: &str) -> Message {
+        Message::Failure(Failure::new(HashMap::from_iter(vec![
+            ("code".to_string(), Value::from(code)),
+            ("message".to_string(), Value::from(message)),
+        ])))
+    }
+
+    pub(crate) fn record(fields: Vec<Value>) -> Message {
+        Message::Record(Record::new(fields))
+    }
+
+    pub(crate) fn run_msg(statement: &str) -> Message {
+        Message::Run(Run::new(statement.to_string(), HashMap::new()))
+    }
+
+    pub(crate) fn run_with_metadata_msg(statement: &str) -> Message {
+        Message::RunWithMetadata(RunWithMetadata::new(
+            statement.to_string(),
+            HashMap::new(),
+            HashMap::new(),
+        ))
+    }
+
+    // The INIT or HELLO message sent by `initialize_client`
+    pub(crate) fn init_msg(version: u8, succeed: bool) -> Message {
+        let password = if succeed { PASSWORD } else { "invalid" };
+        let auth_token = vec![
+            ("scheme".to_string(), Value::from("basic")),
+            ("principal".to_string(), Value::from(USERNAME)),
+            ("credentials".to_string(), Value::from(password)),
+        ];
+        if [1, 2].contains(&version) {
+            Message::Init(Init::new(
+                USER_AGENT.to_string(),
+                HashMap::from_iter(auth_token),
+            ))
+        } else {
+            let mut metadata = HashMap::from_iter(auth_token);
+            metadata.insert("user_agent".to_string(), Value::from(USER_AGENT));
+            Message::Hello(Hello::new(metadata))
+        }
+    }
+
+    // The RUN message sent by `run_invalid_query`
+    pub(crate) fn invalid_query(version: u8) -> Message {
+        if version > 2 {
+            Message::RunWithMetadata(RunWithMetadata::new(
+                "RETURN invalid query oof as n;".to_string(),
+                HashMap::from_iter(vec![("some_val".to_string(), Value::from(25.5432))]),
+                HashMap::from_iter(vec![("some_key".to_string(), Value::from(true))]),
+            ))
+        } else {
+            run_msg("")
+        }
+    }
+
+    // The RUN message sent by `run_valid_query`
+    pub(crate) fn valid_query(version: u8) -> Message {
+        if version > 2 {
+            Message::RunWithMetadata(RunWithMetadata::new(
+                "RETURN $some_val as n;".to_string(),
+                HashMap::from_iter(vec![("some_val".to_string(), Value::from(25.5432))]),
+                HashMap::from_iter(vec![("some_key".to_string(), Value::from(true))]),
+            ))
+        } else {
+            run_msg("RETURN 1 as n;")
+        }
+    }
+
+    pub(crate) fn fields_success() -> Message {
+        success(vec![("fields", Value::from(vec!["n"]))])
+    }
+
+    pub(crate) fn syntax_error() -> Message {
+        failure(
+            "Neo.ClientError.Statement.SyntaxError",
+            "Invalid input 'q': expected <init>",
+        )
+    }
+
+    // A script for a server that accepts the credentials sent by `initialize_client`
+    pub(crate) fn initialized(version: u8) -> Script {
+        Script::new(ProtocolVersion::new(version, 0)).expect(
+            init_msg(version, true),
+            vec![success(vec![("server", Value::from("Neo4j/4.0.0"))])],
         )
-        .await?;
-        client.handshake(&[version, 0, 0, 0]).await?;
+    }
+
+    // Expect each of the given messages in turn, replying with the corresponding responses
+    pub(crate) fn expect_all(
+        script: Script,
+        messages: &[Message],
+        responses: Vec<Vec<Message>>,
+    ) -> Script {
+        messages
+            .iter()
+            .cloned()
+            .zip(responses)
+            .fold(script, |script, (message, responses)| {
+                script.expect(message, responses)
+            })
+    }
+
+    pub(crate) async fn new_client(server: &MockServer, version: u8) -> Result<Client> {
+        let mut client = Client::new(server.address(), None).await?;
+        client
+            .handshake(&[VersionRange::exact(ProtocolVersion::new(version, 0))])
+            .await?;
         Ok(client)
     }
 
     pub(crate) async fn initialize_client(client: &mut Client, succeed: bool) -> Result<Message> {
-        let username = env::var("BOLT_TEST_USERNAME").unwrap();
-        let password = if succeed {
-            env::var("BOLT_TEST_PASSWORD").unwrap()
-        } else {
-            "invalid".to_string()
-        };
+        let password = if succeed { PASSWORD } else { "invalid" };
 
         let version = client.version.unwrap();
-        if [1_u32, 2_u32].contains(&version) {
+        if [1, 2].contains(&version.major()) {
             client
                 .init(
-                    "bolt-client/X.Y.Z".to_string(),
+                    USER_AGENT.to_string(),
                     HashMap::from_iter(vec![
                         ("scheme".to_string(), "basic".to_string()),
-                        ("principal".to_string(), username),
-                        ("credentials".to_string(), password),
+                        ("principal".to_string(), USERNAME.to_string()),
+                        ("credentials".to_string(), password.to_string()),
                     ]),
                 )
                 .await
         } else {
             client
                 .hello(HashMap::from_iter(vec![
-                    ("user_agent".to_string(), "bolt-client/X.Y.Z".to_string()),
+                    ("user_agent".to_string(), USER_AGENT.to_string()),
                     ("scheme".to_string(), "basic".to_string()),
-                    ("principal".to_string(), username),
-                    ("credentials".to_string(), password),
+                    ("principal".to_string(), USERNAME.to_string()),
+                    ("credentials".to_string(), password.to_string()),
                 ]))
                 .await
         }
     }
 
-    pub(crate) async fn get_initialized_client(version: u32) -> Result<Client> {
-        let mut client = new_client(version).await?;
+    pub(crate) async fn get_initialized_client(server: &MockServer, version: u8) -> Result<Client> {
+        let mut client = new_client(server, version).await?;
         initialize_client(&mut client, true).await?;
         Ok(client)
     }
 
     pub(crate) async fn run_invalid_query(client: &mut Client) -> Result<Message> {
-        if client.version.unwrap() > 2 {
+        if client.version.unwrap().major() > 2 {
             client
                 .run_with_metadata(
                     "RETURN invalid query oof as n;".to_string(),
@@ -251,7 +373,7 @@ pub(crate) mod tests {
     }
 
     pub(crate) async fn run_valid_query(client: &mut Client) -> Result<Message> {
-        if client.version.unwrap() > 2 {
+        if client.version.unwrap().major() > 2 {
             client
                 .run_with_metadata(
                     "RETURN $some_val as n;".to_string(),
@@ -272,36 +394,48 @@ pub(crate) mod tests {
 
     #[tokio::test]
     async fn init() {
-        let client = new_client(1).await;
-        skip_if_handshake_failed!(client);
-        let mut client = client.unwrap();
+        let server = MockServer::start(initialized(1)).await;
+        let mut client = new_client(&server, 1).await.unwrap();
         let response = initialize_client(&mut client, true).await.unwrap();
         assert!(Success::try_from(response).is_ok());
     }
 
     #[tokio::test]
     async fn init_fail() {
-        let client = new_client(1).await;
-        skip_if_handshake_failed!(client);
-        let mut client = client.unwrap();
+        let script = Script::new(ProtocolVersion::new(1, 0))
+            .expect(
+                init_msg(1, false),
+                vec![failure(
+                    "Neo.ClientError.Security.Unauthorized",
+                    "The client is unauthorized due to authentication failure.",
+                )],
+            )
+            .close();
+        let server = MockServer::start(script).await;
+        let mut client = new_client(&server, 1).await.unwrap();
         let response = initialize_client(&mut client, false).await.unwrap();
         assert!(Failure::try_from(response).is_ok());
 
         // See https://github.com/neo4j/neo4j/pull/8050.
-        // The current behavior is to simply close the connection on a failed INIT.
-        // Messages now fail to send since connection was closed
-        let response = initialize_client(&mut client, true).await;
-        assert!(match response {
-            Err(Error::ProtocolError(bolt_proto::error::Error::IOError(_))) => true,
-            _ => false,
-        })
+        // The current behavior is to simply close the connection on a failed INIT, so the client is now defunct
+        assert_eq!(client.server_state(), ServerState::Defunct);
+        assert!(matches!(
+            initialize_client(&mut client, true).await,
+            Err(Error::InvalidState {
+                request: "INIT/HELLO",
+                state: ServerState::Defunct,
+            })
+        ));
     }
 
     #[tokio::test]
     async fn ack_failure() {
-        let client = get_initialized_client(1).await;
-        skip_if_handshake_failed!(client);
-        let mut client = client.unwrap();
+        let script = initialized(1)
+            .expect(invalid_query(1), vec![syntax_error()])
+            .expect(Message::AckFailure, vec![success(vec![])])
+            .expect(valid_query(1), vec![fields_success()]);
+        let server = MockServer::start(script).await;
+        let mut client = get_initialized_client(&server, 1).await.unwrap();
         let response = run_invalid_query(&mut client).await.unwrap();
         assert!(Failure::try_from(response).is_ok());
         let response = client.ack_failure().await.unwrap();
@@ -312,9 +446,13 @@ pub(crate) mod tests {
 
     #[tokio::test]
     async fn ack_failure_after_ignored() {
-        let client = get_initialized_client(1).await;
-        skip_if_handshake_failed!(client);
-        let mut client = client.unwrap();
+        let script = initialized(1)
+            .expect(invalid_query(1), vec![syntax_error()])
+            .expect(valid_query(1), vec![Message::Ignored])
+            .expect(Message::AckFailure, vec![success(vec![])])
+            .expect(valid_query(1), vec![fields_success()]);
+        let server = MockServer::start(script).await;
+        let mut client = get_initialized_client(&server, 1).await.unwrap();
         let response = run_invalid_query(&mut client).await.unwrap();
         assert!(Failure::try_from(response).is_ok());
         let response = run_valid_query(&mut client).await.unwrap();
@@ -330,18 +468,15 @@ pub(crate) mod tests {
 
     #[tokio::test]
     async fn run() {
-        let client = get_initialized_client(1).await;
-        skip_if_handshake_failed!(client);
-        let mut client = client.unwrap();
+        let script = initialized(1).expect(valid_query(1), vec![fields_success()]);
+        let server = MockServer::start(script).await;
+        let mut client = get_initialized_client(&server, 1).await.unwrap();
         let response = run_valid_query(&mut client).await.unwrap();
         assert!(Success::try_from(response).is_ok());
     }
 
     #[tokio::test]
     async fn run_pipelined() {
-        let client = get_initialized_client(1).await;
-        skip_if_handshake_failed!(client);
-        let mut client = client.unwrap();
         let messages = vec![
             Message::Run(Run::new("MATCH (n {test: 'v1-pipelined'}) DETACH DELETE n;".to_string(), Default::default())),
             Message::PullAll,
@@ -356,26 +491,40 @@ pub(crate) mod tests {
                 Default::default())),
             Message::PullAll,
         ];
-        for response in client.pipeline(messages).await.unwrap() {
-            assert!(match response {
-                Message::Success(_) => true,
-                Message::Record(record) => {
-                    assert_eq!(
-                        Record::try_from(record).unwrap().fields()[0],
-                        Value::from(12_i8)
-                    );
-                    true
-                }
-                _ => false,
-            });
-        }
+        let script = expect_all(
+            initialized(1),
+            &messages,
+            vec![
+                vec![success(vec![])],
+                vec![success(vec![])],
+                vec![success(vec![])],
+                vec![success(vec![])],
+                vec![success(vec![])],
+                vec![success(vec![])],
+                vec![fields_success()],
+                vec![record(vec![Value::from(12_i8)]), success(vec![])],
+            ],
+        );
+        let server = MockServer::start(script).await;
+        let mut client = get_initialized_client(&server, 1).await.unwrap();
+        let responses = client.pipeline(messages).await.unwrap();
+        assert_eq!(responses.len(), 8);
+        assert!(responses
+            .iter()
+            .all(|response| response.success().is_some()));
+        assert_eq!(responses[7].records()[0].fields(), &[Value::from(12_i8)]);
     }
 
     #[tokio::test]
     async fn run_and_pull() {
-        let client = get_initialized_client(1).await;
-        skip_if_handshake_failed!(client);
-        let mut client = client.unwrap();
+        let script = initialized(1)
+            .expect(run_msg("RETURN 3458376 as n;"), vec![fields_success()])
+            .expect(
+                Message::PullAll,
+                vec![record(vec![Value::from(3_458_376)]), success(vec![])],
+            );
+        let server = MockServer::start(script).await;
+        let mut client = get_initialized_client(&server, 1).await.unwrap();
         let response = client
             .run("RETURN 3458376 as n;".to_string(), None)
             .await
@@ -388,23 +537,95 @@ pub(crate) mod tests {
         assert_eq!(records[0].fields(), &[Value::from(3_458_376)]);
     }
 
+    #[tokio::test]
+    async fn run_and_pull_stream() {
+        let script = initialized(1)
+            .expect(
+                run_msg("UNWIND range(1, 5) AS n RETURN n;"),
+                vec![fields_success()],
+            )
+            .expect(
+                Message::PullAll,
+                (1..=5_i8)
+                    .map(|n| record(vec![Value::from(n)]))
+                    .chain(vec![success(vec![])])
+                    .collect(),
+            )
+            .expect(valid_query(1), vec![fields_success()]);
+        let server = MockServer::start(script).await;
+        let mut client = get_initialized_client(&server, 1).await.unwrap();
+        let response = client
+            .run("UNWIND range(1, 5) AS n RETURN n;".to_string(), None)
+            .await
+            .unwrap();
+        assert!(Success::try_from(response).is_ok());
+
+        let mut stream = client.pull_all_stream().await.unwrap();
+        let mut records = vec![];
+        while let Some(record) = stream.next().await {
+            records.push(record.unwrap());
+        }
+        assert!(Success::try_from(stream.into_summary().unwrap()).is_ok());
+        assert_eq!(records.len(), 5);
+        assert_eq!(records[4].fields(), &[Value::from(5_i8)]);
+
+        // The client can be used again once the stream has been consumed
+        let response = run_valid_query(&mut client).await.unwrap();
+        assert!(Success::try_from(response).is_ok());
+    }
+
     #[tokio::test]
     async fn node_and_rel_creation() {
-        let client = get_initialized_client(1).await;
-        skip_if_handshake_failed!(client);
-        let mut client = client.unwrap();
-        let statement = "MATCH (n {test: 'v1-node-rel'}) DETACH DELETE n;".to_string();
-        client.run(statement, None).await.unwrap();
+        let delete = "MATCH (n {test: 'v1-node-rel'}) DETACH DELETE n;";
+        let create = "CREATE (:Client {name: 'bolt-client', test: 'v1-node-rel'})-[:WRITTEN_IN]->(:Language {name: 'Rust', test: 'v1-node-rel'});";
+        let query = "MATCH (c {test: 'v1-node-rel'})-[r:WRITTEN_IN]->(l) RETURN c, r, l;";
+        let client_node = Node::new(
+            1,
+            vec!["Client".to_string()],
+            HashMap::from_iter(vec![
+                ("name".to_string(), "bolt-client"),
+                ("test".to_string(), "v1-node-rel"),
+            ]),
+        );
+        let language_node = Node::new(
+            2,
+            vec!["Language".to_string()],
+            HashMap::from_iter(vec![
+                ("name".to_string(), "Rust"),
+                ("test".to_string(), "v1-node-rel"),
+            ]),
+        );
+        let rel = Relationship::new(
+            3,
+            1,
+            2,
+            "WRITTEN_IN".to_string(),
+            HashMap::<String, Value>::new(),
+        );
+        let script = initialized(1)
+            .expect(run_msg(delete), vec![success(vec![])])
+            .expect(Message::PullAll, vec![success(vec![])])
+            .expect(run_msg(create), vec![success(vec![])])
+            .expect(Message::PullAll, vec![success(vec![])])
+            .expect(run_msg(query), vec![success(vec![])])
+            .expect(
+                Message::PullAll,
+                vec![
+                    record(vec![
+                        Value::from(client_node),
+                        Value::from(rel),
+                        Value::from(language_node),
+                    ]),
+                    success(vec![]),
+                ],
+            );
+        let server = MockServer::start(script).await;
+        let mut client = get_initialized_client(&server, 1).await.unwrap();
+        client.run(delete.to_string(), None).await.unwrap();
         client.pull_all().await.unwrap();
-
-        let statement =
-            "CREATE (:Client {name: 'bolt-client', test: 'v1-node-rel'})-[:WRITTEN_IN]->(:Language {name: 'Rust', test: 'v1-node-rel'});"
-                .to_string();
-        client.run(statement, None).await.unwrap();
+        client.run(create.to_string(), None).await.unwrap();
         client.pull_all().await.unwrap();
-        let statement =
-            "MATCH (c {test: 'v1-node-rel'})-[r:WRITTEN_IN]->(l) RETURN c, r, l;".to_string();
-        client.run(statement, None).await.unwrap();
+        client.run(query.to_string(), None).await.unwrap();
         let (_response, records) = client.pull_all().await.unwrap();
 
         let c = Node::try_from(records[0].fields()[0].clone()).unwrap();
@@ -428,18 +649,25 @@ pub(crate) mod tests {
 
     #[tokio::test]
     async fn discard_all_fail() {
-        let client = get_initialized_client(1).await;
-        skip_if_handshake_failed!(client);
-        let mut client = client.unwrap();
-        let response = client.discard_all().await.unwrap();
-        assert!(Failure::try_from(response).is_ok());
+        // There is no result to discard, so the message is never sent
+        let server = MockServer::start(initialized(1)).await;
+        let mut client = get_initialized_client(&server, 1).await.unwrap();
+        assert!(matches!(
+            client.discard_all().await,
+            Err(Error::InvalidState {
+                request: "PULL_ALL/DISCARD_ALL",
+                state: ServerState::Ready,
+            })
+        ));
     }
 
     #[tokio::test]
     async fn discard_all() {
-        let client = get_initialized_client(1).await;
-        skip_if_handshake_failed!(client);
-        let mut client = client.unwrap();
+        let script = initialized(1)
+            .expect(valid_query(1), vec![fields_success()])
+            .expect(Message::DiscardAll, vec![success(vec![])]);
+        let server = MockServer::start(script).await;
+        let mut client = get_initialized_client(&server, 1).await.unwrap();
         let response = run_valid_query(&mut client).await.unwrap();
         assert!(Success::try_from(response).is_ok());
         let response = client.discard_all().await.unwrap();
@@ -448,23 +676,33 @@ pub(crate) mod tests {
 
     #[tokio::test]
     async fn discard_all_and_pull() {
-        let client = get_initialized_client(1).await;
-        skip_if_handshake_failed!(client);
-        let mut client = client.unwrap();
+        let script = initialized(1)
+            .expect(valid_query(1), vec![fields_success()])
+            .expect(Message::DiscardAll, vec![success(vec![])]);
+        let server = MockServer::start(script).await;
+        let mut client = get_initialized_client(&server, 1).await.unwrap();
         let response = run_valid_query(&mut client).await.unwrap();
         assert!(Success::try_from(response).is_ok());
         let response = client.discard_all().await.unwrap();
         assert!(Success::try_from(response).is_ok());
-        let (response, records) = client.pull_all().await.unwrap();
-        assert!(Failure::try_from(response).is_ok());
-        assert!(records.is_empty());
+        assert!(matches!(
+            client.pull_all().await,
+            Err(Error::InvalidState {
+                request: "PULL_ALL/DISCARD_ALL",
+                state: ServerState::Ready,
+            })
+        ));
     }
 
     #[tokio::test]
     async fn reset() {
-        let client = get_initialized_client(1).await;
-        skip_if_handshake_failed!(client);
-        let mut client = client.unwrap();
+        let script = initialized(1)
+            .expect(invalid_query(1), vec![syntax_error()])
+            .expect(valid_query(1), vec![Message::Ignored])
+            .expect(Message::Reset, vec![success(vec![])])
+            .expect(valid_query(1), vec![fields_success()]);
+        let server = MockServer::start(script).await;
+        let mut client = get_initialized_client(&server, 1).await.unwrap();
         let response = run_invalid_query(&mut client).await.unwrap();
         assert!(Failure::try_from(response).is_ok());
         let response = run_valid_query(&mut client).await.unwrap();
@@ -480,9 +718,11 @@ pub(crate) mod tests {
 
     #[tokio::test]
     async fn ignored() {
-        let client = get_initialized_client(1).await;
-        skip_if_handshake_failed!(client);
-        let mut client = client.unwrap();
+        let script = initialized(1)
+            .expect(invalid_query(1), vec![syntax_error()])
+            .expect(valid_query(1), vec![Message::Ignored]);
+        let server = MockServer::start(script).await;
+        let mut client = get_initialized_client(&server, 1).await.unwrap();
         let response = run_invalid_query(&mut client).await.unwrap();
         assert!(Failure::try_from(response).is_ok());
         let response = run_valid_query(&mut client).await.unwrap();
@@ -494,25 +734,26 @@ pub(crate) mod tests {
 
     #[tokio::test]
     async fn v3_method_with_v1_client_fails() {
-        let client = get_initialized_client(1).await;
-        skip_if_handshake_failed!(client);
-        let mut client = client.unwrap();
+        let server = MockServer::start(initialized(1)).await;
+        let mut client = get_initialized_client(&server, 1).await.unwrap();
         assert!(match client.commit().await {
-            Err(Error::UnsupportedOperation(Some(1))) => true,
+            Err(Error::UnsupportedOperation(Some(version))) => version.major() == 1,
             _ => false,
         });
     }
 
     #[tokio::test]
     async fn v3_message_with_v1_client_fails() {
-        let client = get_initialized_client(1).await;
-        skip_if_handshake_failed!(client);
-        let mut client = client.unwrap();
-        client.send_message(Message::Commit).await.unwrap();
+        // The server doesn't understand the message, and closes the connection
+        let begin = Message::Begin(Begin::new(HashMap::new()));
+        let script = initialized(1).expect(begin.clone(), vec![]).close();
+        let server = MockServer::start(script).await;
+        let mut client = get_initialized_client(&server, 1).await.unwrap();
+        client.send_message(begin).await.unwrap();
         assert!(match client.read_message().await {
-            // Local server just closes connection, but GrapheneDB sends a FAILURE message
-            Err(Error::ProtocolError(_)) | Ok(Message::Failure(_)) => true,
+            Err(Error::ProtocolError(_)) => true,
             _ => false,
         });
+        assert_eq!(client.server_state(), ServerState::Defunct);
     }
 }
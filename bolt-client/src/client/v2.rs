@@ -2,49 +2,65 @@
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
     use std::convert::TryFrom;
+    use std::iter::FromIterator;
 
     use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
 
     use bolt_proto::message::*;
     use bolt_proto::value::*;
+    use bolt_proto::ProtocolVersion;
+    use bolt_test_support::{MockServer, Script};
 
     use crate::client::v1::tests::*;
+    use crate::client::ServerState;
     use crate::error::*;
-    use crate::skip_if_handshake_failed;
 
     #[tokio::test]
     async fn init() {
-        let client = new_client(2).await;
-        skip_if_handshake_failed!(client);
-        let mut client = client.unwrap();
+        let server = MockServer::start(initialized(2)).await;
+        let mut client = new_client(&server, 2).await.unwrap();
         let response = initialize_client(&mut client, true).await.unwrap();
         assert!(Success::try_from(response).is_ok());
     }
 
     #[tokio::test]
     async fn init_fail() {
-        let client = new_client(2).await;
-        skip_if_handshake_failed!(client);
-        let mut client = client.unwrap();
+        let script = Script::new(ProtocolVersion::new(2, 0))
+            .expect(
+                init_msg(2, false),
+                vec![failure(
+                    "Neo.ClientError.Security.Unauthorized",
+                    "The client is unauthorized due to authentication failure.",
+                )],
+            )
+            .close();
+        let server = MockServer::start(script).await;
+        let mut client = new_client(&server, 2).await.unwrap();
         let response = initialize_client(&mut client, false).await.unwrap();
         assert!(Failure::try_from(response).is_ok());
 
         // See https://github.com/neo4j/neo4j/pull/8050.
-        // The current behavior is to simply close the connection on a failed INIT.
-        // Messages now fail to send since connection was closed
-        let response = initialize_client(&mut client, true).await;
-        assert!(match response {
-            Err(Error::ProtocolError(bolt_proto::error::Error::IOError(_))) => true,
-            _ => false,
-        })
+        // The current behavior is to simply close the connection on a failed INIT, so the client is now defunct
+        assert_eq!(client.server_state(), ServerState::Defunct);
+        assert!(matches!(
+            initialize_client(&mut client, true).await,
+            Err(Error::InvalidState {
+                request: "INIT/HELLO",
+                state: ServerState::Defunct,
+            })
+        ));
     }
 
     #[tokio::test]
     async fn ack_failure() {
-        let client = get_initialized_client(2).await;
-        skip_if_handshake_failed!(client);
-        let mut client = client.unwrap();
+        let script = initialized(2)
+            .expect(invalid_query(2), vec![syntax_error()])
+            .expect(Message::AckFailure, vec![success(vec![])])
+            .expect(valid_query(2), vec![fields_success()]);
+        let server = MockServer::start(script).await;
+        let mut client = get_initialized_client(&server, 2).await.unwrap();
         let response = run_invalid_query(&mut client).await.unwrap();
         assert!(Failure::try_from(response).is_ok());
         let response = client.ack_failure().await.unwrap();
@@ -55,9 +71,13 @@ mod tests {
 
     #[tokio::test]
     async fn ack_failure_after_ignored() {
-        let client = get_initialized_client(2).await;
-        skip_if_handshake_failed!(client);
-        let mut client = client.unwrap();
+        let script = initialized(2)
+            .expect(invalid_query(2), vec![syntax_error()])
+            .expect(valid_query(2), vec![Message::Ignored])
+            .expect(Message::AckFailure, vec![success(vec![])])
+            .expect(valid_query(2), vec![fields_success()]);
+        let server = MockServer::start(script).await;
+        let mut client = get_initialized_client(&server, 2).await.unwrap();
         let response = run_invalid_query(&mut client).await.unwrap();
         assert!(Failure::try_from(response).is_ok());
         let response = run_valid_query(&mut client).await.unwrap();
@@ -73,18 +93,15 @@ mod tests {
 
     #[tokio::test]
     async fn run() {
-        let client = get_initialized_client(2).await;
-        skip_if_handshake_failed!(client);
-        let mut client = client.unwrap();
+        let script = initialized(2).expect(valid_query(2), vec![fields_success()]);
+        let server = MockServer::start(script).await;
+        let mut client = get_initialized_client(&server, 2).await.unwrap();
         let response = run_valid_query(&mut client).await.unwrap();
         assert!(Success::try_from(response).is_ok());
     }
 
     #[tokio::test]
     async fn run_pipelined() {
-        let client = get_initialized_client(2).await;
-        skip_if_handshake_failed!(client);
-        let mut client = client.unwrap();
         let messages = vec![
             Message::Run(Run::new("MATCH (n {test: 'v2-pipelined'}) DETACH DELETE n;".to_string(), Default::default())),
             Message::PullAll,
@@ -99,26 +116,62 @@ mod tests {
                 Default::default())),
             Message::PullAll,
         ];
-        for response in client.pipeline(messages).await.unwrap() {
-            assert!(match response {
-                Message::Success(_) => true,
-                Message::Record(record) => {
-                    assert_eq!(
-                        Record::try_from(record).unwrap().fields()[0],
-                        Value::from(Duration::new(118, 7, 0, 0))
-                    );
-                    true
-                }
-                _ => false,
-            });
-        }
+        let script = expect_all(
+            initialized(2),
+            &messages,
+            vec![
+                vec![success(vec![])],
+                vec![success(vec![])],
+                vec![success(vec![])],
+                vec![success(vec![])],
+                vec![success(vec![])],
+                vec![success(vec![])],
+                vec![fields_success()],
+                vec![
+                    record(vec![Value::from(Duration::new(118, 7, 0, 0))]),
+                    success(vec![]),
+                ],
+            ],
+        );
+        let server = MockServer::start(script).await;
+        let mut client = get_initialized_client(&server, 2).await.unwrap();
+        let responses = client.pipeline(messages).await.unwrap();
+        assert_eq!(responses.len(), 8);
+        assert!(responses
+            .iter()
+            .all(|response| response.success().is_some()));
+        assert_eq!(
+            responses[7].records()[0].fields(),
+            &[Value::from(Duration::new(118, 7, 0, 0))]
+        );
     }
 
     #[tokio::test]
     async fn run_and_pull() {
-        let client = get_initialized_client(2).await;
-        skip_if_handshake_failed!(client);
-        let mut client = client.unwrap();
+        let date_time = NaiveDateTime::new(
+            NaiveDate::from_ymd(2010, 3, 5),
+            NaiveTime::from_hms_nano(12, 30, 1, 500),
+        );
+        let point = Point3D::new(9157, 42.5123, 1.123, 3214.0);
+        let script = initialized(2)
+            .expect(
+                run_msg("RETURN localdatetime('2010-03-05T12:30:01.000000500');"),
+                vec![fields_success()],
+            )
+            .expect(
+                Message::PullAll,
+                vec![record(vec![Value::from(date_time)]), success(vec![])],
+            )
+            .expect(
+                run_msg("RETURN point({x: 42.5123, y: 1.123, z: 3214});"),
+                vec![fields_success()],
+            )
+            .expect(
+                Message::PullAll,
+                vec![record(vec![Value::from(point.clone())]), success(vec![])],
+            );
+        let server = MockServer::start(script).await;
+        let mut client = get_initialized_client(&server, 2).await.unwrap();
         let response = client
             .run(
                 "RETURN localdatetime('2010-03-05T12:30:01.000000500');".to_string(),
@@ -131,13 +184,7 @@ mod tests {
         let (response, records) = client.pull_all().await.unwrap();
         assert!(Success::try_from(response).is_ok());
         assert_eq!(records.len(), 1);
-        assert_eq!(
-            records[0].fields(),
-            &[Value::from(NaiveDateTime::new(
-                NaiveDate::from_ymd(2010, 3, 5),
-                NaiveTime::from_hms_nano(12, 30, 1, 500),
-            ))]
-        );
+        assert_eq!(records[0].fields(), &[Value::from(date_time)]);
 
         let response = client
             .run(
@@ -151,29 +198,63 @@ mod tests {
         let (response, records) = client.pull_all().await.unwrap();
         assert!(Success::try_from(response).is_ok());
         assert_eq!(records.len(), 1);
-        assert_eq!(
-            records[0].fields(),
-            &[Value::from(Point3D::new(9157, 42.5123, 1.123, 3214.0))]
-        );
+        assert_eq!(records[0].fields(), &[Value::from(point)]);
     }
 
     #[tokio::test]
     async fn node_and_rel_creation() {
-        let client = get_initialized_client(2).await;
-        skip_if_handshake_failed!(client);
-        let mut client = client.unwrap();
-        let statement = "MATCH (n {test: 'v2-node-rel'}) DETACH DELETE n;".to_string();
-        client.run(statement, None).await.unwrap();
+        let delete = "MATCH (n {test: 'v2-node-rel'}) DETACH DELETE n;";
+        let create = "CREATE (:Client {name: 'bolt-client', starting: datetime('2019-12-19T16:08:04-08:00'), test: 'v2-node-rel'})-[:WRITTEN_IN]->(:Language {name: 'Rust', test: 'v2-node-rel'});";
+        let query = "MATCH (c {test: 'v2-node-rel'})-[r:WRITTEN_IN]->(l) RETURN c, r, l;";
+        let starting = DateTimeOffset::new(2019, 12, 19, 16, 8, 4, 0, (-8, 0)).unwrap();
+        let client_node = Node::new(
+            1,
+            vec!["Client".to_string()],
+            HashMap::from_iter(vec![
+                ("name".to_string(), Value::from("bolt-client")),
+                ("starting".to_string(), Value::from(starting.clone())),
+                ("test".to_string(), Value::from("v2-node-rel")),
+            ]),
+        );
+        let language_node = Node::new(
+            2,
+            vec!["Language".to_string()],
+            HashMap::from_iter(vec![
+                ("name".to_string(), "Rust"),
+                ("test".to_string(), "v2-node-rel"),
+            ]),
+        );
+        let rel = Relationship::new(
+            3,
+            1,
+            2,
+            "WRITTEN_IN".to_string(),
+            HashMap::<String, Value>::new(),
+        );
+        let script = initialized(2)
+            .expect(run_msg(delete), vec![success(vec![])])
+            .expect(Message::PullAll, vec![success(vec![])])
+            .expect(run_msg(create), vec![success(vec![])])
+            .expect(Message::PullAll, vec![success(vec![])])
+            .expect(run_msg(query), vec![success(vec![])])
+            .expect(
+                Message::PullAll,
+                vec![
+                    record(vec![
+                        Value::from(client_node),
+                        Value::from(rel),
+                        Value::from(language_node),
+                    ]),
+                    success(vec![]),
+                ],
+            );
+        let server = MockServer::start(script).await;
+        let mut client = get_initialized_client(&server, 2).await.unwrap();
+        client.run(delete.to_string(), None).await.unwrap();
         client.pull_all().await.unwrap();
-
-        let statement =
-            "CREATE (:Client {name: 'bolt-client', starting: datetime('2019-12-19T16:08:04-08:00'), test: 'v2-node-rel'})-[:WRITTEN_IN]->(:Language {name: 'Rust', test: 'v2-node-rel'});"
-                .to_string();
-        client.run(statement, None).await.unwrap();
+        client.run(create.to_string(), None).await.unwrap();
         client.pull_all().await.unwrap();
-        let statement =
-            "MATCH (c {test: 'v2-node-rel'})-[r:WRITTEN_IN]->(l) RETURN c, r, l;".to_string();
-        client.run(statement, None).await.unwrap();
+        client.run(query.to_string(), None).await.unwrap();
         let (_response, records) = client.pull_all().await.unwrap();
 
         let c = Node::try_from(records[0].fields()[0].clone()).unwrap();
@@ -185,12 +266,7 @@ mod tests {
             c.properties().get("name"),
             Some(&Value::from("bolt-client"))
         );
-        assert_eq!(
-            c.properties().get("starting"),
-            Some(&Value::from(
-                DateTimeOffset::new(2019, 12, 19, 16, 8, 4, 0, (-8, 0)).unwrap()
-            ))
-        );
+        assert_eq!(c.properties().get("starting"), Some(&Value::from(starting)));
         assert_eq!(l.labels(), &["Language".to_string()]);
         assert_eq!(l.properties().get("name"), Some(&Value::from("Rust")));
         assert_eq!(r.rel_type(), "WRITTEN_IN");
@@ -203,18 +279,25 @@ mod tests {
 
     #[tokio::test]
     async fn discard_all_fail() {
-        let client = get_initialized_client(2).await;
-        skip_if_handshake_failed!(client);
-        let mut client = client.unwrap();
-        let response = client.discard_all().await.unwrap();
-        assert!(Failure::try_from(response).is_ok());
+        // There is no result to discard, so the message is never sent
+        let server = MockServer::start(initialized(2)).await;
+        let mut client = get_initialized_client(&server, 2).await.unwrap();
+        assert!(matches!(
+            client.discard_all().await,
+            Err(Error::InvalidState {
+                request: "PULL_ALL/DISCARD_ALL",
+                state: ServerState::Ready,
+            })
+        ));
     }
 
     #[tokio::test]
     async fn discard_all() {
-        let client = get_initialized_client(2).await;
-        skip_if_handshake_failed!(client);
-        let mut client = client.unwrap();
+        let script = initialized(2)
+            .expect(valid_query(2), vec![fields_success()])
+            .expect(Message::DiscardAll, vec![success(vec![])]);
+        let server = MockServer::start(script).await;
+        let mut client = get_initialized_client(&server, 2).await.unwrap();
         let response = run_valid_query(&mut client).await.unwrap();
         assert!(Success::try_from(response).is_ok());
         let response = client.discard_all().await.unwrap();
@@ -223,23 +306,33 @@ mod tests {
 
     #[tokio::test]
     async fn discard_all_and_pull() {
-        let client = get_initialized_client(2).await;
-        skip_if_handshake_failed!(client);
-        let mut client = client.unwrap();
+        let script = initialized(2)
+            .expect(valid_query(2), vec![fields_success()])
+            .expect(Message::DiscardAll, vec![success(vec![])]);
+        let server = MockServer::start(script).await;
+        let mut client = get_initialized_client(&server, 2).await.unwrap();
         let response = run_valid_query(&mut client).await.unwrap();
         assert!(Success::try_from(response).is_ok());
         let response = client.discard_all().await.unwrap();
         assert!(Success::try_from(response).is_ok());
-        let (response, records) = client.pull_all().await.unwrap();
-        assert!(Failure::try_from(response).is_ok());
-        assert!(records.is_empty());
+        assert!(matches!(
+            client.pull_all().await,
+            Err(Error::InvalidState {
+                request: "PULL_ALL/DISCARD_ALL",
+                state: ServerState::Ready,
+            })
+        ));
     }
 
     #[tokio::test]
     async fn reset() {
-        let client = get_initialized_client(2).await;
-        skip_if_handshake_failed!(client);
-        let mut client = client.unwrap();
+        let script = initialized(2)
+            .expect(invalid_query(2), vec![syntax_error()])
+            .expect(valid_query(2), vec![Message::Ignored])
+            .expect(Message::Reset, vec![success(vec![])])
+            .expect(valid_query(2), vec![fields_success()]);
+        let server = MockServer::start(script).await;
+        let mut client = get_initialized_client(&server, 2).await.unwrap();
         let response = run_invalid_query(&mut client).await.unwrap();
         assert!(Failure::try_from(response).is_ok());
         let response = run_valid_query(&mut client).await.unwrap();
@@ -255,9 +348,11 @@ mod tests {
 
     #[tokio::test]
     async fn ignored() {
-        let client = get_initialized_client(2).await;
-        skip_if_handshake_failed!(client);
-        let mut client = client.unwrap();
+        let script = initialized(2)
+            .expect(invalid_query(2), vec![syntax_error()])
+            .expect(valid_query(2), vec![Message::Ignored]);
+        let server = MockServer::start(script).await;
+        let mut client = get_initialized_client(&server, 2).await.unwrap();
         let response = run_invalid_query(&mut client).await.unwrap();
         assert!(Failure::try_from(response).is_ok());
         let response = run_valid_query(&mut client).await.unwrap();
@@ -12,7 +12,12 @@ impl Client {
     ///
     /// # Description
     /// This message is the equivalent of `INIT` for Bolt v3 clients, but the client name and auth token are merged into
-    /// a single metadata object.
+    /// a single metadata object. Bolt v4.1+ clients connecting to a cluster may also include a `routing` key, containing
+    /// the routing context (a map of string keys and values) to use for the connection. See
+    /// [`Hello::with_routing`](bolt_proto::message::Hello::with_routing).
+    ///
+    /// Bolt v4.3+ servers may include a `hints` map in the `SUCCESS` metadata. If it contains a
+    /// `connection.recv_timeout_seconds` hint, the client's [read timeout](Client::set_read_timeout) is lowered to match.
     ///
     /// # Response
     /// - `SUCCESS {…}` if initialization has completed successfully
@@ -22,7 +27,11 @@ impl Client {
     pub async fn hello(&mut self, metadata: HashMap<String, impl Into<Value>>) -> Result<Message> {
         let hello_msg = Hello::new(metadata.into_iter().map(|(k, v)| (k, v.into())).collect());
         self.send_message(Message::Hello(hello_msg)).await?;
-        self.read_message().await
+        let response = self.read_message().await?;
+        if let Message::Success(success) = &response {
+            self.apply_hints(success);
+        }
+        Ok(response)
     }
 
     /// Send a `GOODBYE` message to the server.
@@ -40,7 +49,8 @@ impl Client {
     ///
     /// # Description
     /// This message is the equivalent of `RUN` for Bolt v3 clients, but allows passing an arbitrary metadata hash along
-    /// with the request.
+    /// with the request. Bolt v4 clients may include a `db` key in the metadata to select the database against which
+    /// the statement is executed, see [`RunWithMetadata::with_db`](bolt_proto::message::RunWithMetadata::with_db).
     ///
     /// # Response
     /// - `SUCCESS {…​}` if the statement has been accepted for execution
@@ -66,6 +76,8 @@ impl Client {
     ///
     /// # Description
     /// This Bolt v3 message begins a transaction. A hash of arbitrary metadata can be passed along with the request.
+    /// Bolt v4 clients may include a `db` key in the metadata to select the database for the transaction, see
+    /// [`Begin::with_db`](bolt_proto::message::Begin::with_db).
     ///
     /// # Response
     /// - `SUCCESS {}` if transaction has started successfully
@@ -112,54 +124,71 @@ impl Client {
 #[cfg(test)]
 mod tests {
     use std::convert::TryFrom;
+    use std::iter::FromIterator;
 
     use bolt_proto::value::*;
+    use bolt_proto::ProtocolVersion;
+    use bolt_test_support::{MockServer, Script};
 
     use crate::client::v1::tests::*;
-    use crate::skip_if_handshake_failed;
+    use crate::client::ServerState;
 
     use super::*;
 
+    fn database_node() -> Value {
+        Value::from(Node::new(
+            1,
+            vec!["Database".to_string()],
+            HashMap::from_iter(vec![("name".to_string(), "neo4j")]),
+        ))
+    }
+
     #[tokio::test]
     async fn hello() {
-        let client = new_client(3).await;
-        skip_if_handshake_failed!(client);
-        let mut client = client.unwrap();
+        let server = MockServer::start(initialized(3)).await;
+        let mut client = new_client(&server, 3).await.unwrap();
         let response = initialize_client(&mut client, true).await.unwrap();
         assert!(Success::try_from(response).is_ok());
     }
 
     #[tokio::test]
     async fn hello_fail() {
-        let client = new_client(3).await;
-        skip_if_handshake_failed!(client);
-        let mut client = client.unwrap();
+        let script = Script::new(ProtocolVersion::new(3, 0))
+            .expect(
+                init_msg(3, false),
+                vec![failure(
+                    "Neo.ClientError.Security.Unauthorized",
+                    "The client is unauthorized due to authentication failure.",
+                )],
+            )
+            .close();
+        let server = MockServer::start(script).await;
+        let mut client = new_client(&server, 3).await.unwrap();
         let response = initialize_client(&mut client, false).await.unwrap();
         assert!(Failure::try_from(response).is_ok());
     }
 
     #[tokio::test]
     async fn goodbye() {
-        let client = get_initialized_client(3).await;
-        skip_if_handshake_failed!(client);
-        let mut client = client.unwrap();
+        let script = initialized(3).expect(Message::Goodbye, vec![]).close();
+        let server = MockServer::start(script).await;
+        let mut client = get_initialized_client(&server, 3).await.unwrap();
         assert!(client.goodbye().await.is_ok());
+        // The server hangs up once it has received the GOODBYE
+        assert!(client.read_message().await.is_err());
     }
 
     #[tokio::test]
     async fn run_with_metadata() {
-        let client = get_initialized_client(3).await;
-        skip_if_handshake_failed!(client);
-        let mut client = client.unwrap();
+        let script = initialized(3).expect(valid_query(3), vec![fields_success()]);
+        let server = MockServer::start(script).await;
+        let mut client = get_initialized_client(&server, 3).await.unwrap();
         let response = run_valid_query(&mut client).await.unwrap();
         assert!(Success::try_from(response).is_ok())
     }
 
     #[tokio::test]
     async fn run_with_metadata_pipelined() {
-        let client = get_initialized_client(3).await;
-        skip_if_handshake_failed!(client);
-        let mut client = client.unwrap();
         let messages = vec![
             Message::RunWithMetadata(RunWithMetadata::new(
                 "MATCH (n {test: 'v3-pipelined'}) DETACH DELETE n;".to_string(),
@@ -178,26 +207,44 @@ mod tests {
                 Default::default(), Default::default())),
             Message::PullAll,
         ];
-        for response in client.pipeline(messages).await.unwrap() {
-            assert!(match response {
-                Message::Success(_) => true,
-                Message::Record(record) => {
-                    assert_eq!(
-                        Record::try_from(record).unwrap().fields()[0],
-                        Value::from(Duration::new(118, 7, 0, 0))
-                    );
-                    true
-                }
-                _ => false,
-            });
-        }
+        let script = expect_all(
+            initialized(3),
+            &messages,
+            vec![
+                vec![success(vec![])],
+                vec![success(vec![])],
+                vec![success(vec![])],
+                vec![success(vec![])],
+                vec![success(vec![])],
+                vec![success(vec![])],
+                vec![fields_success()],
+                vec![
+                    record(vec![Value::from(Duration::new(118, 7, 0, 0))]),
+                    success(vec![]),
+                ],
+            ],
+        );
+        let server = MockServer::start(script).await;
+        let mut client = get_initialized_client(&server, 3).await.unwrap();
+        let responses = client.pipeline(messages).await.unwrap();
+        assert_eq!(responses.len(), 8);
+        assert!(responses
+            .iter()
+            .all(|response| response.success().is_some()));
+        assert_eq!(
+            responses[7].records()[0].fields(),
+            &[Value::from(Duration::new(118, 7, 0, 0))]
+        );
     }
 
     #[tokio::test]
     async fn begin() {
-        let client = get_initialized_client(3).await;
-        skip_if_handshake_failed!(client);
-        let mut client = client.unwrap();
+        let script = initialized(3).expect(
+            Message::Begin(Begin::new(HashMap::new())),
+            vec![success(vec![])],
+        );
+        let server = MockServer::start(script).await;
+        let mut client = get_initialized_client(&server, 3).await.unwrap();
         let metadata = HashMap::<std::string::String, bool>::new(); // dummy empty metadata
         let response = client.begin(metadata).await.unwrap();
         assert!(Success::try_from(response).is_ok());
@@ -205,9 +252,17 @@ mod tests {
 
     #[tokio::test]
     async fn commit_empty_transaction() {
-        let client = get_initialized_client(3).await;
-        skip_if_handshake_failed!(client);
-        let mut client = client.unwrap();
+        let script = initialized(3)
+            .expect(
+                Message::Begin(Begin::new(HashMap::new())),
+                vec![success(vec![])],
+            )
+            .expect(
+                Message::Commit,
+                vec![success(vec![("bookmark", Value::from("bookmark:1"))])],
+            );
+        let server = MockServer::start(script).await;
+        let mut client = get_initialized_client(&server, 3).await.unwrap();
         let metadata = HashMap::<std::string::String, bool>::new(); // dummy empty metadata
         client.begin(metadata).await.unwrap();
         let response = client.commit().await.unwrap();
@@ -216,12 +271,6 @@ mod tests {
 
     #[tokio::test]
     async fn commit() {
-        let client = get_initialized_client(3).await;
-        skip_if_handshake_failed!(client);
-        let mut client = client.unwrap();
-        let metadata = HashMap::<std::string::String, bool>::new(); // dummy empty metadata
-        client.begin(metadata).await.unwrap();
-
         let messages = vec![
             Message::RunWithMetadata(RunWithMetadata::new(
                 "MATCH (n {test: 'v3-commit'}) DETACH DELETE n;".to_string(),
@@ -232,45 +281,59 @@ mod tests {
                 Default::default(), Default::default())),
             Message::PullAll,
         ];
+        let query = "MATCH (n {test: 'v3-commit'}) RETURN n;";
+        let script = initialized(3).expect(
+            Message::Begin(Begin::new(HashMap::new())),
+            vec![success(vec![])],
+        );
+        let script = expect_all(script, &messages, vec![vec![success(vec![])]; 4])
+            .expect(
+                Message::Commit,
+                vec![success(vec![("bookmark", Value::from("bookmark:1"))])],
+            )
+            .expect(run_with_metadata_msg(query), vec![fields_success()])
+            .expect(
+                Message::PullAll,
+                vec![record(vec![database_node()]), success(vec![])],
+            );
+        let server = MockServer::start(script).await;
+        let mut client = get_initialized_client(&server, 3).await.unwrap();
+        let metadata = HashMap::<std::string::String, bool>::new(); // dummy empty metadata
+        client.begin(metadata).await.unwrap();
+
         client.pipeline(messages).await.unwrap();
         let response = client.commit().await.unwrap();
         assert!(Success::try_from(response).is_ok());
 
-        let messages = vec![
-            Message::RunWithMetadata(RunWithMetadata::new(
-                "MATCH (n {test: 'v3-commit'}) RETURN n;".to_string(),
-                Default::default(),
-                Default::default(),
-            )),
-            Message::PullAll,
-        ];
-        let mut node_exists = false;
-        for response in client.pipeline(messages).await.unwrap() {
-            if let Message::Record(record) = response {
-                let node =
-                    Node::try_from(Record::try_from(record).unwrap().fields()[0].clone()).unwrap();
-                assert_eq!(node.labels(), &["Database".to_string()]);
-                node_exists = true;
-                break;
-            }
-        }
-        assert!(node_exists);
+        let messages = vec![run_with_metadata_msg(query), Message::PullAll];
+        let responses = client.pipeline(messages).await.unwrap();
+        let node = Node::try_from(responses[1].records()[0].fields()[0].clone()).unwrap();
+        assert_eq!(node.labels(), &["Database".to_string()]);
     }
 
     #[tokio::test]
     async fn commit_with_no_begin_fails() {
-        let client = get_initialized_client(3).await;
-        skip_if_handshake_failed!(client);
-        let mut client = client.unwrap();
-        let response = client.commit().await.unwrap();
-        assert!(Failure::try_from(response).is_ok());
+        let server = MockServer::start(initialized(3)).await;
+        let mut client = get_initialized_client(&server, 3).await.unwrap();
+        assert!(matches!(
+            client.commit().await,
+            Err(Error::InvalidState {
+                request: "COMMIT",
+                state: ServerState::Ready,
+            })
+        ));
     }
 
     #[tokio::test]
     async fn rollback_empty_transaction() {
-        let client = get_initialized_client(3).await;
-        skip_if_handshake_failed!(client);
-        let mut client = client.unwrap();
+        let script = initialized(3)
+            .expect(
+                Message::Begin(Begin::new(HashMap::new())),
+                vec![success(vec![])],
+            )
+            .expect(Message::Rollback, vec![success(vec![])]);
+        let server = MockServer::start(script).await;
+        let mut client = get_initialized_client(&server, 3).await.unwrap();
         let metadata = HashMap::<std::string::String, bool>::new(); // dummy empty metadata
         client.begin(metadata).await.unwrap();
         let response = client.rollback().await.unwrap();
@@ -279,11 +342,6 @@ mod tests {
 
     #[tokio::test]
     async fn rollback() {
-        let client = get_initialized_client(3).await;
-        skip_if_handshake_failed!(client);
-        let mut client = client.unwrap();
-        let metadata = HashMap::<std::string::String, bool>::new(); // dummy empty metadata
-        client.begin(metadata).await.unwrap();
         let messages = vec![
             Message::RunWithMetadata(RunWithMetadata::new(
                 "MATCH (n {test: 'v3-rollback'}) DETACH DELETE n;".to_string(),
@@ -294,33 +352,161 @@ mod tests {
                 Default::default(), Default::default())),
             Message::PullAll,
         ];
+        let query = "MATCH (n {test: 'v3-rollback'}) RETURN n;";
+        let script = initialized(3).expect(
+            Message::Begin(Begin::new(HashMap::new())),
+            vec![success(vec![])],
+        );
+        let script = expect_all(script, &messages, vec![vec![success(vec![])]; 4])
+            .expect(Message::Rollback, vec![success(vec![])])
+            .expect(run_with_metadata_msg(query), vec![fields_success()])
+            .expect(Message::PullAll, vec![success(vec![])]);
+        let server = MockServer::start(script).await;
+        let mut client = get_initialized_client(&server, 3).await.unwrap();
+        let metadata = HashMap::<std::string::String, bool>::new(); // dummy empty metadata
+        client.begin(metadata).await.unwrap();
         client.pipeline(messages).await.unwrap();
         let response = client.rollback().await.unwrap();
         assert!(Success::try_from(response).is_ok());
 
-        let messages = vec![
-            Message::RunWithMetadata(RunWithMetadata::new(
-                "MATCH (n {test: 'v3-rollback'}) RETURN n;".to_string(),
-                Default::default(),
-                Default::default(),
-            )),
-            Message::PullAll,
-        ];
+        let messages = vec![run_with_metadata_msg(query), Message::PullAll];
         for response in client.pipeline(messages).await.unwrap() {
-            assert!(match response {
-                Message::Success(_) => true,
-                // There should be no RECORD messages
-                _ => false,
-            });
+            assert!(response.success().is_some());
+            // There should be no RECORD messages
+            assert!(response.records().is_empty());
         }
     }
 
     #[tokio::test]
     async fn rollback_with_no_begin_fails() {
-        let client = get_initialized_client(3).await;
-        skip_if_handshake_failed!(client);
-        let mut client = client.unwrap();
-        let response = client.rollback().await.unwrap();
-        assert!(Failure::try_from(response).is_ok());
+        let server = MockServer::start(initialized(3)).await;
+        let mut client = get_initialized_client(&server, 3).await.unwrap();
+        assert!(matches!(
+            client.rollback().await,
+            Err(Error::InvalidState {
+                request: "ROLLBACK",
+                state: ServerState::Ready,
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn server_state_in_transaction() {
+        let script = initialized(3)
+            .expect(
+                Message::Begin(Begin::new(HashMap::new())),
+                vec![success(vec![])],
+            )
+            .expect(run_with_metadata_msg("RETURN 1;"), vec![fields_success()])
+            .expect(
+                Message::PullAll,
+                vec![record(vec![Value::from(1_i8)]), success(vec![])],
+            )
+            .expect(Message::Commit, vec![success(vec![])]);
+        let server = MockServer::start(script).await;
+        let mut client = get_initialized_client(&server, 3).await.unwrap();
+        assert_eq!(client.server_state(), ServerState::Ready);
+        client.begin(HashMap::<String, Value>::new()).await.unwrap();
+        assert_eq!(client.server_state(), ServerState::TxReady);
+        assert!(client.is_dirty());
+        client
+            .run_with_metadata("RETURN 1;".to_string(), None, None)
+            .await
+            .unwrap();
+        assert_eq!(client.server_state(), ServerState::TxStreaming);
+        assert!(matches!(
+            client.commit().await,
+            Err(Error::InvalidState {
+                request: "COMMIT",
+                state: ServerState::TxStreaming,
+            })
+        ));
+        client.pull_all().await.unwrap();
+        assert_eq!(client.server_state(), ServerState::TxReady);
+        client.commit().await.unwrap();
+        assert_eq!(client.server_state(), ServerState::Ready);
+        assert!(!client.is_dirty());
+    }
+
+    #[tokio::test]
+    async fn run_while_streaming_fails() {
+        let script =
+            initialized(3).expect(run_with_metadata_msg("RETURN 1;"), vec![fields_success()]);
+        let server = MockServer::start(script).await;
+        let mut client = get_initialized_client(&server, 3).await.unwrap();
+        client
+            .run_with_metadata("RETURN 1;".to_string(), None, None)
+            .await
+            .unwrap();
+        assert!(matches!(
+            client
+                .run_with_metadata("RETURN 2;".to_string(), None, None)
+                .await,
+            Err(Error::InvalidState {
+                request: "RUN",
+                state: ServerState::Streaming,
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn illegal_pipeline_is_not_sent() {
+        let server = MockServer::start(initialized(3)).await;
+        let mut client = get_initialized_client(&server, 3).await.unwrap();
+        let messages = vec![
+            run_with_metadata_msg("RETURN 1;"),
+            run_with_metadata_msg("RETURN 2;"),
+        ];
+        assert!(matches!(
+            client.pipeline(messages).await,
+            Err(Error::InvalidState {
+                request: "RUN",
+                state: ServerState::Streaming,
+            })
+        ));
+        assert_eq!(client.server_state(), ServerState::Ready);
+    }
+
+    #[tokio::test]
+    async fn failure_in_pipeline() {
+        let script = initialized(3)
+            .expect(
+                run_with_metadata_msg("RETURN 1 AS"),
+                vec![failure("Neo.ClientError.Statement.SyntaxError", "")],
+            )
+            .expect(Message::PullAll, vec![Message::Ignored])
+            .expect(Message::Reset, vec![success(vec![])]);
+        let server = MockServer::start(script).await;
+        let mut client = get_initialized_client(&server, 3).await.unwrap();
+        let messages = vec![run_with_metadata_msg("RETURN 1 AS"), Message::PullAll];
+        let responses = client.pipeline(messages).await.unwrap();
+        assert!(responses[0].failure().is_some());
+        assert_eq!(responses[0].failed_request(), None);
+        assert!(responses[1].is_ignored());
+        assert_eq!(responses[1].failed_request(), Some(0));
+        assert_eq!(client.server_state(), ServerState::Failed);
+        client.reset().await.unwrap();
+        assert_eq!(client.server_state(), ServerState::Ready);
+    }
+
+    #[tokio::test]
+    async fn connection_loss_makes_client_defunct() {
+        let script = initialized(3)
+            .expect(run_with_metadata_msg("RETURN 1;"), vec![])
+            .close();
+        let server = MockServer::start(script).await;
+        let mut client = get_initialized_client(&server, 3).await.unwrap();
+        assert!(client
+            .run_with_metadata("RETURN 1;".to_string(), None, None)
+            .await
+            .is_err());
+        assert!(client.is_defunct());
+        assert!(matches!(
+            client.reset().await,
+            Err(Error::InvalidState {
+                request: "RESET",
+                state: ServerState::Defunct,
+            })
+        ));
     }
 }
@@ -1,4 +1,624 @@
-use crate::Client;
+use std::collections::HashMap;
 
-// TODO: Implement v4 methods
-impl Client {}
+use bolt_client_macros::*;
+use bolt_proto::message::*;
+use bolt_proto::{Message, ProtocolVersion, Value};
+
+use crate::error::*;
+use crate::{Client, RecordStream};
+
+impl Client {
+    /// Send a `DISCARD` message to the server.
+    ///
+    /// # Description
+    /// This message is the equivalent of `DISCARD_ALL` for Bolt v4 clients, but allows passing an arbitrary metadata
+    /// hash along with the request. The following keys are recognized by the server:
+    /// - `n`: the number of records to discard (`-1` discards all remaining records)
+    /// - `qid`: the id of the statement to discard records from, as returned in the `SUCCESS` metadata of a `RUN`
+    ///     inside an explicit transaction (`-1` refers to the most recent statement)
+    ///
+    /// # Response
+    /// - `SUCCESS {"has_more": true, …}` if there are still records in the result stream
+    /// - `SUCCESS {…}` if the result stream has been fully discarded
+    /// - `FAILURE {"code": …​, "message": …​}` if no result stream is currently available
+    #[bolt_version(4)]
+    pub async fn discard(&mut self, metadata: Option<HashMap<String, Value>>) -> Result<Message> {
+        let discard_msg = Discard::new(metadata.unwrap_or_default());
+        self.send_message(Message::Discard(discard_msg)).await?;
+        self.read_message().await
+    }
+
+    /// Send a `PULL` message to the server. Returns a tuple containing a `Vec` of the records returned from the server
+    /// as well as the summary message (`SUCCESS` or `FAILURE`).
+    ///
+    /// # Description
+    /// This message is the equivalent of `PULL_ALL` for Bolt v4 clients, but allows passing an arbitrary metadata hash
+    /// along with the request. The following keys are recognized by the server:
+    /// - `n`: the number of records to fetch (`-1` fetches all remaining records)
+    /// - `qid`: the id of the statement to fetch records from, as returned in the `SUCCESS` metadata of a `RUN`
+    ///     inside an explicit transaction (`-1` refers to the most recent statement)
+    ///
+    /// # Response
+    /// - `SUCCESS {"has_more": true, …}` if there are still records in the result stream
+    /// - `SUCCESS {…​}` if the result stream has been fully transferred
+    /// - `FAILURE {"code": …​, "message": …​}` if no result stream is currently available or if retrieval fails
+    #[bolt_version(4)]
+    pub async fn pull(
+        &mut self,
+        metadata: Option<HashMap<String, Value>>,
+    ) -> Result<(Message, Vec<Record>)> {
+        let pull_msg = Pull::new(metadata.unwrap_or_default());
+        self.send_message(Message::Pull(pull_msg)).await?;
+        let mut records = vec![];
+        loop {
+            match self.read_message().await? {
+                Message::Record(record) => records.push(record),
+                other => return Ok((other, records)),
+            }
+        }
+    }
+
+    /// Send a `PULL` message to the server, requesting records in batches of the given size. Returns a
+    /// [`RecordStream`](crate::RecordStream) that yields each record as it is received, and automatically sends another
+    /// `PULL` message whenever the server reports that more records are available. A fetch size of `-1` requests all
+    /// records at once.
+    ///
+    /// The summary message (`SUCCESS` or `FAILURE`) is available from the stream once it has ended. See
+    /// [`pull`](Client::pull) for a description of the `PULL` message.
+    #[bolt_version(4)]
+    pub async fn pull_stream(&mut self, fetch_size: i64) -> Result<RecordStream<'_>> {
+        let pull_msg = Pull::new(HashMap::new()).with_n(fetch_size);
+        self.send_message(Message::Pull(pull_msg)).await?;
+        Ok(RecordStream::new(self, Some(fetch_size)))
+    }
+
+    /// Send a `ROUTE` message to the server.
+    ///
+    /// # Description
+    /// This Bolt v4.3 message requests the routing table for a database from a cluster member. The routing context is
+    /// usually the set of query parameters from the connection URI, with the initial server address given under the
+    /// `address` key. If no database is provided, the routing table for the default database is returned.
+    ///
+    /// From Bolt v4.4, the database is sent in a metadata map (`{"db": …}`) rather than as a plain string; the layout
+    /// matching the negotiated version is used.
+    ///
+    /// # Response
+    /// - `SUCCESS {"rt": {"ttl": …, "db": …, "servers": […]}}` if the routing table could be retrieved
+    /// - `FAILURE {"code": …​, "message": …​}` if the request was malformed, or if the database does not exist
+    #[bolt_version(4.3)]
+    pub async fn route(
+        &mut self,
+        context: HashMap<String, impl Into<Value>>,
+        bookmarks: Vec<String>,
+        db: Option<String>,
+    ) -> Result<Message> {
+        let context = context.into_iter().map(|(k, v)| (k, v.into())).collect();
+        if self.version() >= Some(ProtocolVersion::new(4, 4)) {
+            let mut route_msg = RouteWithMetadata::new(context, bookmarks, HashMap::new());
+            if let Some(db) = db {
+                route_msg = route_msg.with_db(db);
+            }
+            self.send_message(Message::RouteWithMetadata(Box::new(route_msg)))
+                .await?;
+        } else {
+            let route_msg = Route::new(context, bookmarks, db);
+            self.send_message(Message::Route(route_msg)).await?;
+        }
+        self.read_message().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::{TryFrom, TryInto};
+    use std::iter::FromIterator;
+    use std::time::Duration;
+
+    use bolt_proto::value::*;
+    use bolt_proto::version::VersionRange;
+    use bolt_proto::ProtocolVersion;
+    use bolt_test_support::{MockServer, Script};
+    use bytes::Bytes;
+    use tokio::io::BufStream;
+    use tokio::net::TcpListener;
+    use tokio::prelude::*;
+    use tokio::stream::StreamExt;
+
+    use crate::client::v1::tests::*;
+    use crate::client::ServerState;
+
+    use super::*;
+
+    fn pull_msg(n: i8) -> Message {
+        Message::Pull(Pull::new(HashMap::from_iter(vec![(
+            "n".to_string(),
+            Value::from(n),
+        )])))
+    }
+
+    fn has_more() -> Message {
+        success(vec![("has_more", Value::from(true))])
+    }
+
+    fn numbers(range: std::ops::RangeInclusive<i8>) -> Vec<Message> {
+        range.map(|n| record(vec![Value::from(n)])).collect()
+    }
+
+    #[tokio::test]
+    async fn hello() {
+        let server = MockServer::start(initialized(4)).await;
+        let mut client = new_client(&server, 4).await.unwrap();
+        let response = initialize_client(&mut client, true).await.unwrap();
+        assert!(Success::try_from(response).is_ok());
+    }
+
+    #[tokio::test]
+    async fn hello_fail() {
+        let script = Script::new(ProtocolVersion::new(4, 0))
+            .expect(
+                init_msg(4, false),
+                vec![failure(
+                    "Neo.ClientError.Security.Unauthorized",
+                    "The client is unauthorized due to authentication failure.",
+                )],
+            )
+            .close();
+        let server = MockServer::start(script).await;
+        let mut client = new_client(&server, 4).await.unwrap();
+        let response = initialize_client(&mut client, false).await.unwrap();
+        assert!(Failure::try_from(response).is_ok());
+    }
+
+    #[tokio::test]
+    async fn recv_timeout_hint() {
+        let hints = success(vec![(
+            "hints",
+            Value::from(HashMap::from_iter(vec![(
+                "connection.recv_timeout_seconds".to_string(),
+                Value::from(60),
+            )])),
+        )]);
+        let minute = Duration::from_secs(60);
+        for &(version, configured, expected) in &[
+            (ProtocolVersion::new(4, 3), None, Some(minute)),
+            (
+                ProtocolVersion::new(4, 3),
+                Some(minute / 2),
+                Some(minute / 2),
+            ),
+            (ProtocolVersion::new(4, 3), Some(minute * 2), Some(minute)),
+            (ProtocolVersion::new(4, 2), None, None),
+        ] {
+            let script = Script::new(version).expect(init_msg(4, true), vec![hints.clone()]);
+            let server = MockServer::start(script).await;
+            let mut client = Client::new(server.address(), None).await.unwrap();
+            client
+                .handshake(&[VersionRange::new(ProtocolVersion::new(4, 3), 3)])
+                .await
+                .unwrap();
+            client.set_read_timeout(configured);
+            initialize_client(&mut client, true).await.unwrap();
+            assert_eq!(client.read_timeout(), expected, "{:?}", version);
+        }
+    }
+
+    #[tokio::test]
+    async fn noops_are_skipped() {
+        // The mock server can't send NOOPs, so this server sends some before its response to HELLO
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut stream = BufStream::new(stream);
+            let mut handshake = [0; 20];
+            stream.read_exact(&mut handshake).await.unwrap();
+            stream
+                .write_u32(ProtocolVersion::new(4, 1).into())
+                .await
+                .unwrap();
+            stream.flush().await.unwrap();
+            Message::from_stream(&mut stream).await.unwrap();
+            for _ in 0..4 {
+                stream.write_u16(0).await.unwrap();
+                stream.flush().await.unwrap();
+                tokio::time::delay_for(Duration::from_millis(50)).await;
+            }
+            let chunks: Vec<Bytes> = success(vec![]).try_into().unwrap();
+            for mut chunk in chunks {
+                stream.write_buf(&mut chunk).await.unwrap();
+            }
+            stream.flush().await.unwrap();
+        });
+
+        let mut client = Client::new(address, None).await.unwrap();
+        client
+            .handshake(&[VersionRange::exact(ProtocolVersion::new(4, 1))])
+            .await
+            .unwrap();
+        // Each NOOP arrives within the read timeout, although the response as a whole doesn't
+        client.set_read_timeout(Some(Duration::from_millis(150)));
+        let response = initialize_client(&mut client, true).await.unwrap();
+        assert!(Success::try_from(response).is_ok());
+        assert_eq!(client.server_state(), ServerState::Ready);
+    }
+
+    #[tokio::test]
+    async fn goodbye() {
+        let script = initialized(4).expect(Message::Goodbye, vec![]).close();
+        let server = MockServer::start(script).await;
+        let mut client = get_initialized_client(&server, 4).await.unwrap();
+        assert!(client.goodbye().await.is_ok());
+        // The server hangs up once it has received the GOODBYE
+        assert!(client.read_message().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn run_with_metadata() {
+        let script = initialized(4).expect(valid_query(4), vec![fields_success()]);
+        let server = MockServer::start(script).await;
+        let mut client = get_initialized_client(&server, 4).await.unwrap();
+        let response = run_valid_query(&mut client).await.unwrap();
+        assert!(Success::try_from(response).is_ok())
+    }
+
+    #[tokio::test]
+    async fn run_with_metadata_and_db() {
+        let script = initialized(4).expect(
+            Message::RunWithMetadata(RunWithMetadata::new(
+                "RETURN 1 as n;".to_string(),
+                HashMap::new(),
+                HashMap::from_iter(vec![("db".to_string(), Value::from("neo4j"))]),
+            )),
+            vec![fields_success()],
+        );
+        let server = MockServer::start(script).await;
+        let mut client = get_initialized_client(&server, 4).await.unwrap();
+        let response = client
+            .run_with_metadata(
+                "RETURN 1 as n;".to_string(),
+                None,
+                Some(HashMap::from_iter(vec![(
+                    "db".to_string(),
+                    Value::from("neo4j"),
+                )])),
+            )
+            .await
+            .unwrap();
+        assert!(Success::try_from(response).is_ok())
+    }
+
+    #[tokio::test]
+    async fn pull() {
+        let script = initialized(4)
+            .expect(
+                run_with_metadata_msg("UNWIND range(1, 5) AS n RETURN n;"),
+                vec![fields_success()],
+            )
+            .expect(pull_msg(3), [numbers(1..=3), vec![has_more()]].concat())
+            .expect(
+                pull_msg(-1),
+                [numbers(4..=5), vec![success(vec![])]].concat(),
+            );
+        let server = MockServer::start(script).await;
+        let mut client = get_initialized_client(&server, 4).await.unwrap();
+        let response = client
+            .run_with_metadata("UNWIND range(1, 5) AS n RETURN n;".to_string(), None, None)
+            .await
+            .unwrap();
+        assert!(Success::try_from(response).is_ok());
+
+        let (response, records) = client
+            .pull(Some(HashMap::from_iter(vec![(
+                "n".to_string(),
+                Value::from(3_i8),
+            )])))
+            .await
+            .unwrap();
+        let success = Success::try_from(response).unwrap();
+        assert_eq!(success.metadata().get("has_more"), Some(&Value::from(true)));
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].fields(), &[Value::from(1_i8)]);
+
+        let (response, records) = client
+            .pull(Some(HashMap::from_iter(vec![(
+                "n".to_string(),
+                Value::from(-1_i8),
+            )])))
+            .await
+            .unwrap();
+        let success = Success::try_from(response).unwrap();
+        assert!(success.metadata().get("has_more").is_none());
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].fields(), &[Value::from(5_i8)]);
+    }
+
+    #[tokio::test]
+    async fn pull_fail() {
+        let server = MockServer::start(initialized(4)).await;
+        let mut client = get_initialized_client(&server, 4).await.unwrap();
+        assert!(matches!(
+            client.pull(None).await,
+            Err(Error::InvalidState {
+                request: "PULL/DISCARD",
+                state: ServerState::Ready,
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn pull_stream() {
+        let script = initialized(4)
+            .expect(
+                run_with_metadata_msg("UNWIND range(1, 5) AS n RETURN n;"),
+                vec![fields_success()],
+            )
+            .expect(pull_msg(2), [numbers(1..=2), vec![has_more()]].concat())
+            .expect(pull_msg(2), [numbers(3..=4), vec![has_more()]].concat())
+            .expect(
+                pull_msg(2),
+                [numbers(5..=5), vec![success(vec![])]].concat(),
+            );
+        let server = MockServer::start(script).await;
+        let mut client = get_initialized_client(&server, 4).await.unwrap();
+        let response = client
+            .run_with_metadata("UNWIND range(1, 5) AS n RETURN n;".to_string(), None, None)
+            .await
+            .unwrap();
+        assert!(Success::try_from(response).is_ok());
+
+        // Records are fetched in several batches
+        let mut stream = client.pull_stream(2).await.unwrap();
+        let mut records = vec![];
+        while let Some(record) = stream.next().await {
+            records.push(record.unwrap());
+        }
+        let success = Success::try_from(stream.into_summary().unwrap()).unwrap();
+        assert!(success.metadata().get("has_more").is_none());
+        assert_eq!(records.len(), 5);
+        assert_eq!(records[0].fields(), &[Value::from(1_i8)]);
+        assert_eq!(records[4].fields(), &[Value::from(5_i8)]);
+    }
+
+    #[tokio::test]
+    async fn pull_stream_fail() {
+        let server = MockServer::start(initialized(4)).await;
+        let mut client = get_initialized_client(&server, 4).await.unwrap();
+        assert!(matches!(
+            client.pull_stream(-1).await,
+            Err(Error::InvalidState {
+                request: "PULL/DISCARD",
+                state: ServerState::Ready,
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn discard() {
+        let script = initialized(4)
+            .expect(valid_query(4), vec![fields_success()])
+            .expect(
+                Message::Discard(Discard::new(HashMap::from_iter(vec![(
+                    "n".to_string(),
+                    Value::from(-1_i8),
+                )]))),
+                vec![success(vec![])],
+            );
+        let server = MockServer::start(script).await;
+        let mut client = get_initialized_client(&server, 4).await.unwrap();
+        let response = run_valid_query(&mut client).await.unwrap();
+        assert!(Success::try_from(response).is_ok());
+        let response = client
+            .discard(Some(HashMap::from_iter(vec![(
+                "n".to_string(),
+                Value::from(-1_i8),
+            )])))
+            .await
+            .unwrap();
+        assert!(Success::try_from(response).is_ok());
+    }
+
+    #[tokio::test]
+    async fn discard_fail() {
+        let server = MockServer::start(initialized(4)).await;
+        let mut client = get_initialized_client(&server, 4).await.unwrap();
+        assert!(matches!(
+            client.discard(None).await,
+            Err(Error::InvalidState {
+                request: "PULL/DISCARD",
+                state: ServerState::Ready,
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn begin_with_db() {
+        let script = initialized(4)
+            .expect(
+                Message::Begin(Begin::new(HashMap::from_iter(vec![(
+                    "db".to_string(),
+                    Value::from("neo4j"),
+                )]))),
+                vec![success(vec![])],
+            )
+            .expect(
+                Message::Commit,
+                vec![success(vec![("bookmark", Value::from("bookmark:1"))])],
+            );
+        let server = MockServer::start(script).await;
+        let mut client = get_initialized_client(&server, 4).await.unwrap();
+        let response = client
+            .begin(HashMap::from_iter(vec![("db".to_string(), "neo4j")]))
+            .await
+            .unwrap();
+        assert!(Success::try_from(response).is_ok());
+        let response = client.commit().await.unwrap();
+        assert!(Success::try_from(response).is_ok());
+    }
+
+    #[tokio::test]
+    async fn commit_pulled_transaction() {
+        let messages = vec![
+            Message::RunWithMetadata(RunWithMetadata::new(
+                "MATCH (n {test: 'v4-commit'}) DETACH DELETE n;".to_string(),
+                Default::default(), Default::default())),
+            Message::Pull(Pull::new(HashMap::from_iter(vec![("n".to_string(), Value::from(-1_i8))]))),
+            Message::RunWithMetadata(RunWithMetadata::new(
+                "CREATE (:Database {name: 'neo4j', v1_release: date('2010-02-16'), test: 'v4-commit'});".to_string(),
+                Default::default(), Default::default())),
+            Message::Pull(Pull::new(HashMap::from_iter(vec![("n".to_string(), Value::from(-1_i8))]))),
+        ];
+        let query = "MATCH (n {test: 'v4-commit'}) RETURN n;";
+        let database = Node::new(
+            1,
+            vec!["Database".to_string()],
+            HashMap::from_iter(vec![("name".to_string(), "neo4j")]),
+        );
+        let script = initialized(4).expect(
+            Message::Begin(Begin::new(HashMap::new())),
+            vec![success(vec![])],
+        );
+        let script = expect_all(script, &messages, vec![vec![success(vec![])]; 4])
+            .expect(
+                Message::Commit,
+                vec![success(vec![("bookmark", Value::from("bookmark:1"))])],
+            )
+            .expect(run_with_metadata_msg(query), vec![fields_success()])
+            .expect(
+                pull_msg(-1),
+                vec![record(vec![Value::from(database)]), success(vec![])],
+            );
+        let server = MockServer::start(script).await;
+        let mut client = get_initialized_client(&server, 4).await.unwrap();
+        let metadata = HashMap::<std::string::String, bool>::new(); // dummy empty metadata
+        client.begin(metadata).await.unwrap();
+
+        client.pipeline(messages).await.unwrap();
+        let response = client.commit().await.unwrap();
+        assert!(Success::try_from(response).is_ok());
+
+        client
+            .run_with_metadata(query.to_string(), None, None)
+            .await
+            .unwrap();
+        let (response, records) = client
+            .pull(Some(HashMap::from_iter(vec![(
+                "n".to_string(),
+                Value::from(-1_i8),
+            )])))
+            .await
+            .unwrap();
+        assert!(Success::try_from(response).is_ok());
+        let node = Node::try_from(records[0].fields()[0].clone()).unwrap();
+        assert_eq!(node.labels(), &["Database".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn pipeline() {
+        let messages = vec![
+            Message::Begin(Begin::new(HashMap::new())),
+            run_with_metadata_msg("RETURN 1 AS"),
+            pull_msg(-1),
+            Message::Commit,
+            Message::Reset,
+            run_with_metadata_msg("RETURN 1 AS n;"),
+            pull_msg(-1),
+            Message::Goodbye,
+        ];
+        let script = expect_all(
+            initialized(4),
+            &messages[..7],
+            vec![
+                vec![success(vec![])],
+                vec![failure("Neo.ClientError.Statement.SyntaxError", "")],
+                vec![Message::Ignored],
+                vec![Message::Ignored],
+                vec![success(vec![])],
+                vec![fields_success()],
+                vec![record(vec![Value::from(1_i8)]), success(vec![])],
+            ],
+        );
+        let server = MockServer::start(script).await;
+        let mut client = get_initialized_client(&server, 4).await.unwrap();
+        let responses = client.pipeline(messages.clone()).await.unwrap();
+
+        // No response is sent for GOODBYE
+        assert_eq!(responses.len(), 7);
+        for (response, request) in responses.iter().zip(messages) {
+            assert_eq!(response.request(), &request);
+        }
+        assert!(responses[1].failure().is_some());
+        for response in &responses[2..4] {
+            assert!(response.is_ignored());
+            assert_eq!(response.failed_request(), Some(1));
+        }
+        assert!(responses[4].success().is_some());
+        assert_eq!(responses[4].failed_request(), None);
+        assert_eq!(responses[6].records()[0].fields(), &[Value::from(1_i8)]);
+        assert!(client.is_defunct());
+    }
+
+    #[tokio::test]
+    async fn route() {
+        let context = HashMap::from_iter(vec![("address".to_string(), Value::from("a:1"))]);
+        let bookmarks = vec!["bm:1".to_string()];
+        let routing_table = success(vec![("rt", Value::from(HashMap::<String, Value>::new()))]);
+        for &(minor, ref route_msg) in &[
+            (
+                3,
+                Message::Route(Route::new(
+                    context.clone(),
+                    bookmarks.clone(),
+                    Some("people".to_string()),
+                )),
+            ),
+            (
+                4,
+                Message::RouteWithMetadata(Box::new(
+                    RouteWithMetadata::new(context.clone(), bookmarks.clone(), HashMap::new())
+                        .with_db("people"),
+                )),
+            ),
+        ] {
+            let version = ProtocolVersion::new(4, minor);
+            let script = Script::new(version)
+                .expect(init_msg(4, true), vec![success(vec![])])
+                .expect(route_msg.clone(), vec![routing_table.clone()]);
+            let server = MockServer::start(script).await;
+            let mut client = Client::new(server.address(), None).await.unwrap();
+            client
+                .handshake(&[VersionRange::exact(version)])
+                .await
+                .unwrap();
+            initialize_client(&mut client, true).await.unwrap();
+            let response = client
+                .route(
+                    context.clone(),
+                    bookmarks.clone(),
+                    Some("people".to_string()),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response, routing_table, "{:?}", version);
+        }
+    }
+
+    #[tokio::test]
+    async fn v1_method_with_v4_client_fails() {
+        let server = MockServer::start(initialized(4)).await;
+        let mut client = get_initialized_client(&server, 4).await.unwrap();
+        assert!(matches!(
+            client.pull_all().await,
+            Err(Error::UnsupportedOperation(Some(version))) if version.major() == 4
+        ));
+    }
+
+    #[tokio::test]
+    async fn v4_3_method_with_v4_0_client_fails() {
+        let server = MockServer::start(initialized(4)).await;
+        let mut client = get_initialized_client(&server, 4).await.unwrap();
+        assert!(matches!(
+            client.route(HashMap::<String, Value>::new(), vec![], None).await,
+            Err(Error::UnsupportedOperation(Some(version))) if version == ProtocolVersion::new(4, 0)
+        ));
+    }
+}
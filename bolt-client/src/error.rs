@@ -1,17 +1,292 @@
+use std::fmt;
+
 use thiserror::Error;
 
+use bolt_proto::message::Failure;
+use bolt_proto::{Message, ProtocolVersion, Value};
+
+use crate::client::ServerState;
+use crate::routing::AccessMode;
+
 pub type Result<T> = std::result::Result<T, Error>;
 
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("Invalid DNS name: {0}")]
     InvalidDNSName(String),
+    #[error("Invalid URI: {0}")]
+    InvalidUri(String),
+    #[error("Invalid TLS configuration: {0}")]
+    InvalidTlsConfig(String),
     #[error(transparent)]
     IOError(#[from] std::io::Error),
+    #[cfg(feature = "websocket")]
+    #[error(transparent)]
+    WebSocketError(#[from] tokio_tungstenite::tungstenite::Error),
+    #[error("Operation timed out")]
+    Timeout,
     #[error("Handshake with server failed")]
     HandshakeFailed,
+    #[error("Too many versions proposed for handshake: {0} (maximum is 4)")]
+    TooManyVersions(usize),
     #[error("Unsupported operation for client with version = {0:?}")]
-    UnsupportedOperation(Option<u32>),
+    UnsupportedOperation(Option<ProtocolVersion>),
+    #[error("Client initialization failed: {0:?}")]
+    InitFailed(Message),
+    #[error("Unable to retrieve routing table for database {0:?}")]
+    RoutingTableUnavailable(Option<String>),
+    #[error("Invalid routing table: {0}")]
+    InvalidRoutingTable(String),
+    #[error("No servers available for {0:?} access")]
+    NoServersAvailable(AccessMode),
+    #[error("Missing field {0:?}")]
+    MissingField(String),
+    #[error("Invalid value for field {field:?}: {source}")]
+    InvalidField { field: String, source: Box<Error> },
+    #[error("Node does not have label {0:?}")]
+    MissingLabel(String),
+    #[error("Cannot send {request} while the server is in the {state} state")]
+    InvalidState {
+        request: &'static str,
+        state: ServerState,
+    },
+    #[error("Server failure: {0}")]
+    ServerFailure(ServerError),
     #[error(transparent)]
     ProtocolError(#[from] bolt_proto::error::Error),
 }
+
+impl From<Failure> for Error {
+    fn from(failure: Failure) -> Self {
+        Error::ServerFailure(ServerError::from(failure))
+    }
+}
+
+/// The classification of a Neo4j status code, which determines whether the failed operation may be retried.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Classification {
+    /// The client sent a bad request. Retrying the same request will fail again.
+    ClientError,
+    /// The request succeeded, but the server has a notification for the client.
+    ClientNotification,
+    /// The server could not process the request at this time. Retrying the same request may succeed.
+    TransientError,
+    /// The server failed to process a valid request.
+    DatabaseError,
+}
+
+/// An error reported by the server in a `FAILURE` message.
+///
+/// Neo4j status codes have the form `Neo.<Classification>.<Category>.<Title>`, for example
+/// `Neo.ClientError.Statement.SyntaxError`. Codes that don't have this form are preserved, but have no
+/// classification, category or title.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ServerError {
+    code: String,
+    message: String,
+    classification: Option<Classification>,
+    category: Option<String>,
+    title: Option<String>,
+}
+
+impl ServerError {
+    pub fn new(code: impl Into<String>, message: impl Into<String>) -> Self {
+        let code = code.into();
+        let parts: Vec<&str> = code.split('.').collect();
+        let (classification, category, title) = match parts.as_slice() {
+            ["Neo", classification, category, title] => {
+                let classification = match *classification {
+                    "ClientError" => Some(Classification::ClientError),
+                    "ClientNotification" => Some(Classification::ClientNotification),
+                    "TransientError" => Some(Classification::TransientError),
+                    "DatabaseError" => Some(Classification::DatabaseError),
+                    _ => None,
+                };
+                (
+                    classification,
+                    classification.map(|_| category.to_string()),
+                    classification.map(|_| title.to_string()),
+                )
+            }
+            _ => (None, None, None),
+        };
+        Self {
+            code,
+            message: message.into(),
+            classification,
+            category,
+            title,
+        }
+    }
+
+    /// The full status code, e.g. `Neo.ClientError.Statement.SyntaxError`.
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn classification(&self) -> Option<Classification> {
+        self.classification
+    }
+
+    /// The category of the status code, e.g. `Statement`.
+    pub fn category(&self) -> Option<&str> {
+        self.category.as_deref()
+    }
+
+    /// The title of the status code, e.g. `SyntaxError`.
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    /// Whether the failed operation may succeed if retried, possibly on another cluster member.
+    ///
+    /// Transient errors are retryable, except for transactions that were terminated by the user or interrupted by a
+    /// client-side timeout. A write sent to a cluster member that is no longer the leader is also retryable.
+    pub fn is_retryable(&self) -> bool {
+        match (self.classification, self.category(), self.title()) {
+            (Some(Classification::TransientError), Some("Transaction"), Some(title)) => {
+                !matches!(title, "Terminated" | "LockClientStopped")
+            }
+            (Some(Classification::TransientError), _, _) => true,
+            (Some(Classification::ClientError), Some("Cluster"), Some("NotALeader")) => true,
+            (
+                Some(Classification::ClientError),
+                Some("General"),
+                Some("ForbiddenOnReadOnlyDatabase"),
+            ) => true,
+            _ => false,
+        }
+    }
+
+    /// Whether the server rejected the client's credentials.
+    pub fn is_authentication_error(&self) -> bool {
+        self.classification == Some(Classification::ClientError)
+            && self.category() == Some("Security")
+            && matches!(
+                self.title(),
+                Some("Unauthorized")
+                    | Some("AuthenticationRateLimit")
+                    | Some("CredentialsExpired")
+                    | Some("TokenExpired")
+            )
+    }
+
+    /// Whether the failed operation would have violated a schema constraint, such as a uniqueness constraint.
+    pub fn is_constraint_violation(&self) -> bool {
+        self.classification == Some(Classification::ClientError)
+            && matches!(
+                (self.category(), self.title()),
+                (Some("Schema"), Some("ConstraintValidationFailed"))
+                    | (Some("Schema"), Some("ConstraintViolation"))
+                    | (Some("Statement"), Some("ConstraintVerificationFailed"))
+            )
+    }
+}
+
+impl From<Failure> for ServerError {
+    fn from(failure: Failure) -> Self {
+        let field = |key: &str| match failure.metadata().get(key) {
+            Some(Value::String(value)) => value.to_string(),
+            _ => std::string::String::new(),
+        };
+        ServerError::new(field("code"), field("message"))
+    }
+}
+
+impl fmt::Display for ServerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.code, self.message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::iter::FromIterator;
+
+    use super::*;
+
+    #[test]
+    fn parse_code() {
+        let error = ServerError::new("Neo.ClientError.Statement.SyntaxError", "Invalid input");
+        assert_eq!(error.classification(), Some(Classification::ClientError));
+        assert_eq!(error.category(), Some("Statement"));
+        assert_eq!(error.title(), Some("SyntaxError"));
+        assert_eq!(
+            error.to_string(),
+            "Neo.ClientError.Statement.SyntaxError: Invalid input"
+        );
+
+        for code in &[
+            "",
+            "Neo.ClientError.Statement",
+            "Neo.UnknownError.A.B",
+            "Foo.ClientError.A.B",
+        ] {
+            let error = ServerError::new(*code, "");
+            assert_eq!(error.code(), *code);
+            assert_eq!(error.classification(), None);
+            assert_eq!(error.category(), None);
+            assert_eq!(error.title(), None);
+        }
+    }
+
+    #[test]
+    fn from_failure() {
+        let failure = Failure::new(HashMap::from_iter(vec![
+            (
+                "code".to_string(),
+                Value::from("Neo.TransientError.General.DatabaseUnavailable"),
+            ),
+            ("message".to_string(), Value::from("Database unavailable")),
+        ]));
+        let error = ServerError::from(failure.clone());
+        assert_eq!(error.classification(), Some(Classification::TransientError));
+        assert_eq!(error.message(), "Database unavailable");
+        assert!(matches!(Error::from(failure), Error::ServerFailure(e) if e == error));
+
+        let error = ServerError::from(Failure::new(HashMap::new()));
+        assert_eq!(error.code(), "");
+        assert_eq!(error.classification(), None);
+    }
+
+    #[test]
+    fn predicates() {
+        let retryable = [
+            "Neo.TransientError.General.DatabaseUnavailable",
+            "Neo.TransientError.Transaction.DeadlockDetected",
+            "Neo.ClientError.Cluster.NotALeader",
+            "Neo.ClientError.General.ForbiddenOnReadOnlyDatabase",
+        ];
+        let not_retryable = [
+            "Neo.TransientError.Transaction.Terminated",
+            "Neo.TransientError.Transaction.LockClientStopped",
+            "Neo.ClientError.Statement.SyntaxError",
+            "Neo.DatabaseError.General.UnknownError",
+        ];
+        for code in &retryable {
+            assert!(ServerError::new(*code, "").is_retryable(), "{}", code);
+        }
+        for code in &not_retryable {
+            assert!(!ServerError::new(*code, "").is_retryable(), "{}", code);
+        }
+
+        assert!(
+            ServerError::new("Neo.ClientError.Security.Unauthorized", "").is_authentication_error()
+        );
+        assert!(
+            !ServerError::new("Neo.ClientError.Security.Forbidden", "").is_authentication_error()
+        );
+        assert!(
+            ServerError::new("Neo.ClientError.Schema.ConstraintValidationFailed", "")
+                .is_constraint_violation()
+        );
+        assert!(
+            !ServerError::new("Neo.ClientError.Schema.IndexNotFound", "").is_constraint_violation()
+        );
+    }
+}
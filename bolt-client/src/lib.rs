@@ -14,6 +14,7 @@
 //! use bolt_proto::{Message, Value};
 //! use bolt_proto::message::*;
 //! use bolt_proto::value::*;
+//! use bolt_proto::version::*;
 //!
 //! #[tokio::main]
 //! async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -21,10 +22,14 @@
 //!     // protocol version. In this example, all connection/authentication details are
 //!     // stored in environment variables. A domain is optional - including it will
 //!     // create a client that uses a TLS-secured connection.
+//!     # if env::var("BOLT_TEST_ADDR").is_err() {
+//!     #     println!("Skipping test: no server address given");
+//!     #     return Ok(());
+//!     # }
 //!     let mut client = Client::new(env::var("BOLT_TEST_ADDR")?,
 //!                                  env::var("BOLT_TEST_DOMAIN").ok().as_deref()).await?;
 //!     // This example demonstrates usage of the v3 protocol
-//!     let handshake_result = client.handshake(&[3, 0, 0, 0]).await;
+//!     let handshake_result = client.handshake(&[VersionRange::exact(ProtocolVersion::new(3, 0))]).await;
 //!     # if let Err(bolt_client::error::Error::HandshakeFailed) = handshake_result {
 //!     #     println!("Skipping test: client handshake failed");
 //!     #     return Ok(());
@@ -96,13 +101,21 @@
 //! # use bolt_proto::{Message, Value};
 //! # use bolt_proto::message::*;
 //! # use bolt_proto::value::*;
+//! # use bolt_proto::version::*;
 //! #
 //! # #[tokio::main]
 //! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! #     if env::var("BOLT_TEST_ADDR").is_err() {
+//! #         println!("Skipping test: no server address given");
+//! #         return Ok(());
+//! #     }
 //! #     let mut client = Client::new(env::var("BOLT_TEST_ADDR")?,
 //! #                                  env::var("BOLT_TEST_DOMAIN").ok().as_deref()).await?;
 //! // For the handshake we want to support versions 1 and 2 only, preferring version 2.
-//! let handshake_result = client.handshake(&[2, 1, 0, 0]).await;
+//! let handshake_result = client.handshake(&[
+//!     VersionRange::exact(ProtocolVersion::new(2, 0)),
+//!     VersionRange::exact(ProtocolVersion::new(1, 0)),
+//! ]).await;
 //!     # if let Err(bolt_client::error::Error::HandshakeFailed) = handshake_result {
 //!     #     println!("Skipping test: client handshake failed");
 //!     #     return Ok(());
@@ -146,27 +159,50 @@
 //!     # Ok(())
 //! # }
 //! ```
+//! Bolt v4.3 and later servers also understand proposals covering a range of minor versions. For example, to support
+//! versions 4.0 through 4.4, while still allowing older servers to pick version 4.0 or 3:
+//! ```no_run
+//! # use std::env;
+//! #
+//! # use bolt_client::Client;
+//! # use bolt_proto::version::*;
+//! #
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! #     let mut client = Client::new(env::var("BOLT_TEST_ADDR")?,
+//! #                                  env::var("BOLT_TEST_DOMAIN").ok().as_deref()).await?;
+//! client.handshake(&[
+//!     VersionRange::new(ProtocolVersion::new(4, 4), 3),
+//!     VersionRange::exact(ProtocolVersion::new(4, 0)),
+//!     VersionRange::exact(ProtocolVersion::new(3, 0)),
+//! ]).await?;
+//! // The negotiated version is available after the handshake
+//! let version = client.version().unwrap();
+//! println!("Using Bolt v{}.{}", version.major(), version.minor());
+//!     # Ok(())
+//! # }
+//! ```
+//!
 //! See the documentation of the `Client` struct for information on transaction management, error handling, and more.
 #[doc(inline)]
 pub use self::client::Client;
+#[doc(inline)]
+pub use self::record_stream::RecordStream;
 
+// Allows the derive macros, which refer to ::bolt_client, to be used inside this crate
+extern crate self as bolt_client;
+
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub mod bookmarks;
 pub mod client;
+pub mod config;
 pub mod error;
+pub mod mapping;
+mod record_stream;
+pub mod routing;
+pub mod session;
 mod stream;
-
-#[doc(hidden)]
-#[macro_export]
-macro_rules! skip_if_handshake_failed {
-    ($var:expr) => {
-        if let ::std::result::Result::Err(crate::error::Error::HandshakeFailed) = $var {
-            println!("Skipping test: client handshake failed");
-            return;
-        }
-    };
-    ($var:expr, $ret:expr) => {
-        if let ::std::result::Result::Err(crate::error::Error::HandshakeFailed) = $var {
-            println!("Skipping test: client handshake failed");
-            return $ret;
-        }
-    };
-}
+pub mod tls;
+#[cfg(feature = "websocket")]
+mod websocket;
@@ -0,0 +1,258 @@
+//! Conversion of records and nodes into user-defined structs.
+//!
+//! The [`FromRecord`](FromRecord) and [`FromNode`](FromNode) traits can be derived for structs with named fields.
+//! Each field is read from the record column or node property with the same name, and converted using the
+//! `TryFrom<Value>` impls provided by bolt-proto. Fields can be customized with `#[bolt(...)]` attributes:
+//! - `rename = "name"`: read from the given column or property instead
+//! - `default`: use `Default::default()` if the column or property is missing
+//! - `node` (records only): convert the column with [`FromNode`](FromNode)
+//! - `id` (nodes only): use the node identity
+//! - `labels` (nodes only): use the node labels
+//!
+//! Fields of type `Option<T>` are set to `None` if the column or property is missing or null. When deriving
+//! `FromNode`, `#[bolt(label = "...")]` can be added to the struct to require the node to have a label.
+//!
+//! # Example
+//! ```
+//! use std::collections::HashMap;
+//! use std::iter::FromIterator;
+//!
+//! use bolt_client::mapping::{FromNode, FromRecord};
+//! use bolt_proto::message::Record;
+//! use bolt_proto::value::Node;
+//! use bolt_proto::Value;
+//!
+//! #[derive(FromNode)]
+//! #[bolt(label = "Language")]
+//! struct Language {
+//!     #[bolt(id)]
+//!     id: i64,
+//!     name: String,
+//!     #[bolt(rename = "first_appeared")]
+//!     year: Option<i64>,
+//! }
+//!
+//! #[derive(FromRecord)]
+//! struct Row {
+//!     #[bolt(node)]
+//!     language: Language,
+//!     users: i64,
+//! }
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let node = Node::new(
+//!     1,
+//!     vec!["Language".to_string()],
+//!     HashMap::from_iter(vec![("name".to_string(), Value::from("Rust"))]),
+//! );
+//! // The field names are returned by the server in response to RUN
+//! let fields = vec!["language".to_string(), "users".to_string()];
+//! let row = Row::from_record(Record::new(vec![Value::from(node), Value::from(100_i8)]), &fields)?;
+//! assert_eq!(row.language.name, "Rust");
+//! assert_eq!(row.language.year, None);
+//! assert_eq!(row.users, 100);
+//! # Ok(())
+//! # }
+//! ```
+use bolt_proto::message::Record;
+use bolt_proto::value::Node;
+
+pub use bolt_client_derive::{FromNode, FromRecord};
+
+use crate::error::*;
+
+/// A type that can be created from a [`Record`](bolt_proto::message::Record), given the names of its fields.
+pub trait FromRecord: Sized {
+    fn from_record(record: Record, fields: &[String]) -> Result<Self>;
+}
+
+/// A type that can be created from a [`Node`](bolt_proto::value::Node).
+pub trait FromNode: Sized {
+    fn from_node(node: Node) -> Result<Self>;
+}
+
+// Used by the derive macros
+#[doc(hidden)]
+pub mod __private {
+    use std::collections::HashMap;
+    use std::convert::{TryFrom, TryInto};
+
+    pub use bolt_proto::message::Record;
+    pub use bolt_proto::value::Node;
+    pub use bolt_proto::Value;
+
+    use crate::error::*;
+    use crate::mapping::FromNode;
+
+    pub fn columns(record: Record, fields: &[String]) -> HashMap<String, Value> {
+        fields
+            .iter()
+            .cloned()
+            .zip(record.fields().iter().cloned())
+            .collect()
+    }
+
+    pub fn convert<T>(value: Value, field: &str) -> Result<T>
+    where
+        Value: TryInto<T, Error = bolt_proto::error::Error>,
+    {
+        value
+            .try_into()
+            .map_err(|error| invalid(field, error.into()))
+    }
+
+    pub fn convert_node<T: FromNode>(value: Value, field: &str) -> Result<T> {
+        Node::try_from(value)
+            .map_err(Error::from)
+            .and_then(T::from_node)
+            .map_err(|error| invalid(field, error))
+    }
+
+    pub fn check_label(node: &Node, label: &str) -> Result<()> {
+        if node.labels().iter().any(|l| l == label) {
+            Ok(())
+        } else {
+            Err(Error::MissingLabel(label.to_string()))
+        }
+    }
+
+    pub fn missing(field: &str) -> Error {
+        Error::MissingField(field.to_string())
+    }
+
+    fn invalid(field: &str, error: Error) -> Error {
+        Error::InvalidField {
+            field: field.to_string(),
+            source: Box::new(error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::iter::FromIterator;
+
+    use bolt_proto::Value;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, FromNode)]
+    #[bolt(label = "Person")]
+    struct Person {
+        #[bolt(id)]
+        id: i64,
+        #[bolt(labels)]
+        labels: Vec<String>,
+        name: String,
+        #[bolt(rename = "years")]
+        age: i64,
+        nickname: Option<String>,
+        #[bolt(default)]
+        friends: Vec<String>,
+    }
+
+    #[derive(Debug, PartialEq, FromRecord)]
+    struct Row {
+        #[bolt(node)]
+        person: Person,
+        #[bolt(rename = "count(*)")]
+        count: i64,
+        score: Option<f64>,
+    }
+
+    fn person_node(properties: Vec<(&str, Value)>) -> Node {
+        Node::new(
+            7,
+            vec!["Person".to_string(), "Admin".to_string()],
+            HashMap::from_iter(properties.into_iter().map(|(k, v)| (k.to_string(), v))),
+        )
+    }
+
+    fn alice() -> Node {
+        person_node(vec![
+            ("name", Value::from("Alice")),
+            ("years", Value::from(42_i8)),
+            ("nickname", Value::Null),
+        ])
+    }
+
+    fn fields() -> Vec<String> {
+        vec!["person".to_string(), "count(*)".to_string()]
+    }
+
+    #[test]
+    fn from_node() {
+        assert_eq!(
+            Person::from_node(alice()).unwrap(),
+            Person {
+                id: 7,
+                labels: vec!["Person".to_string(), "Admin".to_string()],
+                name: "Alice".to_string(),
+                age: 42,
+                nickname: None,
+                friends: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn from_node_missing_label() {
+        let node = Node::new(
+            7,
+            vec![],
+            HashMap::from_iter(vec![("name".to_string(), Value::from("Alice"))]),
+        );
+        assert!(matches!(
+            Person::from_node(node),
+            Err(Error::MissingLabel(label)) if label == "Person"
+        ));
+    }
+
+    #[test]
+    fn from_node_missing_property() {
+        let node = person_node(vec![("name", Value::from("Alice"))]);
+        assert!(matches!(
+            Person::from_node(node),
+            Err(Error::MissingField(field)) if field == "years"
+        ));
+    }
+
+    #[test]
+    fn from_node_invalid_property() {
+        let node = person_node(vec![
+            ("name", Value::from("Alice")),
+            ("years", Value::from("forty-two")),
+        ]);
+        assert!(matches!(
+            Person::from_node(node),
+            Err(Error::InvalidField { field, .. }) if field == "years"
+        ));
+    }
+
+    #[test]
+    fn from_record() {
+        let record = Record::new(vec![Value::from(alice()), Value::from(3_i8)]);
+        let row = Row::from_record(record, &fields()).unwrap();
+        assert_eq!(row.person.name, "Alice");
+        assert_eq!(row.count, 3);
+        assert_eq!(row.score, None);
+    }
+
+    #[test]
+    fn from_record_missing_column() {
+        let record = Record::new(vec![Value::from(alice())]);
+        assert!(matches!(
+            Row::from_record(record, &fields()[..1]),
+            Err(Error::MissingField(field)) if field == "count(*)"
+        ));
+    }
+
+    #[test]
+    fn from_record_invalid_node() {
+        let record = Record::new(vec![Value::from(1_i8), Value::from(3_i8)]);
+        let error = Row::from_record(record, &fields()).unwrap_err();
+        assert!(matches!(&error, Error::InvalidField { field, .. } if field == "person"));
+        assert!(error.to_string().contains("\"person\""));
+    }
+}
@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use tokio::stream::Stream;
+
+use bolt_proto::message::*;
+use bolt_proto::{Message, Value};
+
+use crate::error::*;
+use crate::Client;
+
+type PendingMessage<'a> =
+    Pin<Box<dyn Future<Output = (&'a mut Client, Result<Message>)> + Send + 'a>>;
+
+/// A [`Stream`](tokio::stream::Stream) of the records in a result stream, yielding each record as soon as it has been
+/// received from the server. Obtained from [`Client::pull_all_stream`](Client::pull_all_stream) or
+/// [`Client::pull_stream`](Client::pull_stream).
+///
+/// Once the stream has ended, the summary message (`SUCCESS`, `FAILURE`, or `IGNORED`) sent by the server is available
+/// from [`summary`](RecordStream::summary). The client is borrowed for as long as the stream exists, and should not
+/// be used for anything else until the result stream has been fully consumed. If the stream is dropped early, the
+/// client must be reset before it can be used again.
+pub struct RecordStream<'a> {
+    client: Option<&'a mut Client>,
+    pending: Option<PendingMessage<'a>>,
+    fetch_size: Option<i64>,
+    summary: Option<Message>,
+    finished: bool,
+}
+
+impl<'a> RecordStream<'a> {
+    // A fetch size is only given for Bolt v4 clients, in which case another batch is requested with `PULL` whenever the
+    // server reports that more records are available.
+    pub(crate) fn new(client: &'a mut Client, fetch_size: Option<i64>) -> Self {
+        Self {
+            client: Some(client),
+            pending: None,
+            fetch_size,
+            summary: None,
+            finished: false,
+        }
+    }
+
+    /// Get the summary message sent by the server at the end of the result stream, or `None` if the stream has not
+    /// ended yet.
+    pub fn summary(&self) -> Option<&Message> {
+        self.summary.as_ref()
+    }
+
+    /// Consume the stream, returning the summary message sent by the server, if the stream has ended.
+    pub fn into_summary(self) -> Option<Message> {
+        self.summary
+    }
+
+    fn read_next(client: &'a mut Client) -> PendingMessage<'a> {
+        Box::pin(async move {
+            let result = client.read_message().await;
+            (client, result)
+        })
+    }
+
+    fn pull_next(client: &'a mut Client, fetch_size: i64) -> PendingMessage<'a> {
+        Box::pin(async move {
+            let pull_msg = Pull::new(HashMap::new()).with_n(fetch_size);
+            let result = match client.send_message(Message::Pull(pull_msg)).await {
+                Ok(()) => client.read_message().await,
+                Err(err) => Err(err),
+            };
+            (client, result)
+        })
+    }
+}
+
+impl Stream for RecordStream<'_> {
+    type Item = Result<Record>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if this.finished {
+                return Poll::Ready(None);
+            }
+
+            let mut pending = match (this.pending.take(), this.client.take()) {
+                (Some(pending), _) => pending,
+                (None, Some(client)) => Self::read_next(client),
+                (None, None) => {
+                    unreachable!("either a client or a pending message must be present")
+                }
+            };
+            let (client, result) = match pending.as_mut().poll(cx) {
+                Poll::Ready(output) => output,
+                Poll::Pending => {
+                    this.pending = Some(pending);
+                    return Poll::Pending;
+                }
+            };
+
+            match result {
+                Ok(Message::Record(record)) => {
+                    this.client = Some(client);
+                    return Poll::Ready(Some(Ok(record)));
+                }
+                Ok(Message::Success(success))
+                    if this.fetch_size.is_some()
+                        && success.metadata().get("has_more") == Some(&Value::from(true)) =>
+                {
+                    this.pending = Some(Self::pull_next(client, this.fetch_size.unwrap()));
+                }
+                Ok(summary) => {
+                    this.finished = true;
+                    this.summary = Some(summary);
+                    return Poll::Ready(None);
+                }
+                Err(err) => {
+                    this.finished = true;
+                    return Poll::Ready(Some(Err(err)));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,817 @@
+//! Client-side routing for Neo4j clusters.
+//!
+//! A [`Router`](Router) keeps track of the members of a cluster using routing tables retrieved from the cluster
+//! itself. Each routing table lists the members that may be used for reading, writing, and routing (i.e. fetching new
+//! routing tables) for a single database, and is only valid for a limited time. The router refreshes routing tables as
+//! they expire, and hands out initialized [`Client`](crate::Client)s connected to a suitable member for the requested
+//! [`AccessMode`](AccessMode).
+//!
+//! Routing is only supported for Bolt v3 and later.
+use std::collections::HashMap;
+use std::convert::{TryFrom, TryInto};
+use std::iter::FromIterator;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use bolt_proto::message::*;
+use bolt_proto::version::VersionRange;
+use bolt_proto::{Message, ProtocolVersion, Value};
+
+use crate::config::ConnectConfig;
+use crate::error::*;
+use crate::Client;
+
+pub(crate) const DEFAULT_VERSIONS: &[VersionRange] = &[
+    VersionRange::new(ProtocolVersion::new(4, 4), 4),
+    VersionRange::exact(ProtocolVersion::new(4, 0)),
+    VersionRange::exact(ProtocolVersion::new(3, 0)),
+];
+
+/// The kind of work a connection will be used for, which determines the cluster members it may connect to.
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
+pub enum AccessMode {
+    Read,
+    Write,
+}
+
+/// The members of a cluster that serve a single database, as reported by the cluster.
+#[derive(Debug, Clone)]
+pub struct RoutingTable {
+    database: Option<String>,
+    routers: Vec<String>,
+    readers: Vec<String>,
+    writers: Vec<String>,
+    expires_at: Instant,
+}
+
+impl RoutingTable {
+    pub fn new(
+        database: Option<String>,
+        routers: Vec<String>,
+        readers: Vec<String>,
+        writers: Vec<String>,
+        ttl: Duration,
+    ) -> Self {
+        Self {
+            database,
+            routers,
+            readers,
+            writers,
+            expires_at: Instant::now() + ttl,
+        }
+    }
+
+    // Parse the `ttl` and `servers` entries returned from the cluster, either in the `rt` metadata of a ROUTE response
+    // or in the record returned from a routing table procedure.
+    fn from_response(database: Option<String>, ttl: Value, servers: Value) -> Result<Self> {
+        // Clamp the TTL so we don't overflow when calculating the expiry time
+        let ttl = i64::try_from(ttl)?.max(0).min(u32::MAX as i64) as u64;
+        let mut table = Self::new(database, vec![], vec![], vec![], Duration::from_secs(ttl));
+
+        let servers: Vec<Value> = servers.try_into()?;
+        for server in servers {
+            let mut server: HashMap<String, Value> = server.try_into()?;
+            let role = server
+                .remove("role")
+                .ok_or_else(|| Error::InvalidRoutingTable("missing server role".to_string()))?;
+            let addresses: Vec<String> = server
+                .remove("addresses")
+                .ok_or_else(|| Error::InvalidRoutingTable("missing server addresses".to_string()))?
+                .try_into()?;
+            match String::try_from(role)?.as_str() {
+                "ROUTE" => table.routers.extend(addresses),
+                "READ" => table.readers.extend(addresses),
+                "WRITE" => table.writers.extend(addresses),
+                other => {
+                    return Err(Error::InvalidRoutingTable(format!(
+                        "unknown server role: {}",
+                        other
+                    )))
+                }
+            }
+        }
+        Ok(table)
+    }
+
+    /// Get the name of the database this table applies to, or `None` for the default database.
+    pub fn database(&self) -> Option<&str> {
+        self.database.as_deref()
+    }
+
+    pub fn routers(&self) -> &[String] {
+        &self.routers
+    }
+
+    pub fn readers(&self) -> &[String] {
+        &self.readers
+    }
+
+    pub fn writers(&self) -> &[String] {
+        &self.writers
+    }
+
+    /// Get the addresses of the members that may be used for the given access mode.
+    pub fn servers(&self, mode: AccessMode) -> &[String] {
+        match mode {
+            AccessMode::Read => &self.readers,
+            AccessMode::Write => &self.writers,
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        Instant::now() >= self.expires_at
+    }
+
+    /// Check whether this table can still be used for the given access mode, i.e. it has not expired, and it contains
+    /// at least one router and one member for the access mode.
+    pub fn is_fresh_for(&self, mode: AccessMode) -> bool {
+        !self.is_expired() && !self.routers.is_empty() && !self.servers(mode).is_empty()
+    }
+
+    /// Remove a member from this table entirely, e.g. after failing to connect to it.
+    pub fn forget(&mut self, address: &str) {
+        self.routers.retain(|a| a != address);
+        self.readers.retain(|a| a != address);
+        self.writers.retain(|a| a != address);
+    }
+
+    /// Remove a member from the writers in this table, e.g. after it reports that it is no longer the leader.
+    pub fn forget_writer(&mut self, address: &str) {
+        self.writers.retain(|a| a != address);
+    }
+}
+
+/// Routes connections to the members of a Neo4j cluster, based on the routing tables of each database.
+#[derive(Debug)]
+pub struct Router {
+    initial_address: String,
+    tls: bool,
+    metadata: HashMap<String, Value>,
+    context: HashMap<String, Value>,
+    versions: Vec<VersionRange>,
+    tables: Mutex<HashMap<Option<String>, RoutingTable>>,
+    next_server: AtomicUsize,
+    // Used to open connections to members instead of the TLS flag, if the router was created from a config
+    config: Option<ConnectConfig>,
+}
+
+impl Router {
+    /// Create a new router, using the provided address to retrieve the first routing table. If `tls` is set, all
+    /// connections will be secured with TLS, using the host part of each member's address as its domain.
+    ///
+    /// The metadata is sent in a `HELLO` message to initialize every connection, and so must contain a `user_agent`
+    /// as well as any authentication details.
+    pub fn new(
+        initial_address: impl Into<String>,
+        tls: bool,
+        metadata: HashMap<String, impl Into<Value>>,
+    ) -> Self {
+        let initial_address = initial_address.into();
+        Self {
+            context: HashMap::from_iter(vec![(
+                "address".to_string(),
+                Value::from(initial_address.as_str()),
+            )]),
+            initial_address,
+            tls,
+            metadata: metadata.into_iter().map(|(k, v)| (k, v.into())).collect(),
+            versions: DEFAULT_VERSIONS.to_vec(),
+            tables: Mutex::new(HashMap::new()),
+            next_server: AtomicUsize::new(0),
+            config: None,
+        }
+    }
+
+    /// Create a new router from a connection configuration, using the configured server to retrieve the first
+    /// routing table. Connections to every member use the configured TLS options, timeouts, protocol versions, user
+    /// agent, and authentication details, and the configured routing context is sent to the cluster.
+    pub fn from_config(config: ConnectConfig) -> Self {
+        let mut router = Self::new(config.address(), config.tls().is_some(), config.metadata())
+            .with_routing_context(config.routing_context().clone())
+            .with_versions(config.versions());
+        router.config = Some(config);
+        router
+    }
+
+    /// Set the routing context sent to the cluster when retrieving routing tables. This is usually the set of query
+    /// parameters from a `neo4j://` URI. The `address` key is always set to the initial address of the router.
+    pub fn with_routing_context(mut self, context: HashMap<String, impl Into<Value>>) -> Self {
+        let address = self.context.remove("address").unwrap();
+        self.context = context.into_iter().map(|(k, v)| (k, v.into())).collect();
+        self.context.insert("address".to_string(), address);
+        self
+    }
+
+    /// Set the protocol versions proposed when connecting to cluster members. By default, versions 4.4 through 4.0 and
+    /// version 3 are proposed.
+    pub fn with_versions(mut self, versions: &[VersionRange]) -> Self {
+        self.versions = versions.to_vec();
+        self
+    }
+
+    /// Get an initialized client connected to a cluster member suitable for the given access mode and database. If no
+    /// database is given, the default database is used.
+    ///
+    /// Members are chosen in a round-robin fashion. Members that cannot be connected to are removed from the routing
+    /// table, and the next member is tried instead. See [`routing_table`](Router::routing_table) for how the bookmarks
+    /// are used.
+    pub async fn acquire(
+        &self,
+        mode: AccessMode,
+        database: Option<&str>,
+        bookmarks: &[String],
+    ) -> Result<Client> {
+        let (_address, client) = self.acquire_member(mode, database, bookmarks).await?;
+        Ok(client)
+    }
+
+    // Like acquire, but also returns the address of the member the client is connected to
+    pub(crate) async fn acquire_member(
+        &self,
+        mode: AccessMode,
+        database: Option<&str>,
+        bookmarks: &[String],
+    ) -> Result<(String, Client)> {
+        let table = self.routing_table(mode, database, bookmarks).await?;
+        let servers = table.servers(mode);
+        let start = self.next_server.fetch_add(1, Ordering::Relaxed);
+        for i in 0..servers.len() {
+            let address = &servers[(start + i) % servers.len()];
+            match self.connect(address).await {
+                Ok(client) => return Ok((address.clone(), client)),
+                Err(_) => self.forget(address),
+            }
+        }
+        Err(Error::NoServersAvailable(mode))
+    }
+
+    /// Get the routing table for the given database, retrieving a new one from the cluster if the current table
+    /// cannot be used for the given access mode. Bookmarks are passed along when retrieving a routing table (Bolt
+    /// v4.3+), so that the cluster knows about databases created by the bookmarked transactions.
+    pub async fn routing_table(
+        &self,
+        mode: AccessMode,
+        database: Option<&str>,
+        bookmarks: &[String],
+    ) -> Result<RoutingTable> {
+        let key = database.map(String::from);
+        if let Some(table) = self.tables.lock().unwrap().get(&key) {
+            if table.is_fresh_for(mode) {
+                return Ok(table.clone());
+            }
+        }
+
+        let table = self.refresh(database, bookmarks).await?;
+        if table.servers(mode).is_empty() {
+            return Err(Error::NoServersAvailable(mode));
+        }
+        Ok(table)
+    }
+
+    /// Remove a member from all routing tables, e.g. after a connection to it has failed.
+    pub fn forget(&self, address: &str) {
+        for table in self.tables.lock().unwrap().values_mut() {
+            table.forget(address);
+        }
+    }
+
+    /// Remove a member from the writers for the given database, e.g. after it reports that it is no longer the leader.
+    pub fn forget_writer(&self, database: Option<&str>, address: &str) {
+        if let Some(table) = self
+            .tables
+            .lock()
+            .unwrap()
+            .get_mut(&database.map(String::from))
+        {
+            table.forget_writer(address);
+        }
+    }
+
+    /// Get an initialized client connected to the member at the given address.
+    pub async fn connect(&self, address: &str) -> Result<Client> {
+        // Use the host part of the address as the TLS domain
+        let host = host(address);
+        let mut client = match &self.config {
+            Some(config) => config.open_at(address, &host).await?,
+            None => Client::new(address, if self.tls { Some(&host) } else { None }).await?,
+        };
+        client.handshake(&self.versions).await?;
+
+        let mut metadata = self.metadata.clone();
+        if client.version().unwrap() >= ProtocolVersion::new(4, 1) {
+            metadata.insert("routing".to_string(), Value::from(self.context.clone()));
+        }
+        match client.hello(metadata).await? {
+            Message::Success(_) => Ok(client),
+            other => Err(Error::InitFailed(other)),
+        }
+    }
+
+    // Retrieve a new routing table from the known routers for the database, falling back to the initial address.
+    async fn refresh(&self, database: Option<&str>, bookmarks: &[String]) -> Result<RoutingTable> {
+        let key = database.map(String::from);
+        let mut routers = match self.tables.lock().unwrap().get(&key) {
+            Some(table) => table.routers.clone(),
+            None => vec![],
+        };
+        if !routers.contains(&self.initial_address) {
+            routers.push(self.initial_address.clone());
+        }
+
+        for router in routers {
+            let table = match self.connect(&router).await {
+                Ok(mut client) => {
+                    let table = self
+                        .fetch_routing_table(&mut client, database, bookmarks)
+                        .await;
+                    // The connection is no longer needed, and closing it is best-effort
+                    let _ = client.goodbye().await;
+                    table
+                }
+                Err(err) => Err(err),
+            };
+            match table {
+                Ok(table) => {
+                    self.tables.lock().unwrap().insert(key, table.clone());
+                    return Ok(table);
+                }
+                Err(_) => self.forget(&router),
+            }
+        }
+        Err(Error::RoutingTableUnavailable(key))
+    }
+
+    async fn fetch_routing_table(
+        &self,
+        client: &mut Client,
+        database: Option<&str>,
+        bookmarks: &[String],
+    ) -> Result<RoutingTable> {
+        let version = client.version().unwrap();
+        if version >= ProtocolVersion::new(4, 3) {
+            let response = client
+                .route(
+                    self.context.clone(),
+                    bookmarks.to_vec(),
+                    database.map(String::from),
+                )
+                .await?;
+            let mut rt: HashMap<String, Value> = Success::try_from(response)?
+                .metadata()
+                .get("rt")
+                .cloned()
+                .ok_or_else(|| Error::InvalidRoutingTable("missing rt".to_string()))?
+                .try_into()?;
+            let ttl = rt
+                .remove("ttl")
+                .ok_or_else(|| Error::InvalidRoutingTable("missing ttl".to_string()))?;
+            let servers = rt
+                .remove("servers")
+                .ok_or_else(|| Error::InvalidRoutingTable("missing servers".to_string()))?;
+            return RoutingTable::from_response(database.map(String::from), ttl, servers);
+        }
+
+        // Older servers provide the routing table through a procedure instead
+        let (response, records) = if version.major() >= 4 {
+            let parameters = HashMap::from_iter(vec![
+                ("context".to_string(), Value::from(self.context.clone())),
+                ("database".to_string(), Value::from(database)),
+            ]);
+            let metadata = HashMap::from_iter(vec![("db".to_string(), Value::from("system"))]);
+            let response = client
+                .run_with_metadata(
+                    "CALL dbms.routing.getRoutingTable($context, $database);".to_string(),
+                    Some(parameters),
+                    Some(metadata),
+                )
+                .await?;
+            Success::try_from(response)?;
+            client
+                .pull(Some(HashMap::from_iter(vec![(
+                    "n".to_string(),
+                    Value::from(-1),
+                )])))
+                .await?
+        } else {
+            let parameters = HashMap::from_iter(vec![(
+                "context".to_string(),
+                Value::from(self.context.clone()),
+            )]);
+            let response = client
+                .run_with_metadata(
+                    "CALL dbms.cluster.routing.getRoutingTable($context);".to_string(),
+                    Some(parameters),
+                    None,
+                )
+                .await?;
+            Success::try_from(response)?;
+            client.pull_all().await?
+        };
+        Success::try_from(response)?;
+
+        let record = records
+            .into_iter()
+            .next()
+            .ok_or_else(|| Error::InvalidRoutingTable("missing record".to_string()))?;
+        match record.fields() {
+            [ttl, servers] => RoutingTable::from_response(
+                database.map(String::from),
+                ttl.clone(),
+                servers.clone(),
+            ),
+            _ => Err(Error::InvalidRoutingTable(format!(
+                "unexpected record: {:?}",
+                record
+            ))),
+        }
+    }
+}
+
+// Get the host part of a `host:port` address, without the brackets around an IPv6 address
+fn host(address: &str) -> String {
+    match address.parse::<SocketAddr>() {
+        Ok(address) => address.ip().to_string(),
+        Err(_) => address
+            .rsplitn(2, ':')
+            .last()
+            .unwrap_or(address)
+            .to_string(),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::Arc;
+
+    use bytes::Bytes;
+    use tokio::io::BufStream;
+    use tokio::net::TcpListener;
+    use tokio::prelude::*;
+
+    use super::*;
+
+    pub(crate) type Responder = Arc<dyn Fn(Message) -> Vec<Message> + Send + Sync>;
+
+    // Start a server that accepts any handshake with the given version, and answers each message using the responder.
+    pub(crate) async fn stub_server(version: ProtocolVersion, responder: Responder) -> String {
+        stub_server_at("127.0.0.1:0", version, responder).await
+    }
+
+    async fn stub_server_at(bind: &str, version: ProtocolVersion, responder: Responder) -> String {
+        let mut listener = TcpListener::bind(bind).await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let responder = Arc::clone(&responder);
+                tokio::spawn(async move {
+                    let mut stream = BufStream::new(stream);
+                    let mut handshake = [0; 20];
+                    stream.read_exact(&mut handshake).await.unwrap();
+                    stream.write_u32(version.into()).await.unwrap();
+                    stream.flush().await.unwrap();
+                    while let Ok(message) = Message::from_stream(&mut stream).await {
+                        if message == Message::Goodbye {
+                            return;
+                        }
+                        for response in responder(message) {
+                            let chunks: Vec<Bytes> = response.try_into().unwrap();
+                            for mut chunk in chunks {
+                                stream.write_buf(&mut chunk).await.unwrap();
+                            }
+                        }
+                        stream.flush().await.unwrap();
+                    }
+                });
+            }
+        });
+        address
+    }
+
+    // A cluster member that returns its name from any query
+    async fn member(version: ProtocolVersion, name: &'static str) -> String {
+        member_at("127.0.0.1:0", version, name).await
+    }
+
+    async fn member_at(bind: &str, version: ProtocolVersion, name: &'static str) -> String {
+        stub_server_at(
+            bind,
+            version,
+            Arc::new(move |message| match message {
+                Message::Hello(_) | Message::RunWithMetadata(_) => {
+                    vec![Message::Success(Success::new(HashMap::new()))]
+                }
+                Message::Pull(_) => vec![
+                    Message::Record(Record::new(vec![Value::from(name)])),
+                    Message::Success(Success::new(HashMap::new())),
+                ],
+                other => panic!("unexpected message: {:?}", other),
+            }),
+        )
+        .await
+    }
+
+    pub(crate) fn servers(routers: &[&str], readers: &[&str], writers: &[&str]) -> Value {
+        let server = |role: &str, addresses: &[&str]| {
+            Value::from(HashMap::from_iter(vec![
+                ("role", Value::from(role)),
+                ("addresses", Value::from(addresses.to_vec())),
+            ]))
+        };
+        Value::from(vec![
+            server("ROUTE", routers),
+            server("READ", readers),
+            server("WRITE", writers),
+        ])
+    }
+
+    // A cluster member that responds to ROUTE messages, counting the number of routing tables requested
+    async fn router(
+        ttl: i64,
+        readers: Vec<String>,
+        writers: Vec<String>,
+        route_count: Arc<AtomicUsize>,
+    ) -> String {
+        versioned_router(
+            ProtocolVersion::new(4, 3),
+            ttl,
+            readers,
+            writers,
+            route_count,
+        )
+        .await
+    }
+
+    async fn versioned_router(
+        version: ProtocolVersion,
+        ttl: i64,
+        readers: Vec<String>,
+        writers: Vec<String>,
+        route_count: Arc<AtomicUsize>,
+    ) -> String {
+        stub_server(
+            version,
+            Arc::new(move |message| {
+                // ROUTE carries the database as its last field in v4.3, and in a metadata map from v4.4
+                let (context, db) = match message {
+                    Message::Hello(hello) => {
+                        assert!(hello.metadata().contains_key("routing"));
+                        return vec![Message::Success(Success::new(HashMap::new()))];
+                    }
+                    Message::Route(route) if version == ProtocolVersion::new(4, 3) => {
+                        (route.context().clone(), route.db().map(String::from))
+                    }
+                    Message::RouteWithMetadata(route) if version >= ProtocolVersion::new(4, 4) => (
+                        route.context().clone(),
+                        route
+                            .metadata()
+                            .get("db")
+                            .map(|db| String::try_from(db.clone()).unwrap()),
+                    ),
+                    other => panic!("unexpected message: {:?}", other),
+                };
+                route_count.fetch_add(1, Ordering::SeqCst);
+                let address = String::try_from(context["address"].clone()).unwrap();
+                let readers: Vec<&str> = readers.iter().map(String::as_str).collect();
+                let writers: Vec<&str> = writers.iter().map(String::as_str).collect();
+                let rt = HashMap::from_iter(vec![
+                    ("ttl", Value::from(ttl)),
+                    ("db", Value::from(db.as_deref().unwrap_or("neo4j"))),
+                    ("servers", servers(&[&address], &readers, &writers)),
+                ]);
+                vec![Message::Success(Success::new(HashMap::from_iter(vec![(
+                    "rt".to_string(),
+                    Value::from(rt),
+                )])))]
+            }),
+        )
+        .await
+    }
+
+    async fn query_name(client: &mut Client) -> Value {
+        client
+            .run_with_metadata("RETURN $name;".to_string(), None, None)
+            .await
+            .unwrap();
+        let (_response, records) = client.pull(None).await.unwrap();
+        records[0].fields()[0].clone()
+    }
+
+    fn new_router(address: String) -> Router {
+        Router::new(
+            address,
+            false,
+            HashMap::from_iter(vec![("user_agent".to_string(), "bolt-client/X.Y.Z")]),
+        )
+    }
+
+    // Get an address that nothing is listening on
+    async fn unreachable_address() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        listener.local_addr().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn acquire_by_access_mode() {
+        let reader = member(ProtocolVersion::new(4, 3), "reader").await;
+        let writer = member(ProtocolVersion::new(4, 3), "writer").await;
+        let route_count = Arc::new(AtomicUsize::new(0));
+        let address = router(300, vec![reader], vec![writer], Arc::clone(&route_count)).await;
+        let router = new_router(address);
+
+        let mut client = router.acquire(AccessMode::Read, None, &[]).await.unwrap();
+        assert_eq!(query_name(&mut client).await, Value::from("reader"));
+        let mut client = router.acquire(AccessMode::Write, None, &[]).await.unwrap();
+        assert_eq!(query_name(&mut client).await, Value::from("writer"));
+        // The routing table is reused until it expires
+        assert_eq!(route_count.load(Ordering::SeqCst), 1);
+
+        // Each database has its own routing table
+        let table = router
+            .routing_table(AccessMode::Read, Some("other"), &[])
+            .await
+            .unwrap();
+        assert_eq!(table.database(), Some("other"));
+        assert_eq!(route_count.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn acquire_from_v4_4_router() {
+        let version = ProtocolVersion::new(4, 4);
+        let reader = member(version, "reader").await;
+        let route_count = Arc::new(AtomicUsize::new(0));
+        let address =
+            versioned_router(version, 300, vec![reader], vec![], Arc::clone(&route_count)).await;
+        let router = new_router(address);
+
+        let mut client = router.acquire(AccessMode::Read, None, &[]).await.unwrap();
+        assert_eq!(client.version(), Some(version));
+        assert_eq!(query_name(&mut client).await, Value::from("reader"));
+        let table = router
+            .routing_table(AccessMode::Read, Some("other"), &[])
+            .await
+            .unwrap();
+        assert_eq!(table.database(), Some("other"));
+        assert_eq!(route_count.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn acquire_ipv6_member() {
+        let reader = member_at("[::1]:0", ProtocolVersion::new(4, 3), "reader").await;
+        assert!(reader.starts_with("[::1]:"));
+        let route_count = Arc::new(AtomicUsize::new(0));
+        let address = router(300, vec![reader], vec![], Arc::clone(&route_count)).await;
+        let router = new_router(address);
+
+        let mut client = router.acquire(AccessMode::Read, None, &[]).await.unwrap();
+        assert_eq!(query_name(&mut client).await, Value::from("reader"));
+    }
+
+    #[test]
+    fn host_of_address() {
+        assert_eq!(host("[::1]:7687"), "::1");
+        assert_eq!(host("127.0.0.1:7687"), "127.0.0.1");
+        assert_eq!(host("core-1.example.com:7687"), "core-1.example.com");
+    }
+
+    #[tokio::test]
+    async fn router_from_config() {
+        let reader = member(ProtocolVersion::new(4, 3), "reader").await;
+        let route_count = Arc::new(AtomicUsize::new(0));
+        let address = router(300, vec![reader], vec![], Arc::clone(&route_count)).await;
+        let config = ConnectConfig::from_uri(&format!("neo4j://{}?region=eu", address))
+            .unwrap()
+            .with_read_timeout(Duration::from_secs(5));
+        let router = Router::from_config(config);
+
+        let client = router.acquire(AccessMode::Read, None, &[]).await.unwrap();
+        assert_eq!(client.read_timeout(), Some(Duration::from_secs(5)));
+        assert_eq!(router.context.get("region"), Some(&Value::from("eu")));
+        assert_eq!(
+            router.context.get("address"),
+            Some(&Value::from(address.as_str()))
+        );
+    }
+
+    #[tokio::test]
+    async fn refresh_expired_routing_table() {
+        let reader = member(ProtocolVersion::new(4, 3), "reader").await;
+        let route_count = Arc::new(AtomicUsize::new(0));
+        let address = router(0, vec![reader], vec![], Arc::clone(&route_count)).await;
+        let router = new_router(address);
+
+        router.acquire(AccessMode::Read, None, &[]).await.unwrap();
+        router.acquire(AccessMode::Read, None, &[]).await.unwrap();
+        assert_eq!(route_count.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn no_writers_available() {
+        let reader = member(ProtocolVersion::new(4, 3), "reader").await;
+        let route_count = Arc::new(AtomicUsize::new(0));
+        let address = router(300, vec![reader], vec![], Arc::clone(&route_count)).await;
+        let router = new_router(address);
+
+        assert!(matches!(
+            router.acquire(AccessMode::Write, None, &[]).await,
+            Err(Error::NoServersAvailable(AccessMode::Write))
+        ));
+    }
+
+    #[tokio::test]
+    async fn forget_unreachable_members() {
+        let unreachable = unreachable_address().await;
+        let writer = member(ProtocolVersion::new(4, 3), "writer").await;
+        let route_count = Arc::new(AtomicUsize::new(0));
+        let address = router(
+            300,
+            vec![],
+            vec![unreachable, writer.clone()],
+            Arc::clone(&route_count),
+        )
+        .await;
+        let router = new_router(address);
+
+        let mut client = router.acquire(AccessMode::Write, None, &[]).await.unwrap();
+        assert_eq!(query_name(&mut client).await, Value::from("writer"));
+        let table = router
+            .routing_table(AccessMode::Write, None, &[])
+            .await
+            .unwrap();
+        assert_eq!(table.writers(), &[writer]);
+        assert_eq!(route_count.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn routing_table_unavailable() {
+        let router = new_router(unreachable_address().await);
+        assert!(matches!(
+            router.acquire(AccessMode::Read, Some("neo4j"), &[]).await,
+            Err(Error::RoutingTableUnavailable(Some(database))) if database == "neo4j"
+        ));
+    }
+
+    #[tokio::test]
+    async fn routing_procedure_before_v4_3() {
+        let reader = member(ProtocolVersion::new(4, 0), "reader").await;
+        let table = servers(&["unused:7687"], &[&reader], &[]);
+        let address = stub_server(
+            ProtocolVersion::new(4, 0),
+            Arc::new(move |message| match message {
+                Message::Hello(_) => vec![Message::Success(Success::new(HashMap::new()))],
+                Message::RunWithMetadata(run) => {
+                    assert!(run.statement().contains("dbms.routing.getRoutingTable"));
+                    assert_eq!(run.metadata().get("db"), Some(&Value::from("system")));
+                    vec![Message::Success(Success::new(HashMap::new()))]
+                }
+                Message::Pull(_) => vec![
+                    Message::Record(Record::new(vec![Value::from(300), table.clone()])),
+                    Message::Success(Success::new(HashMap::new())),
+                ],
+                other => panic!("unexpected message: {:?}", other),
+            }),
+        )
+        .await;
+        let router = new_router(address);
+
+        let mut client = router.acquire(AccessMode::Read, None, &[]).await.unwrap();
+        assert_eq!(query_name(&mut client).await, Value::from("reader"));
+        let table = router
+            .routing_table(AccessMode::Read, None, &[])
+            .await
+            .unwrap();
+        assert_eq!(table.routers(), &["unused:7687".to_string()]);
+    }
+
+    #[test]
+    fn routing_table_freshness() {
+        let table = RoutingTable::new(
+            None,
+            vec!["a:1".to_string()],
+            vec!["b:1".to_string()],
+            vec![],
+            Duration::from_secs(300),
+        );
+        assert!(table.is_fresh_for(AccessMode::Read));
+        assert!(!table.is_fresh_for(AccessMode::Write));
+
+        let mut table = RoutingTable::new(
+            None,
+            vec!["a:1".to_string()],
+            vec!["a:1".to_string(), "b:1".to_string()],
+            vec!["a:1".to_string()],
+            Duration::from_secs(0),
+        );
+        assert!(table.is_expired());
+        assert!(!table.is_fresh_for(AccessMode::Read));
+
+        table.forget_writer("a:1");
+        assert_eq!(table.readers().len(), 2);
+        assert!(table.writers().is_empty());
+        table.forget("a:1");
+        assert!(table.routers().is_empty());
+        assert_eq!(table.readers(), &["b:1".to_string()]);
+    }
+}
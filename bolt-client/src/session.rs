@@ -0,0 +1,1109 @@
+//! Managed transactions with automatic retries.
+//!
+//! A [`Session`](Session) runs units of work, called transaction functions, inside explicit transactions. Each
+//! transaction function is given a [`Transaction`](Transaction) handle to run queries with. The transaction is
+//! committed if the function succeeds, and rolled back otherwise. If the transaction fails because of a transient
+//! error reported by the server, or because the connection was lost, the whole transaction function is retried on a
+//! fresh connection, with an exponentially increasing delay between attempts.
+//!
+//! Connections are obtained from a [`Connector`](Connector), such as a [`Router`](crate::routing::Router) for a
+//! cluster, or a [`DirectConnector`](DirectConnector) for a single server. Sessions require Bolt v3 or later.
+//!
+//! Each transaction waits for the last transaction committed by the session, using
+//! [bookmarks](crate::bookmarks). Sessions can also share a [`BookmarkManager`](crate::bookmarks::BookmarkManager)
+//! to read each other's writes.
+//!
+//! # Example
+//! ```no_run
+//! # use std::collections::HashMap;
+//! # use std::iter::FromIterator;
+//! #
+//! use bolt_client::session::{DirectConnector, Session};
+//! use bolt_proto::Value;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let connector = DirectConnector::new(
+//!     "127.0.0.1:7687",
+//!     None,
+//!     HashMap::from_iter(vec![
+//!         ("user_agent", "my-client-name/1.0"),
+//!         ("scheme", "basic"),
+//!         ("principal", "neo4j"),
+//!         ("credentials", "test"),
+//!     ]),
+//! );
+//! let session = Session::new(connector);
+//! let count = session
+//!     .write_transaction(|tx| async move {
+//!         tx.run("CREATE (:Language {name: 'Rust'})", None).await?;
+//!         let result = tx.run("MATCH (n:Language) RETURN count(n)", None).await?;
+//!         Ok(result.records()[0].fields()[0].clone())
+//!     })
+//!     .await?;
+//! assert_eq!(count, Value::from(1_i8));
+//! # Ok(())
+//! # }
+//! ```
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::convert::{TryFrom, TryInto};
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::iter::FromIterator;
+use std::sync::{Arc, Mutex as SyncMutex};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use tokio::sync::Mutex;
+
+use bolt_proto::message::*;
+use bolt_proto::version::VersionRange;
+use bolt_proto::{Message, ProtocolVersion, Value};
+
+use crate::bookmarks::BookmarkManager;
+use crate::config::ConnectConfig;
+use crate::error::*;
+use crate::routing::{AccessMode, Router, DEFAULT_VERSIONS};
+use crate::Client;
+
+const DEFAULT_MAX_RETRY_TIME: Duration = Duration::from_secs(30);
+const INITIAL_RETRY_DELAY: Duration = Duration::from_secs(1);
+const RETRY_DELAY_MULTIPLIER: u32 = 2;
+// Each delay is randomly adjusted by up to this fraction, so that clients failing together don't retry together
+const RETRY_DELAY_JITTER: f64 = 0.2;
+
+/// A source of initialized clients.
+#[async_trait]
+pub trait Connector: Send + Sync {
+    /// Get an initialized client suitable for the given access mode and database, along with the address of the
+    /// server it is connected to. If no database is given, the default database is used. The bookmarks are those the
+    /// next transaction waits for, and may be used to find a server that knows about the database.
+    async fn acquire(
+        &self,
+        mode: AccessMode,
+        database: Option<&str>,
+        bookmarks: &[String],
+    ) -> Result<(String, Client)>;
+
+    /// Called when work on a client acquired for the given database fails, with the address of the server the
+    /// client was connected to. By default, errors are ignored.
+    fn on_error(&self, _address: &str, _database: Option<&str>, _error: &Error) {}
+}
+
+/// Routers stop using members that can't be reached, and writers that report that they are no longer the leader.
+#[async_trait]
+impl Connector for Router {
+    async fn acquire(
+        &self,
+        mode: AccessMode,
+        database: Option<&str>,
+        bookmarks: &[String],
+    ) -> Result<(String, Client)> {
+        self.acquire_member(mode, database, bookmarks).await
+    }
+
+    fn on_error(&self, address: &str, database: Option<&str>, error: &Error) {
+        match error {
+            Error::IOError(_) | Error::ProtocolError(bolt_proto::error::Error::IOError(_)) => {
+                self.forget(address)
+            }
+            Error::ServerFailure(error)
+                if error.code() == "Neo.ClientError.Cluster.NotALeader"
+                    || error.code() == "Neo.ClientError.General.ForbiddenOnReadOnlyDatabase" =>
+            {
+                self.forget_writer(database, address)
+            }
+            _ => {}
+        }
+    }
+}
+
+#[async_trait]
+impl<C: Connector + ?Sized> Connector for Arc<C> {
+    async fn acquire(
+        &self,
+        mode: AccessMode,
+        database: Option<&str>,
+        bookmarks: &[String],
+    ) -> Result<(String, Client)> {
+        (**self).acquire(mode, database, bookmarks).await
+    }
+
+    fn on_error(&self, address: &str, database: Option<&str>, error: &Error) {
+        (**self).on_error(address, database, error)
+    }
+}
+
+/// Connects to a single server, regardless of access mode.
+#[derive(Debug, Clone)]
+pub struct DirectConnector {
+    address: String,
+    domain: Option<String>,
+    metadata: HashMap<String, Value>,
+    versions: Vec<VersionRange>,
+    // Used instead of the other settings to open connections, if the connector was created from a config
+    config: Option<ConnectConfig>,
+}
+
+impl DirectConnector {
+    /// Create a new connector for the server at the given address. If a domain is given, connections are secured
+    /// with TLS.
+    ///
+    /// The metadata is sent in a `HELLO` message to initialize every connection, and so must contain a `user_agent`
+    /// as well as any authentication details.
+    pub fn new(
+        address: impl Into<String>,
+        domain: Option<&str>,
+        metadata: HashMap<impl Into<String>, impl Into<Value>>,
+    ) -> Self {
+        Self {
+            address: address.into(),
+            domain: domain.map(String::from),
+            metadata: metadata
+                .into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+            versions: DEFAULT_VERSIONS.to_vec(),
+            config: None,
+        }
+    }
+
+    /// Create a new connector from a connection configuration. Connections are opened and initialized with
+    /// [`ConnectConfig::connect`](ConnectConfig::connect), so they use the configured TLS options and timeouts.
+    pub fn from_config(config: ConnectConfig) -> Self {
+        Self {
+            address: config.address(),
+            domain: None,
+            metadata: config.metadata(),
+            versions: config.versions().to_vec(),
+            config: Some(config),
+        }
+    }
+
+    /// Set the protocol versions proposed to the server. By default, versions 4.4 through 4.0 and version 3 are
+    /// proposed.
+    pub fn with_versions(mut self, versions: &[VersionRange]) -> Self {
+        self.versions = versions.to_vec();
+        self
+    }
+}
+
+#[async_trait]
+impl Connector for DirectConnector {
+    async fn acquire(
+        &self,
+        _mode: AccessMode,
+        _database: Option<&str>,
+        _bookmarks: &[String],
+    ) -> Result<(String, Client)> {
+        if let Some(config) = &self.config {
+            return Ok((self.address.clone(), config.connect().await?));
+        }
+        let mut client = Client::new(self.address.as_str(), self.domain.as_deref()).await?;
+        client.handshake(&self.versions).await?;
+        match client.hello(self.metadata.clone()).await? {
+            Message::Success(_) => Ok((self.address.clone(), client)),
+            other => Err(Error::InitFailed(other)),
+        }
+    }
+}
+
+/// Runs transaction functions against a database, retrying them when they fail for transient reasons.
+#[derive(Debug)]
+pub struct Session<C> {
+    connector: C,
+    database: Option<String>,
+    max_retry_time: Duration,
+    initial_retry_delay: Duration,
+    // The bookmark of the last transaction committed by this session, or the initial bookmarks
+    bookmarks: SyncMutex<Vec<String>>,
+    bookmark_manager: Option<BookmarkManager>,
+}
+
+impl Session<Arc<dyn Connector>> {
+    /// Create a session from a connection configuration. Connections to a `neo4j` URI's cluster are routed with a
+    /// [`Router`](crate::routing::Router), and other connections are made directly to the configured server. If the
+    /// configuration selects a database, transactions are run against it.
+    pub fn from_config(config: ConnectConfig) -> Self {
+        let database = config.database().map(String::from);
+        let connector: Arc<dyn Connector> = if config.is_routing() {
+            Arc::new(Router::from_config(config))
+        } else {
+            Arc::new(DirectConnector::from_config(config))
+        };
+        let mut session = Session::new(connector);
+        session.database = database;
+        session
+    }
+}
+
+impl<C: Connector> Session<C> {
+    pub fn new(connector: C) -> Self {
+        Self {
+            connector,
+            database: None,
+            max_retry_time: DEFAULT_MAX_RETRY_TIME,
+            initial_retry_delay: INITIAL_RETRY_DELAY,
+            bookmarks: SyncMutex::new(Vec::new()),
+            bookmark_manager: None,
+        }
+    }
+
+    /// Set the database that transactions are run against (Bolt v4+). By default, the server's default database is
+    /// used.
+    pub fn with_database(mut self, database: impl Into<String>) -> Self {
+        self.database = Some(database.into());
+        self
+    }
+
+    /// Set how long a failing transaction function may be retried for, measured from its first attempt. The default
+    /// is 30 seconds. A duration of zero disables retries.
+    pub fn with_max_retry_time(mut self, max_retry_time: Duration) -> Self {
+        self.max_retry_time = max_retry_time;
+        self
+    }
+
+    /// Set the delay before the first retry. The delay doubles after each retry. The default is 1 second.
+    pub fn with_initial_retry_delay(mut self, delay: Duration) -> Self {
+        self.initial_retry_delay = delay;
+        self
+    }
+
+    /// Set the bookmarks that the first transaction in this session waits for, such as ones obtained from another
+    /// session.
+    pub fn with_bookmarks(self, bookmarks: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        *self.bookmarks.lock().unwrap() = bookmarks.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Share bookmarks with other sessions through the given manager. Each transaction then also waits for the
+    /// transactions most recently committed by the other sessions, and the manager is updated whenever this session
+    /// commits a transaction.
+    pub fn with_bookmark_manager(mut self, manager: BookmarkManager) -> Self {
+        self.bookmark_manager = Some(manager);
+        self
+    }
+
+    /// Get the bookmark of the last transaction committed by this session, or the initial bookmarks if none has been
+    /// committed yet.
+    pub fn last_bookmarks(&self) -> Vec<String> {
+        self.bookmarks.lock().unwrap().clone()
+    }
+
+    /// Run a query in an auto-commit transaction and retrieve all of its records. A `FAILURE` from the server is
+    /// returned as [`Error::ServerFailure`](crate::error::Error::ServerFailure).
+    ///
+    /// Unlike transaction functions, auto-commit queries are not retried, since a query may have been committed
+    /// even though its result was lost.
+    pub async fn run(
+        &self,
+        statement: impl Into<String>,
+        parameters: Option<HashMap<String, Value>>,
+    ) -> Result<QueryResult> {
+        let bookmarks = self.current_bookmarks();
+        let (address, mut client) = self
+            .connector
+            .acquire(AccessMode::Write, self.database.as_deref(), &bookmarks)
+            .await?;
+        let mut run = RunWithMetadata::new(
+            statement.into(),
+            parameters.unwrap_or_default(),
+            HashMap::new(),
+        )
+        .with_bookmarks(bookmarks.clone());
+        if let Some(database) = self.database_for(&client) {
+            run = run.with_db(database);
+        }
+        let result = run_query(&mut client, run).await;
+        match &result {
+            Ok(result) => self.update_bookmarks(&bookmarks, result.bookmark()),
+            Err(error) => self
+                .connector
+                .on_error(&address, self.database.as_deref(), error),
+        }
+        // Closing the connection is best-effort
+        let _ = client.goodbye().await;
+        result
+    }
+
+    /// Run the given transaction function in a read transaction. See [`write_transaction`](Session::write_transaction)
+    /// for details.
+    pub async fn read_transaction<F, Fut, T>(&self, work: F) -> Result<T>
+    where
+        F: FnMut(Transaction) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        self.run_with_retries(AccessMode::Read, work).await
+    }
+
+    /// Run the given transaction function in a write transaction, committing the transaction if the function
+    /// succeeds, and rolling it back otherwise.
+    ///
+    /// If the transaction fails because of a transient error or a lost connection, the function is called again in a
+    /// new transaction, until it succeeds or the maximum retry time has passed. Transaction functions should
+    /// therefore not have side effects outside of the database.
+    pub async fn write_transaction<F, Fut, T>(&self, work: F) -> Result<T>
+    where
+        F: FnMut(Transaction) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        self.run_with_retries(AccessMode::Write, work).await
+    }
+
+    async fn run_with_retries<F, Fut, T>(&self, mode: AccessMode, mut work: F) -> Result<T>
+    where
+        F: FnMut(Transaction) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let start = Instant::now();
+        let mut delay = self.initial_retry_delay;
+        loop {
+            match self.run_transaction(mode, &mut work).await {
+                Err(error) if is_retryable(&error) => {
+                    let delay_with_jitter = jitter(delay);
+                    if start.elapsed() + delay_with_jitter > self.max_retry_time {
+                        return Err(error);
+                    }
+                    tokio::time::delay_for(delay_with_jitter).await;
+                    delay *= RETRY_DELAY_MULTIPLIER;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn run_transaction<F, Fut, T>(&self, mode: AccessMode, work: &mut F) -> Result<T>
+    where
+        F: FnMut(Transaction) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let bookmarks = self.current_bookmarks();
+        let (address, client) = self
+            .connector
+            .acquire(mode, self.database.as_deref(), &bookmarks)
+            .await?;
+        let result = self.transaction(client, mode, &bookmarks, work).await;
+        if let Err(error) = &result {
+            self.connector
+                .on_error(&address, self.database.as_deref(), error);
+        }
+        result
+    }
+
+    // Run the transaction function in a transaction on the given client
+    async fn transaction<F, Fut, T>(
+        &self,
+        mut client: Client,
+        mode: AccessMode,
+        bookmarks: &[String],
+        work: &mut F,
+    ) -> Result<T>
+    where
+        F: FnMut(Transaction) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut metadata = HashMap::new();
+        if mode == AccessMode::Read && is_v4(&client) {
+            metadata.insert("mode".to_string(), Value::from("r"));
+        }
+        let mut begin = Begin::new(metadata).with_bookmarks(bookmarks.to_vec());
+        if let Some(database) = self.database_for(&client) {
+            begin = begin.with_db(database);
+        }
+        expect_success(request(&mut client, Message::Begin(begin)).await?)?;
+
+        let client = Arc::new(Mutex::new(client));
+        let result = work(Transaction {
+            client: Arc::clone(&client),
+        })
+        .await;
+
+        let mut client = client.lock().await;
+        match result {
+            Ok(value) => {
+                let summary = expect_success(client.commit().await?)?;
+                self.update_bookmarks(bookmarks, summary.bookmark());
+                // Closing the connection is best-effort
+                let _ = client.goodbye().await;
+                Ok(value)
+            }
+            Err(error) => {
+                // The connection is discarded after a failure, so rolling back is best-effort
+                if let Ok(Message::Failure(_)) | Ok(Message::Ignored) = client.rollback().await {
+                    let _ = client.reset().await;
+                }
+                let _ = client.goodbye().await;
+                Err(error)
+            }
+        }
+    }
+
+    // The database to select on the given client, which is only possible from Bolt v4
+    fn database_for(&self, client: &Client) -> Option<&str> {
+        self.database.as_deref().filter(|_| is_v4(client))
+    }
+
+    // The bookmarks a new transaction waits for
+    fn current_bookmarks(&self) -> Vec<String> {
+        let mut bookmarks = self.last_bookmarks();
+        if let Some(manager) = &self.bookmark_manager {
+            for bookmark in manager.bookmarks() {
+                if !bookmarks.contains(&bookmark) {
+                    bookmarks.push(bookmark);
+                }
+            }
+        }
+        bookmarks
+    }
+
+    // Replace the bookmarks a committed transaction waited for with the bookmark returned for it
+    fn update_bookmarks(&self, previous: &[String], bookmark: Option<&str>) {
+        if let Some(bookmark) = bookmark {
+            if let Some(manager) = &self.bookmark_manager {
+                manager.update(previous, bookmark);
+            }
+            *self.bookmarks.lock().unwrap() = vec![bookmark.to_string()];
+        }
+    }
+}
+
+/// A handle to an open transaction, used to run queries inside a transaction function.
+#[derive(Debug, Clone)]
+pub struct Transaction {
+    client: Arc<Mutex<Client>>,
+}
+
+impl Transaction {
+    /// Run a query in the transaction and retrieve all of its records. A `FAILURE` from the server is returned as
+    /// [`Error::ServerFailure`](crate::error::Error::ServerFailure).
+    pub async fn run(
+        &self,
+        statement: impl Into<String>,
+        parameters: Option<HashMap<String, Value>>,
+    ) -> Result<QueryResult> {
+        let run = RunWithMetadata::new(
+            statement.into(),
+            parameters.unwrap_or_default(),
+            HashMap::new(),
+        );
+        let mut client = self.client.lock().await;
+        run_query(&mut client, run).await
+    }
+}
+
+/// The outcome of running a query in a [`Transaction`](Transaction).
+#[derive(Debug, Clone)]
+pub struct QueryResult {
+    fields: Vec<String>,
+    records: Vec<Record>,
+    summary: HashMap<String, Value>,
+}
+
+impl QueryResult {
+    /// The names of the fields in each record.
+    pub fn fields(&self) -> &[String] {
+        &self.fields
+    }
+
+    pub fn records(&self) -> &[Record] {
+        &self.records
+    }
+
+    pub fn into_records(self) -> Vec<Record> {
+        self.records
+    }
+
+    /// The metadata sent by the server after the last record.
+    pub fn summary(&self) -> &HashMap<String, Value> {
+        &self.summary
+    }
+
+    /// Get the bookmark of the transaction, if the query was run in an auto-commit transaction.
+    pub fn bookmark(&self) -> Option<&str> {
+        match self.summary.get("bookmark") {
+            Some(Value::String(bookmark)) => Some(bookmark),
+            _ => None,
+        }
+    }
+}
+
+// Run a query and pull all of its records
+async fn run_query(client: &mut Client, run: RunWithMetadata) -> Result<QueryResult> {
+    let response = request(client, Message::RunWithMetadata(run)).await?;
+    let fields = expect_success(response)?
+        .metadata()
+        .get("fields")
+        .cloned()
+        .map(Value::try_into)
+        .transpose()?
+        .unwrap_or_default();
+
+    let (response, records) = if is_v4(client) {
+        client
+            .pull(Some(HashMap::from_iter(vec![(
+                "n".to_string(),
+                Value::from(-1_i8),
+            )])))
+            .await?
+    } else {
+        client.pull_all().await?
+    };
+    let summary = expect_success(response)?.metadata().clone();
+    Ok(QueryResult {
+        fields,
+        records,
+        summary,
+    })
+}
+
+// Send a BEGIN or RUN message built by the session, which relies on messages introduced in Bolt v3
+async fn request(client: &mut Client, message: Message) -> Result<Message> {
+    match client.version() {
+        Some(version) if version >= ProtocolVersion::new(3, 0) => {
+            client.send_message(message).await?;
+            client.read_message().await
+        }
+        version => Err(Error::UnsupportedOperation(version)),
+    }
+}
+
+fn is_v4(client: &Client) -> bool {
+    client.version().unwrap() >= ProtocolVersion::new(4, 0)
+}
+
+fn expect_success(response: Message) -> Result<Success> {
+    match response {
+        Message::Success(success) => Ok(success),
+        Message::Failure(failure) => Err(Error::from(failure)),
+        other => Ok(Success::try_from(other)?),
+    }
+}
+
+// Lost connections may succeed on a new connection, as may server errors like deadlocks or leader changes
+fn is_retryable(error: &Error) -> bool {
+    match error {
+        Error::IOError(_)
+        | Error::Timeout
+        | Error::ProtocolError(bolt_proto::error::Error::IOError(_))
+        | Error::NoServersAvailable(_)
+        | Error::RoutingTableUnavailable(_) => true,
+        Error::ServerFailure(error) => error.is_retryable(),
+        _ => false,
+    }
+}
+
+fn jitter(delay: Duration) -> Duration {
+    // A freshly seeded hasher is a good enough source of randomness for this
+    let random = RandomState::new().build_hasher().finish() as f64 / u64::MAX as f64;
+    delay.mul_f64(1.0 - RETRY_DELAY_JITTER + 2.0 * RETRY_DELAY_JITTER * random)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use bolt_test_support::MockServer;
+
+    use crate::client::v1::tests::*;
+    use crate::routing::tests::{servers, stub_server};
+
+    use super::*;
+
+    fn begin(metadata: Vec<(&str, Value)>) -> Message {
+        Message::Begin(Begin::new(
+            metadata
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v))
+                .collect(),
+        ))
+    }
+
+    fn run(statement: &str) -> Message {
+        Message::RunWithMetadata(RunWithMetadata::new(
+            statement.to_string(),
+            HashMap::new(),
+            HashMap::new(),
+        ))
+    }
+
+    fn pull() -> Message {
+        Message::Pull(Pull::new(HashMap::from_iter(vec![(
+            "n".to_string(),
+            Value::from(-1_i8),
+        )])))
+    }
+
+    fn fields(names: &[&str]) -> Message {
+        success(vec![("fields", Value::from(names.to_vec()))])
+    }
+
+    fn connector(server: &MockServer) -> DirectConnector {
+        DirectConnector::new(
+            server.address(),
+            None,
+            HashMap::from_iter(vec![
+                ("user_agent", USER_AGENT),
+                ("scheme", "basic"),
+                ("principal", USERNAME),
+                ("credentials", PASSWORD),
+            ]),
+        )
+    }
+
+    fn session(server: &MockServer) -> Session<DirectConnector> {
+        Session::new(connector(server)).with_initial_retry_delay(Duration::from_millis(10))
+    }
+
+    fn transient_error() -> Message {
+        failure(
+            "Neo.TransientError.Transaction.DeadlockDetected",
+            "Deadlock detected.",
+        )
+    }
+
+    #[tokio::test]
+    async fn direct_connector_from_config() {
+        let server = MockServer::start(initialized(4)).await;
+        let config = ConnectConfig::from_uri(&format!(
+            "bolt://{}:{}@{}",
+            USERNAME,
+            PASSWORD,
+            server.address()
+        ))
+        .unwrap()
+        .with_user_agent(USER_AGENT)
+        .with_read_timeout(Duration::from_secs(5));
+        let (address, client) = DirectConnector::from_config(config)
+            .acquire(AccessMode::Write, None, &[])
+            .await
+            .unwrap();
+        assert_eq!(address, server.address().to_string());
+        assert_eq!(client.read_timeout(), Some(Duration::from_secs(5)));
+    }
+
+    #[tokio::test]
+    async fn session_from_config() {
+        let script = initialized(4)
+            .expect(
+                begin(vec![("db", Value::from("people"))]),
+                vec![success(vec![])],
+            )
+            .expect(Message::Commit, vec![success(vec![])]);
+        let server = MockServer::start(script).await;
+        let config = ConnectConfig::from_uri(&format!(
+            "bolt://{}:{}@{}?db=people",
+            USERNAME,
+            PASSWORD,
+            server.address()
+        ))
+        .unwrap()
+        .with_user_agent(USER_AGENT);
+        Session::from_config(config)
+            .write_transaction(|_| async { Ok(()) })
+            .await
+            .unwrap();
+        server.verify();
+    }
+
+    #[tokio::test]
+    async fn commit_on_success() {
+        let script = initialized(4)
+            .expect(
+                begin(vec![("db", Value::from("people"))]),
+                vec![success(vec![])],
+            )
+            .expect(run("RETURN 1 AS n"), vec![fields(&["n"])])
+            .expect(
+                pull(),
+                vec![record(vec![Value::from(1_i8)]), success(vec![])],
+            )
+            .expect(
+                Message::Commit,
+                vec![success(vec![("bookmark", Value::from("bookmark:1"))])],
+            );
+        let server = MockServer::start(script).await;
+        let result = session(&server)
+            .with_database("people")
+            .write_transaction(|tx| async move { tx.run("RETURN 1 AS n", None).await })
+            .await
+            .unwrap();
+        assert_eq!(result.fields(), &["n".to_string()]);
+        assert_eq!(result.records()[0].fields(), &[Value::from(1_i8)]);
+    }
+
+    #[tokio::test]
+    async fn read_transaction_v3() {
+        let script = initialized(3)
+            .expect(begin(vec![]), vec![success(vec![])])
+            .expect(run("RETURN 1 AS n"), vec![fields(&["n"])])
+            .expect(
+                Message::PullAll,
+                vec![record(vec![Value::from(1_i8)]), success(vec![])],
+            )
+            .expect(Message::Commit, vec![success(vec![])]);
+        let server = MockServer::start(script).await;
+        let value = session(&server)
+            .read_transaction(|tx| async move {
+                let result = tx.run("RETURN 1 AS n", None).await?;
+                Ok(result.into_records().remove(0).fields()[0].clone())
+            })
+            .await
+            .unwrap();
+        assert_eq!(value, Value::from(1_i8));
+    }
+
+    #[tokio::test]
+    async fn read_mode_v4() {
+        let script = initialized(4)
+            .expect(
+                begin(vec![("mode", Value::from("r"))]),
+                vec![success(vec![])],
+            )
+            .expect(Message::Commit, vec![success(vec![])]);
+        let server = MockServer::start(script).await;
+        session(&server)
+            .read_transaction(|_| async { Ok(()) })
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn rollback_on_error() {
+        let script = initialized(4)
+            .expect(begin(vec![]), vec![success(vec![])])
+            .expect(Message::Rollback, vec![success(vec![])]);
+        let server = MockServer::start(script).await;
+        let result: Result<()> = session(&server)
+            .write_transaction(|_| async { Err(Error::InvalidDNSName("oops".to_string())) })
+            .await;
+        assert!(matches!(result, Err(Error::InvalidDNSName(_))));
+    }
+
+    #[tokio::test]
+    async fn query_failure_is_not_retried() {
+        let script = initialized(4)
+            .expect(begin(vec![]), vec![success(vec![])])
+            .expect(run("RETURN 1 AS"), vec![syntax_error()])
+            .expect(Message::Rollback, vec![Message::Ignored])
+            .expect(Message::Reset, vec![success(vec![])]);
+        let server = MockServer::start(script).await;
+        let attempts = AtomicUsize::new(0);
+        let result = session(&server)
+            .write_transaction(|tx| {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                async move { tx.run("RETURN 1 AS", None).await }
+            })
+            .await;
+        assert!(matches!(result, Err(Error::ServerFailure(_))));
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn transient_failure_is_retried() {
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let server_attempts = Arc::clone(&attempts);
+        // Fail the first attempt with a transient error, then succeed
+        let script = initialized(4)
+            .expect(begin(vec![]), vec![success(vec![])])
+            .then_respond_with(move |message| match message {
+                Message::RunWithMetadata(_) => {
+                    if server_attempts.fetch_add(1, Ordering::SeqCst) == 0 {
+                        Some(vec![transient_error()])
+                    } else {
+                        Some(vec![fields(&[])])
+                    }
+                }
+                Message::Pull(_) | Message::Commit | Message::Rollback | Message::Reset => {
+                    Some(vec![success(vec![])])
+                }
+                Message::Goodbye => Some(vec![]),
+                _ => None,
+            });
+        let server = MockServer::start(script).await;
+        session(&server)
+            .write_transaction(|tx| async move { tx.run("CREATE (n)", None).await })
+            .await
+            .unwrap();
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    }
+
+    // Connects to the first server for the first attempt, and the second server afterwards
+    struct FailoverConnector {
+        connectors: Vec<DirectConnector>,
+        attempts: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl Connector for FailoverConnector {
+        async fn acquire(
+            &self,
+            mode: AccessMode,
+            database: Option<&str>,
+            bookmarks: &[String],
+        ) -> Result<(String, Client)> {
+            let attempt = self.attempts.fetch_add(1, Ordering::SeqCst);
+            self.connectors[attempt.min(1)]
+                .acquire(mode, database, bookmarks)
+                .await
+        }
+    }
+
+    #[tokio::test]
+    async fn connection_loss_is_retried() {
+        let failing = MockServer::start(
+            initialized(4)
+                .expect(begin(vec![]), vec![success(vec![])])
+                .close(),
+        )
+        .await;
+        let working = MockServer::start(
+            initialized(4)
+                .expect(begin(vec![]), vec![success(vec![])])
+                .expect(run("CREATE (n)"), vec![fields(&[])])
+                .expect(pull(), vec![success(vec![])])
+                .expect(Message::Commit, vec![success(vec![])]),
+        )
+        .await;
+        let connector = FailoverConnector {
+            connectors: vec![connector(&failing), connector(&working)],
+            attempts: AtomicUsize::new(0),
+        };
+        Session::new(connector)
+            .with_initial_retry_delay(Duration::from_millis(10))
+            .write_transaction(|tx| async move { tx.run("CREATE (n)", None).await })
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn leader_change_is_retried() {
+        let version = ProtocolVersion::new(4, 3);
+        let old_leader = stub_server(
+            version,
+            Arc::new(|message| match message {
+                Message::RunWithMetadata(_) => vec![failure(
+                    "Neo.ClientError.Cluster.NotALeader",
+                    "No longer the leader.",
+                )],
+                Message::Rollback => vec![Message::Ignored],
+                _ => vec![success(vec![])],
+            }),
+        )
+        .await;
+        let new_leader = stub_server(
+            version,
+            Arc::new(|message| match message {
+                Message::RunWithMetadata(_) => vec![fields(&[])],
+                _ => vec![success(vec![])],
+            }),
+        )
+        .await;
+        let route_count = Arc::new(AtomicUsize::new(0));
+        let server_route_count = Arc::clone(&route_count);
+        let router = stub_server(
+            version,
+            Arc::new(move |message| match message {
+                Message::Route(route) => {
+                    // Only the first routing table lists the old leader
+                    let writer = if server_route_count.fetch_add(1, Ordering::SeqCst) == 0 {
+                        &old_leader
+                    } else {
+                        &new_leader
+                    };
+                    let address = String::try_from(route.context()["address"].clone()).unwrap();
+                    let rt = HashMap::from_iter(vec![
+                        ("ttl", Value::from(300)),
+                        ("servers", servers(&[&address], &[], &[writer])),
+                    ]);
+                    vec![success(vec![("rt", Value::from(rt))])]
+                }
+                _ => vec![success(vec![])],
+            }),
+        )
+        .await;
+
+        let router = Router::new(
+            router,
+            false,
+            HashMap::from_iter(vec![("user_agent".to_string(), USER_AGENT)]),
+        );
+        Session::new(router)
+            .with_initial_retry_delay(Duration::from_millis(10))
+            .write_transaction(|tx| async move { tx.run("CREATE (n)", None).await })
+            .await
+            .unwrap();
+        // The old leader was forgotten after it failed, so a new routing table was fetched
+        assert_eq!(route_count.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn retries_give_up_after_max_retry_time() {
+        let script = initialized(4)
+            .expect(begin(vec![]), vec![success(vec![])])
+            .then_respond_with(|message| match message {
+                Message::RunWithMetadata(_) => Some(vec![transient_error()]),
+                Message::Rollback | Message::Reset => Some(vec![success(vec![])]),
+                Message::Goodbye => Some(vec![]),
+                _ => None,
+            });
+        let server = MockServer::start(script).await;
+        let start = Instant::now();
+        let result = session(&server)
+            .with_max_retry_time(Duration::from_millis(100))
+            .write_transaction(|tx| async move { tx.run("CREATE (n)", None).await })
+            .await;
+        assert!(matches!(result, Err(Error::ServerFailure(_))));
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+
+    fn bookmarks(bookmarks: &[&str]) -> (&'static str, Value) {
+        ("bookmarks", Value::from(bookmarks.to_vec()))
+    }
+
+    fn commit_success(bookmark: &str) -> Message {
+        success(vec![("bookmark", Value::from(bookmark))])
+    }
+
+    #[tokio::test]
+    async fn bookmarks_are_sent_on_begin() {
+        let script = initialized(4)
+            .expect(
+                begin(vec![bookmarks(&["bookmark:0"])]),
+                vec![success(vec![])],
+            )
+            .expect(Message::Commit, vec![commit_success("bookmark:1")]);
+        let server = MockServer::start(script).await;
+        let session = session(&server).with_bookmarks(vec!["bookmark:0"]);
+        session
+            .write_transaction(|_| async { Ok(()) })
+            .await
+            .unwrap();
+        assert_eq!(session.last_bookmarks(), vec!["bookmark:1"]);
+    }
+
+    #[tokio::test]
+    async fn bookmarks_are_sent_on_auto_commit_run() {
+        let script = initialized(3)
+            .expect(
+                Message::RunWithMetadata(RunWithMetadata::new(
+                    "RETURN 1 AS n".to_string(),
+                    HashMap::new(),
+                    HashMap::from_iter(vec![(
+                        "bookmarks".to_string(),
+                        Value::from(vec!["bookmark:1"]),
+                    )]),
+                )),
+                vec![fields(&["n"])],
+            )
+            .expect(
+                Message::PullAll,
+                vec![
+                    record(vec![Value::from(1_i8)]),
+                    commit_success("bookmark:2"),
+                ],
+            );
+        let server = MockServer::start(script).await;
+        let session = session(&server).with_bookmarks(vec!["bookmark:1"]);
+        let result = session.run("RETURN 1 AS n", None).await.unwrap();
+        assert_eq!(result.records()[0].fields(), &[Value::from(1_i8)]);
+        assert_eq!(result.bookmark(), Some("bookmark:2"));
+        assert_eq!(session.last_bookmarks(), vec!["bookmark:2"]);
+    }
+
+    #[tokio::test]
+    async fn bookmarks_are_sent_on_route() {
+        let route_bookmarks = Arc::new(SyncMutex::new(Vec::new()));
+        let server_route_bookmarks = Arc::clone(&route_bookmarks);
+        // A single member that acts as both router and writer
+        let server = stub_server(
+            ProtocolVersion::new(4, 3),
+            Arc::new(move |message| match message {
+                Message::Route(route) => {
+                    *server_route_bookmarks.lock().unwrap() = route.bookmarks().to_vec();
+                    let address = String::try_from(route.context()["address"].clone()).unwrap();
+                    let rt = HashMap::from_iter(vec![
+                        ("ttl", Value::from(300)),
+                        ("servers", servers(&[&address], &[], &[&address])),
+                    ]);
+                    vec![success(vec![("rt", Value::from(rt))])]
+                }
+                Message::Commit => vec![commit_success("bookmark:1")],
+                _ => vec![success(vec![])],
+            }),
+        )
+        .await;
+
+        let router = Router::new(
+            server,
+            false,
+            HashMap::from_iter(vec![("user_agent".to_string(), USER_AGENT)]),
+        );
+        let session = Session::new(router).with_bookmarks(vec!["bookmark:0"]);
+        session
+            .write_transaction(|_| async { Ok(()) })
+            .await
+            .unwrap();
+        assert_eq!(*route_bookmarks.lock().unwrap(), vec!["bookmark:0"]);
+        assert_eq!(session.last_bookmarks(), vec!["bookmark:1"]);
+    }
+
+    #[tokio::test]
+    async fn bookmark_manager_is_shared() {
+        let first_server = MockServer::start(
+            initialized(4)
+                .expect(begin(vec![]), vec![success(vec![])])
+                .expect(Message::Commit, vec![commit_success("bookmark:1")]),
+        )
+        .await;
+        let second_server = MockServer::start(
+            initialized(4)
+                .expect(
+                    begin(vec![bookmarks(&["bookmark:1"])]),
+                    vec![success(vec![])],
+                )
+                .expect(Message::Commit, vec![commit_success("bookmark:2")]),
+        )
+        .await;
+        let manager = BookmarkManager::new();
+        let first_session = session(&first_server).with_bookmark_manager(manager.clone());
+        let second_session = session(&second_server).with_bookmark_manager(manager.clone());
+
+        first_session
+            .write_transaction(|_| async { Ok(()) })
+            .await
+            .unwrap();
+        assert_eq!(manager.bookmarks(), vec!["bookmark:1"]);
+        second_session
+            .write_transaction(|_| async { Ok(()) })
+            .await
+            .unwrap();
+        assert_eq!(manager.bookmarks(), vec!["bookmark:2"]);
+        assert_eq!(first_session.last_bookmarks(), vec!["bookmark:1"]);
+    }
+
+    #[test]
+    fn retryable_errors() {
+        assert!(is_retryable(&Error::ServerFailure(ServerError::new(
+            "Neo.TransientError.General.DatabaseUnavailable",
+            ""
+        ))));
+        assert!(!is_retryable(&Error::ServerFailure(ServerError::new(
+            "Neo.ClientError.Statement.SyntaxError",
+            ""
+        ))));
+        assert!(is_retryable(&Error::IOError(std::io::Error::from(
+            std::io::ErrorKind::ConnectionReset
+        ))));
+        assert!(is_retryable(&Error::Timeout));
+        assert!(!is_retryable(&Error::HandshakeFailed));
+    }
+
+    #[test]
+    fn jitter_stays_in_range() {
+        for _ in 0..100 {
+            let delay = jitter(Duration::from_millis(1000));
+            assert!(delay >= Duration::from_millis(800) && delay <= Duration::from_millis(1200));
+        }
+    }
+}
@@ -1,16 +1,37 @@
-use std::fmt::Debug;
+use std::fmt::{self, Debug};
 use std::io;
 use std::pin::Pin;
 use std::task::{Context, Poll};
 
 use tokio::net::TcpStream;
+#[cfg(unix)]
+use tokio::net::UnixStream;
 use tokio::prelude::*;
 use tokio_rustls::client::TlsStream;
 
-#[derive(Debug)]
+// A transport provided by the user
+pub(crate) trait Transport: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Transport for T {}
+
 pub(crate) enum Stream {
     Tcp(TcpStream),
     SecureTcp(Box<TlsStream<TcpStream>>),
+    #[cfg(unix)]
+    Unix(UnixStream),
+    Custom(Box<dyn Transport>),
+}
+
+impl Debug for Stream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Stream::Tcp(tcp_stream) => f.debug_tuple("Tcp").field(tcp_stream).finish(),
+            Stream::SecureTcp(tls_stream) => f.debug_tuple("SecureTcp").field(tls_stream).finish(),
+            #[cfg(unix)]
+            Stream::Unix(unix_stream) => f.debug_tuple("Unix").field(unix_stream).finish(),
+            Stream::Custom(_) => f.write_str("Custom"),
+        }
+    }
 }
 
 impl AsyncRead for Stream {
@@ -22,6 +43,9 @@ impl AsyncRead for Stream {
         match self.get_mut() {
             Stream::Tcp(tcp_stream) => Pin::new(tcp_stream).poll_read(cx, buf),
             Stream::SecureTcp(tls_stream) => Pin::new(tls_stream).poll_read(cx, buf),
+            #[cfg(unix)]
+            Stream::Unix(unix_stream) => Pin::new(unix_stream).poll_read(cx, buf),
+            Stream::Custom(transport) => Pin::new(transport).poll_read(cx, buf),
         }
     }
 }
@@ -35,6 +59,9 @@ impl AsyncWrite for Stream {
         match self.get_mut() {
             Stream::Tcp(tcp_stream) => Pin::new(tcp_stream).poll_write(cx, buf),
             Stream::SecureTcp(tls_stream) => Pin::new(tls_stream).poll_write(cx, buf),
+            #[cfg(unix)]
+            Stream::Unix(unix_stream) => Pin::new(unix_stream).poll_write(cx, buf),
+            Stream::Custom(transport) => Pin::new(transport).poll_write(cx, buf),
         }
     }
 
@@ -42,6 +69,9 @@ impl AsyncWrite for Stream {
         match self.get_mut() {
             Stream::Tcp(tcp_stream) => Pin::new(tcp_stream).poll_flush(cx),
             Stream::SecureTcp(tls_stream) => Pin::new(tls_stream).poll_flush(cx),
+            #[cfg(unix)]
+            Stream::Unix(unix_stream) => Pin::new(unix_stream).poll_flush(cx),
+            Stream::Custom(transport) => Pin::new(transport).poll_flush(cx),
         }
     }
 
@@ -49,6 +79,9 @@ impl AsyncWrite for Stream {
         match self.get_mut() {
             Stream::Tcp(tcp_stream) => Pin::new(tcp_stream).poll_shutdown(cx),
             Stream::SecureTcp(tls_stream) => Pin::new(tls_stream).poll_shutdown(cx),
+            #[cfg(unix)]
+            Stream::Unix(unix_stream) => Pin::new(unix_stream).poll_shutdown(cx),
+            Stream::Custom(transport) => Pin::new(transport).poll_shutdown(cx),
         }
     }
 }
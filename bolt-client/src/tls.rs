@@ -0,0 +1,351 @@
+//! TLS configuration for secure connections.
+//!
+//! By default, secure connections trust the Mozilla root certificates bundled with
+//! [webpki-roots](https://crates.io/crates/webpki-roots). [`TlsOptions`](TlsOptions) can be used to trust additional
+//! certificate authorities, to present a client certificate, or to skip verification of the server's certificate
+//! entirely. For anything else, a [`ClientConfig`](tokio_rustls::rustls::ClientConfig) can be given to
+//! [`Client::with_tls`](crate::Client::with_tls) directly.
+//!
+//! # Example
+//! ```no_run
+//! use bolt_client::tls::TlsOptions;
+//! use bolt_client::Client;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let options = TlsOptions::new()
+//!     .with_ca_file("/etc/neo4j/certificates/ca.pem")?
+//!     .with_client_cert_files("client.pem", "client.key")?;
+//! let client = Client::with_tls("db.internal:7687", "db.internal", options).await?;
+//! # Ok(())
+//! # }
+//! ```
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Cursor};
+use std::path::Path;
+use std::sync::Arc;
+
+use tokio_rustls::rustls::internal::pemfile;
+use tokio_rustls::rustls::{
+    Certificate, ClientConfig, PrivateKey, RootCertStore, ServerCertVerified, ServerCertVerifier,
+    TLSError,
+};
+use tokio_rustls::webpki::DNSNameRef;
+use webpki_roots::TLS_SERVER_ROOTS;
+
+use crate::error::*;
+
+/// Options for securing connections with TLS.
+///
+/// The options can be converted into a rustls [`ClientConfig`](tokio_rustls::rustls::ClientConfig) and passed to
+/// [`Client::with_tls`](crate::Client::with_tls).
+#[derive(Clone)]
+pub struct TlsOptions {
+    config: ClientConfig,
+    skip_verify: bool,
+}
+
+impl TlsOptions {
+    /// Create options that trust the bundled root certificates only.
+    pub fn new() -> Self {
+        let mut config = ClientConfig::new();
+        config
+            .root_store
+            .add_server_trust_anchors(&TLS_SERVER_ROOTS);
+        Self {
+            config,
+            skip_verify: false,
+        }
+    }
+
+    /// Trust the certificate authorities in the given PEM data, in addition to the bundled root certificates.
+    pub fn with_ca_pem(self, pem: &[u8]) -> Result<Self> {
+        self.with_ca_certs(&mut Cursor::new(pem))
+    }
+
+    /// Trust the certificate authorities in the given PEM file, in addition to the bundled root certificates.
+    pub fn with_ca_file(self, path: impl AsRef<Path>) -> Result<Self> {
+        self.with_ca_certs(&mut BufReader::new(File::open(path)?))
+    }
+
+    fn with_ca_certs(mut self, reader: &mut dyn BufRead) -> Result<Self> {
+        for cert in read_certs(reader)? {
+            self.config.root_store.add(&cert).map_err(|error| {
+                Error::InvalidTlsConfig(format!("invalid CA certificate: {}", error))
+            })?;
+        }
+        Ok(self)
+    }
+
+    /// Present the given client certificate chain and private key to the server, both in PEM format. The key may be
+    /// a PKCS#8 or an RSA private key.
+    pub fn with_client_cert_pem(mut self, cert_chain: &[u8], key: &[u8]) -> Result<Self> {
+        let certs = read_certs(&mut Cursor::new(cert_chain))?;
+        let key = read_key(key)?;
+        self.config
+            .set_single_client_cert(certs, key)
+            .map_err(|error| Error::InvalidTlsConfig(error.to_string()))?;
+        Ok(self)
+    }
+
+    /// Present the client certificate chain and private key in the given PEM files to the server. See
+    /// [`with_client_cert_pem`](TlsOptions::with_client_cert_pem) for details.
+    pub fn with_client_cert_files(
+        self,
+        cert_chain_path: impl AsRef<Path>,
+        key_path: impl AsRef<Path>,
+    ) -> Result<Self> {
+        let cert_chain = std::fs::read(cert_chain_path)?;
+        let key = std::fs::read(key_path)?;
+        self.with_client_cert_pem(&cert_chain, &key)
+    }
+
+    /// Accept any certificate presented by the server, including self-signed and expired certificates, and
+    /// certificates issued for another host. The connection is still encrypted, but is vulnerable to
+    /// man-in-the-middle attacks, so this should only be used for development.
+    pub fn insecure_skip_verify(mut self) -> Self {
+        self.config
+            .dangerous()
+            .set_certificate_verifier(Arc::new(NoVerification));
+        self.skip_verify = true;
+        self
+    }
+
+    /// Whether the server's certificate is accepted without verification.
+    pub fn skips_verification(&self) -> bool {
+        self.skip_verify
+    }
+}
+
+impl Default for TlsOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for TlsOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TlsOptions")
+            .field("root_certs", &self.config.root_store.len())
+            .field("skip_verify", &self.skip_verify)
+            .finish()
+    }
+}
+
+impl From<TlsOptions> for ClientConfig {
+    fn from(options: TlsOptions) -> Self {
+        options.config
+    }
+}
+
+impl From<TlsOptions> for Arc<ClientConfig> {
+    fn from(options: TlsOptions) -> Self {
+        Arc::new(options.config)
+    }
+}
+
+fn read_certs(reader: &mut dyn BufRead) -> Result<Vec<Certificate>> {
+    match pemfile::certs(reader) {
+        Ok(certs) if !certs.is_empty() => Ok(certs),
+        _ => Err(Error::InvalidTlsConfig(
+            "no certificates found in PEM data".to_string(),
+        )),
+    }
+}
+
+fn read_key(pem: &[u8]) -> Result<PrivateKey> {
+    let pkcs8_keys = pemfile::pkcs8_private_keys(&mut Cursor::new(pem)).unwrap_or_default();
+    let rsa_keys = pemfile::rsa_private_keys(&mut Cursor::new(pem)).unwrap_or_default();
+    pkcs8_keys
+        .into_iter()
+        .chain(rsa_keys)
+        .next()
+        .ok_or_else(|| Error::InvalidTlsConfig("no private key found in PEM data".to_string()))
+}
+
+// Accepts any server certificate
+struct NoVerification;
+
+impl ServerCertVerifier for NoVerification {
+    fn verify_server_cert(
+        &self,
+        _roots: &RootCertStore,
+        _presented_certs: &[Certificate],
+        _dns_name: DNSNameRef,
+        _ocsp_response: &[u8],
+    ) -> std::result::Result<ServerCertVerified, TLSError> {
+        Ok(ServerCertVerified::assertion())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rcgen::{BasicConstraints, Certificate as GeneratedCert, CertificateParams, IsCa};
+    use tokio::net::TcpListener;
+    use tokio::prelude::*;
+    use tokio_rustls::rustls::{AllowAnyAuthenticatedClient, NoClientAuth, ServerConfig};
+    use tokio_rustls::TlsAcceptor;
+
+    use bolt_proto::version::VersionRange;
+    use bolt_proto::ProtocolVersion;
+
+    use crate::Client;
+
+    use super::*;
+
+    struct Pki {
+        ca: GeneratedCert,
+        server: GeneratedCert,
+        client: GeneratedCert,
+    }
+
+    impl Pki {
+        fn new() -> Self {
+            let mut ca_params = CertificateParams::new(vec![]);
+            ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+            Self {
+                ca: GeneratedCert::from_params(ca_params).unwrap(),
+                server: GeneratedCert::from_params(CertificateParams::new(vec![
+                    "localhost".to_string()
+                ]))
+                .unwrap(),
+                client: GeneratedCert::from_params(CertificateParams::new(vec![
+                    "client".to_string()
+                ]))
+                .unwrap(),
+            }
+        }
+
+        fn ca_pem(&self) -> String {
+            self.ca.serialize_pem().unwrap()
+        }
+
+        fn signed_pem(&self, cert: &GeneratedCert) -> String {
+            cert.serialize_pem_with_signer(&self.ca).unwrap()
+        }
+    }
+
+    fn der_cert(pem: &str) -> Vec<Certificate> {
+        read_certs(&mut Cursor::new(pem.as_bytes())).unwrap()
+    }
+
+    fn der_key(cert: &GeneratedCert) -> PrivateKey {
+        PrivateKey(cert.serialize_private_key_der())
+    }
+
+    // Start a TLS server that completes a Bolt handshake on each connection, agreeing to version 4.0
+    async fn tls_server(config: ServerConfig) -> String {
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let acceptor = TlsAcceptor::from(Arc::new(config));
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let acceptor = acceptor.clone();
+                tokio::spawn(async move {
+                    if let Ok(mut stream) = acceptor.accept(stream).await {
+                        let mut handshake = [0; 20];
+                        if stream.read_exact(&mut handshake).await.is_ok() {
+                            let _ = stream.write_u32(0x0000_0004).await;
+                            let _ = stream.flush().await;
+                        }
+                    }
+                });
+            }
+        });
+        address
+    }
+
+    fn server_config(pki: &Pki, self_signed: bool, require_client_cert: bool) -> ServerConfig {
+        let verifier = if require_client_cert {
+            let mut roots = RootCertStore::empty();
+            roots.add(&der_cert(&pki.ca_pem())[0]).unwrap();
+            AllowAnyAuthenticatedClient::new(roots)
+        } else {
+            NoClientAuth::new()
+        };
+        let mut config = ServerConfig::new(verifier);
+        let cert_pem = if self_signed {
+            pki.server.serialize_pem().unwrap()
+        } else {
+            pki.signed_pem(&pki.server)
+        };
+        config
+            .set_single_cert(der_cert(&cert_pem), der_key(&pki.server))
+            .unwrap();
+        config
+    }
+
+    async fn connect(address: &str, options: TlsOptions) -> Result<Client> {
+        let mut client = Client::with_tls(address, "localhost", options).await?;
+        client
+            .handshake(&[VersionRange::exact(ProtocolVersion::new(4, 0))])
+            .await?;
+        Ok(client)
+    }
+
+    #[tokio::test]
+    async fn custom_ca() {
+        let pki = Pki::new();
+        let address = tls_server(server_config(&pki, false, false)).await;
+        assert!(connect(&address, TlsOptions::new()).await.is_err());
+
+        let options = TlsOptions::new()
+            .with_ca_pem(pki.ca_pem().as_bytes())
+            .unwrap();
+        let client = connect(&address, options).await.unwrap();
+        assert_eq!(client.version(), Some(ProtocolVersion::new(4, 0)));
+    }
+
+    #[tokio::test]
+    async fn ca_file() {
+        let pki = Pki::new();
+        let address = tls_server(server_config(&pki, false, false)).await;
+        let path = std::env::temp_dir().join(format!("bolt-client-ca-{}.pem", std::process::id()));
+        std::fs::write(&path, pki.ca_pem()).unwrap();
+        let options = TlsOptions::new().with_ca_file(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(connect(&address, options.unwrap()).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn client_cert() {
+        let pki = Pki::new();
+        let address = tls_server(server_config(&pki, false, true)).await;
+        let options = TlsOptions::new()
+            .with_ca_pem(pki.ca_pem().as_bytes())
+            .unwrap();
+        assert!(connect(&address, options.clone()).await.is_err());
+
+        let options = options
+            .with_client_cert_pem(
+                pki.signed_pem(&pki.client).as_bytes(),
+                pki.client.serialize_private_key_pem().as_bytes(),
+            )
+            .unwrap();
+        assert!(connect(&address, options).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn self_signed() {
+        let pki = Pki::new();
+        let address = tls_server(server_config(&pki, true, false)).await;
+        assert!(connect(&address, TlsOptions::new()).await.is_err());
+        let options = TlsOptions::new().insecure_skip_verify();
+        assert!(connect(&address, options).await.is_ok());
+    }
+
+    #[test]
+    fn invalid_pem() {
+        assert!(matches!(
+            TlsOptions::new().with_ca_pem(b"not a certificate"),
+            Err(Error::InvalidTlsConfig(_))
+        ));
+        let pki = Pki::new();
+        assert!(matches!(
+            TlsOptions::new().with_client_cert_pem(pki.ca_pem().as_bytes(), b""),
+            Err(Error::InvalidTlsConfig(_))
+        ));
+    }
+}
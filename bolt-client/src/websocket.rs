@@ -0,0 +1,245 @@
+//! Bolt over WebSocket, for servers that accept WebSocket connections on their Bolt port.
+//!
+//! Each write is sent as a single binary frame, and the payloads of received binary frames are read back as one
+//! continuous stream of bytes, so Bolt chunks may span frames in either direction.
+
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use bytes::{Buf, Bytes};
+use futures_util::{Sink, Stream};
+use tokio::prelude::*;
+use tokio_tungstenite::tungstenite::{self, Message};
+use tokio_tungstenite::WebSocketStream;
+
+use crate::error::*;
+use crate::Client;
+
+// Perform the WebSocket opening handshake over the given stream and wrap the result in a Client
+pub(crate) async fn connect<S>(url: &str, stream: S) -> Result<Client>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let (socket, _) = tokio_tungstenite::client_async(url, stream).await?;
+    Ok(Client::from_transport(WebSocketTransport::new(socket)))
+}
+
+// Adapts a WebSocket connection to a byte stream
+pub(crate) struct WebSocketTransport<S> {
+    socket: WebSocketStream<S>,
+    // The unread remainder of the last binary frame received
+    buffer: Bytes,
+}
+
+impl<S> WebSocketTransport<S> {
+    pub(crate) fn new(socket: WebSocketStream<S>) -> Self {
+        Self {
+            socket,
+            buffer: Bytes::new(),
+        }
+    }
+}
+
+fn io_error(error: tungstenite::Error) -> io::Error {
+    match error {
+        tungstenite::Error::Io(error) => error,
+        // Reads and writes after the close handshake behave like those on a closed socket
+        tungstenite::Error::ConnectionClosed | tungstenite::Error::AlreadyClosed => {
+            io::Error::new(io::ErrorKind::UnexpectedEof, error)
+        }
+        error => io::Error::other(error),
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> AsyncRead for WebSocketTransport<S> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        while !this.buffer.has_remaining() {
+            match Pin::new(&mut this.socket).poll_next(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Some(Ok(Message::Binary(data)))) => this.buffer = Bytes::from(data),
+                // Pings are answered by the WebSocket implementation itself
+                Poll::Ready(Some(Ok(Message::Ping(_))))
+                | Poll::Ready(Some(Ok(Message::Pong(_)))) => {}
+                Poll::Ready(Some(Ok(Message::Close(_)))) | Poll::Ready(None) => {
+                    return Poll::Ready(Ok(0))
+                }
+                Poll::Ready(Some(Ok(Message::Text(_)))) => {
+                    return Poll::Ready(Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "received a text frame, Bolt requires binary frames",
+                    )))
+                }
+                Poll::Ready(Some(Err(error))) => return Poll::Ready(Err(io_error(error))),
+            }
+        }
+        let length = buf.len().min(this.buffer.len());
+        this.buffer.copy_to_slice(&mut buf[..length]);
+        Poll::Ready(Ok(length))
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> AsyncWrite for WebSocketTransport<S> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let mut socket = Pin::new(&mut self.get_mut().socket);
+        match socket.as_mut().poll_ready(cx) {
+            Poll::Pending => return Poll::Pending,
+            Poll::Ready(Err(error)) => return Poll::Ready(Err(io_error(error))),
+            Poll::Ready(Ok(())) => {}
+        }
+        socket
+            .start_send(Message::Binary(buf.to_vec()))
+            .map_err(io_error)?;
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().socket)
+            .poll_flush(cx)
+            .map_err(io_error)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().socket)
+            .poll_close(cx)
+            .map_err(io_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+    use std::sync::Arc;
+
+    use rcgen::generate_simple_self_signed;
+    use tokio::net::TcpListener;
+    use tokio::task::JoinHandle;
+    use tokio_rustls::rustls::internal::pemfile::{certs, pkcs8_private_keys};
+    use tokio_rustls::rustls::{NoClientAuth, ServerConfig};
+    use tokio_rustls::TlsAcceptor;
+
+    use bolt_proto::message::*;
+    use bolt_proto::version::VersionRange;
+    use bolt_proto::{Message, ProtocolVersion};
+    use bolt_test_support::Script;
+
+    use crate::client::v1::tests::*;
+    use crate::tls::TlsOptions;
+
+    use super::*;
+
+    // Accept a single WebSocket connection, optionally secured with TLS, and play the script over it
+    async fn websocket_server(
+        script: Script,
+        tls: Option<TlsAcceptor>,
+    ) -> (String, JoinHandle<std::result::Result<(), String>>) {
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            match tls {
+                Some(acceptor) => {
+                    let stream = acceptor.accept(stream).await.unwrap();
+                    let socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+                    script.play(WebSocketTransport::new(socket)).await
+                }
+                None => {
+                    let socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+                    script.play(WebSocketTransport::new(socket)).await
+                }
+            }
+        });
+        (address, server)
+    }
+
+    fn self_signed_acceptor() -> TlsAcceptor {
+        let cert = generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let cert_pem = cert.serialize_pem().unwrap();
+        let key_pem = cert.serialize_private_key_pem();
+        let mut config = ServerConfig::new(NoClientAuth::new());
+        config
+            .set_single_cert(
+                certs(&mut cert_pem.as_bytes()).unwrap(),
+                pkcs8_private_keys(&mut key_pem.as_bytes())
+                    .unwrap()
+                    .remove(0),
+            )
+            .unwrap();
+        TlsAcceptor::from(Arc::new(config))
+    }
+
+    async fn initialize_and_reset(mut client: Client) {
+        client
+            .handshake(&[VersionRange::exact(ProtocolVersion::new(3, 0))])
+            .await
+            .unwrap();
+        let response = initialize_client(&mut client, true).await.unwrap();
+        assert!(Success::try_from(response).is_ok());
+        let response = client.reset().await.unwrap();
+        assert!(Success::try_from(response).is_ok());
+    }
+
+    #[tokio::test]
+    async fn plain() {
+        let script = initialized(3).expect(Message::Reset, vec![success(vec![])]);
+        let (address, server) = websocket_server(script, None).await;
+
+        let client = Client::connect_websocket(&address, None).await.unwrap();
+        initialize_and_reset(client).await;
+        server.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn secure() {
+        let script = initialized(3).expect(Message::Reset, vec![success(vec![])]);
+        let (address, server) = websocket_server(script, Some(self_signed_acceptor())).await;
+        let address = address.replace("127.0.0.1", "localhost");
+
+        let options = TlsOptions::new().insecure_skip_verify();
+        let client = Client::connect_websocket_with_tls(&address, "localhost", options)
+            .await
+            .unwrap();
+        initialize_and_reset(client).await;
+        server.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn untrusted_certificate() {
+        let (address, _) = websocket_server(initialized(3), Some(self_signed_acceptor())).await;
+        let address = address.replace("127.0.0.1", "localhost");
+        assert!(Client::connect_websocket(&address, Some("localhost"))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn text_frames_are_rejected() {
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+            futures_util::SinkExt::send(
+                &mut socket,
+                tungstenite::Message::Text("hello".to_string()),
+            )
+            .await
+            .unwrap();
+        });
+
+        let mut client = Client::connect_websocket(&address, None).await.unwrap();
+        let result = client
+            .handshake(&[VersionRange::exact(ProtocolVersion::new(3, 0))])
+            .await;
+        assert!(matches!(result, Err(Error::IOError(_))));
+    }
+}
@@ -50,13 +50,10 @@ fn impl_signature(ast: &syn::DeriveInput) -> TokenStream {
         quote!(let #var_name = crate::Value::from(self.#field_name).try_into_bytes()?;)
     });
 
-    let deserialize_fields =
-        fields
-            .iter()
-            .map(|field| {
-                let field_name = field.ident.as_ref().unwrap();
-                quote!(#field_name: crate::Value::try_from(::std::sync::Arc::clone(&remaining_bytes_arc))?.try_into()?,)
-            });
+    let deserialize_fields = fields.iter().map(|field| {
+        let field_name = field.ident.as_ref().unwrap();
+        quote!(#field_name: crate::Value::try_from(&mut *input_bytes)?.try_into()?,)
+    });
 
     let gen = quote! {
         use ::bytes::BufMut;
@@ -108,12 +105,12 @@ fn impl_signature(ast: &syn::DeriveInput) -> TokenStream {
         #where_clause
         {}
 
-        impl ::std::convert::TryFrom<::std::sync::Arc<::std::sync::Mutex<::bytes::Bytes>>> for #name#type_args
+        impl ::std::convert::TryFrom<&mut ::bytes::Bytes> for #name#type_args
         #where_clause
         {
             type Error = crate::error::Error;
 
-            fn try_from(remaining_bytes_arc: ::std::sync::Arc<::std::sync::Mutex<::bytes::Bytes>>) -> crate::error::Result<Self> {
+            fn try_from(input_bytes: &mut ::bytes::Bytes) -> crate::error::Result<Self> {
                 use ::std::convert::{TryFrom, TryInto};
                 Ok(#name {
                     #(#deserialize_fields)*
@@ -0,0 +1,150 @@
+use std::convert::{TryFrom, TryInto};
+use std::mem;
+
+use bytes::{Buf, Bytes, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::error::*;
+use crate::Message;
+
+/// A codec that frames Bolt messages, for use with [`Framed`](tokio_util::codec::Framed) and friends.
+///
+/// Messages are split into chunks, each prefixed with its size as a 16-bit integer, and terminated by an empty chunk.
+/// Empty chunks received between messages (e.g. keep-alive NOOPs sent by the server) are ignored.
+#[derive(Debug, Default)]
+pub struct BoltCodec {
+    // The data from the chunks of the current message received so far
+    message: BytesMut,
+}
+
+impl BoltCodec {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Decoder for BoltCodec {
+    type Item = Message;
+    type Error = Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Message>> {
+        loop {
+            if src.len() < mem::size_of::<u16>() {
+                return Ok(None);
+            }
+            let chunk_size = u16::from_be_bytes([src[0], src[1]]) as usize;
+            if chunk_size == 0 {
+                src.advance(mem::size_of::<u16>());
+                if self.message.is_empty() {
+                    continue;
+                }
+                let mut message_bytes = self.message.split().freeze();
+                return Message::try_from(&mut message_bytes).map(Some);
+            }
+
+            let frame_size = mem::size_of::<u16>() + chunk_size;
+            if src.len() < frame_size {
+                src.reserve(frame_size - src.len());
+                return Ok(None);
+            }
+            src.advance(mem::size_of::<u16>());
+            self.message.extend_from_slice(&src.split_to(chunk_size));
+        }
+    }
+}
+
+impl Encoder<Message> for BoltCodec {
+    type Error = Error;
+
+    fn encode(&mut self, message: Message, dst: &mut BytesMut) -> Result<()> {
+        let chunks: Vec<Bytes> = message.try_into()?;
+        dst.reserve(chunks.iter().map(Bytes::len).sum());
+        for chunk in chunks {
+            dst.extend_from_slice(&chunk);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::iter::FromIterator;
+
+    use bytes::BufMut;
+
+    use crate::message::*;
+    use crate::Value;
+
+    use super::*;
+
+    fn new_record() -> Message {
+        Message::Record(Record::new(vec![
+            Value::from(1200_i16),
+            Value::from("hi there"),
+            Value::from(HashMap::from_iter(vec![("key", Value::from("value"))])),
+        ]))
+    }
+
+    #[test]
+    fn encode_decode() {
+        let mut codec = BoltCodec::new();
+        let mut bytes = BytesMut::new();
+        codec.encode(new_record(), &mut bytes).unwrap();
+        codec.encode(Message::PullAll, &mut bytes).unwrap();
+        assert_eq!(codec.decode(&mut bytes).unwrap(), Some(new_record()));
+        assert_eq!(codec.decode(&mut bytes).unwrap(), Some(Message::PullAll));
+        assert_eq!(codec.decode(&mut bytes).unwrap(), None);
+        assert!(bytes.is_empty());
+    }
+
+    #[test]
+    fn decode_partial_input() {
+        let mut codec = BoltCodec::new();
+        let mut encoded = BytesMut::new();
+        codec.encode(new_record(), &mut encoded).unwrap();
+
+        // Feed the message to the decoder one byte at a time
+        let mut bytes = BytesMut::new();
+        for (i, byte) in encoded.iter().enumerate() {
+            bytes.put_u8(*byte);
+            let decoded = codec.decode(&mut bytes).unwrap();
+            if i < encoded.len() - 1 {
+                assert_eq!(decoded, None);
+            } else {
+                assert_eq!(decoded, Some(new_record()));
+            }
+        }
+    }
+
+    #[test]
+    fn decode_multiple_chunks() {
+        let message = Message::Record(Record::new(vec![Value::from("a".repeat(40_000))]));
+        let mut codec = BoltCodec::new();
+        let mut bytes = BytesMut::new();
+        codec.encode(message.clone(), &mut bytes).unwrap();
+        assert_eq!(codec.decode(&mut bytes).unwrap(), Some(message));
+    }
+
+    #[test]
+    fn decode_skips_noop_chunks() {
+        let mut codec = BoltCodec::new();
+        let mut bytes = BytesMut::new();
+        bytes.put_u16(0);
+        bytes.put_u16(0);
+        codec.encode(Message::Ignored, &mut bytes).unwrap();
+        assert_eq!(codec.decode(&mut bytes).unwrap(), Some(Message::Ignored));
+    }
+
+    #[test]
+    fn decode_invalid_message() {
+        let mut codec = BoltCodec::new();
+        let mut bytes = BytesMut::from(&[0x00, 0x02, 0xB0, 0xFF, 0x00, 0x00][..]);
+        assert!(matches!(
+            codec.decode(&mut bytes),
+            Err(Error::DeserializationError(
+                DeserializationError::InvalidSignatureByte(0xFF)
+            ))
+        ));
+    }
+}
@@ -22,6 +22,8 @@ pub enum Error {
     InvalidTimeZoneOffset((i32, i32)),
     #[error("Invalid time zone ID: {0}")]
     InvalidTimeZoneId(String),
+    #[error("Invalid protocol version: {0:#010x}")]
+    InvalidProtocolVersion(u32),
     #[error(transparent)]
     ConversionError(#[from] ConversionError),
     #[error(transparent)]
@@ -34,16 +36,28 @@ pub enum ConversionError {
     FromValue(Value),
     #[error("Invalid conversion from message {0:?}")]
     FromMessage(Message),
+    #[error(
+        "Integer {0} is out of range for PackStream, which only supports 64-bit signed integers"
+    )]
+    IntegerOutOfRange(String),
+    #[error("Invalid map key {0:?}, PackStream map keys must be strings")]
+    InvalidMapKey(Value),
+    #[error("{0}")]
+    Serde(String),
 }
 
 #[derive(Debug, Error)]
 pub enum DeserializationError {
-    #[error("Panicked during deserialization")]
-    Panicked,
+    #[error("Unexpected end of input")]
+    UnexpectedEof,
     #[error("Invalid marker byte: {0:x}")]
     InvalidMarkerByte(u8),
     #[error("Invalid signature byte: {0:x}")]
     InvalidSignatureByte(u8),
     #[error("String deserialization failed: {0}")]
     InvalidUTF8(#[from] Utf8Error),
+    #[error("Invalid map key: {0:?}")]
+    InvalidMapKey(Value),
+    #[error("Values are nested too deeply")]
+    NestingTooDeep,
 }
@@ -1,11 +1,15 @@
+pub use codec::BoltCodec;
 pub use message::Message;
 pub use serialization::{Deserialize, Marker, Serialize, Signature};
 pub use value::Value;
+pub use version::ProtocolVersion;
 
+pub mod codec;
 pub mod error;
 pub mod message;
 mod serialization;
 pub mod value;
+pub mod version;
 
 #[doc(hidden)]
 #[macro_export]
@@ -48,7 +52,6 @@ macro_rules! impl_empty_message_tests {
         mod tests {
             use ::bytes::Bytes;
             use ::std::convert::TryFrom;
-            use ::std::sync::{Arc, Mutex};
 
             use crate::serialization::*;
 
@@ -78,7 +81,7 @@ macro_rules! impl_empty_message_tests {
                 let msg = $T;
                 let msg_bytes = &[];
                 assert_eq!(
-                    $T::try_from(Arc::new(Mutex::new(Bytes::from_static(msg_bytes)))).unwrap(),
+                    $T::try_from(&mut Bytes::from_static(msg_bytes)).unwrap(),
                     msg
                 );
             }
@@ -1,10 +1,7 @@
 use std::convert::{TryFrom, TryInto};
 use std::mem;
-use std::panic::catch_unwind;
-use std::sync::{Arc, Mutex};
 
 use bytes::Bytes;
-use tokio::io::BufStream;
 use tokio::prelude::*;
 
 // TODO: Do these need to be pub used?
@@ -12,6 +9,7 @@ pub use ack_failure::AckFailure;
 pub use begin::Begin;
 pub(crate) use chunk::Chunk;
 pub use commit::Commit;
+pub use discard::Discard;
 pub use discard_all::DiscardAll;
 pub use failure::Failure;
 pub use goodbye::Goodbye;
@@ -19,10 +17,13 @@ pub use hello::Hello;
 pub use ignored::Ignored;
 pub use init::Init;
 pub(crate) use message_bytes::MessageBytes;
+pub use pull::Pull;
 pub use pull_all::PullAll;
 pub use record::Record;
 pub use reset::Reset;
 pub use rollback::Rollback;
+pub use route::Route;
+pub use route_with_metadata::RouteWithMetadata;
 pub use run::Run;
 pub use run_with_metadata::RunWithMetadata;
 pub use success::Success;
@@ -33,16 +34,20 @@ use crate::serialization::*;
 pub(crate) mod ack_failure;
 pub(crate) mod begin;
 pub(crate) mod commit;
+pub(crate) mod discard;
 pub(crate) mod discard_all;
 pub(crate) mod failure;
 pub(crate) mod goodbye;
 pub(crate) mod hello;
 pub(crate) mod ignored;
 pub(crate) mod init;
+pub(crate) mod pull;
 pub(crate) mod pull_all;
 pub(crate) mod record;
 pub(crate) mod reset;
 pub(crate) mod rollback;
+pub(crate) mod route;
+pub(crate) mod route_with_metadata;
 pub(crate) mod run;
 pub(crate) mod run_with_metadata;
 pub(crate) mod success;
@@ -73,13 +78,20 @@ pub enum Message {
     Begin(Begin),
     Commit,
     Rollback,
+    // V4+-compatible message types
+    Discard(Discard),
+    Pull(Pull),
+    Route(Route),
+    // V4.4+-compatible message types
+    // Boxed, since it would otherwise make every message (and every error carrying one) larger
+    RouteWithMetadata(Box<RouteWithMetadata>),
 }
 
 impl Message {
-    pub async fn from_stream<T: Unpin + AsyncRead + AsyncWrite>(
-        buf_stream: &mut BufStream<T>,
-    ) -> Result<Message> {
-        Message::try_from(MessageBytes::from_stream(buf_stream).await?)
+    /// Read a single message from the given stream. For framing messages over a transport, see
+    /// [`BoltCodec`](crate::BoltCodec).
+    pub async fn from_stream(stream: &mut (impl AsyncRead + Unpin)) -> Result<Message> {
+        Message::try_from(MessageBytes::from_stream(stream).await?)
     }
 }
 
@@ -102,6 +114,10 @@ impl Marker for Message {
             Message::Begin(begin) => begin.get_marker(),
             Message::Commit => Commit.get_marker(),
             Message::Rollback => Rollback.get_marker(),
+            Message::Discard(discard) => discard.get_marker(),
+            Message::Pull(pull) => pull.get_marker(),
+            Message::Route(route) => route.get_marker(),
+            Message::RouteWithMetadata(route_with_metadata) => route_with_metadata.get_marker(),
         }
     }
 }
@@ -125,6 +141,10 @@ impl Signature for Message {
             Message::Begin(begin) => begin.get_signature(),
             Message::Commit => Commit.get_signature(),
             Message::Rollback => Rollback.get_signature(),
+            Message::Discard(discard) => discard.get_signature(),
+            Message::Pull(pull) => pull.get_signature(),
+            Message::Route(route) => route.get_signature(),
+            Message::RouteWithMetadata(route_with_metadata) => route_with_metadata.get_signature(),
         }
     }
 }
@@ -152,67 +172,92 @@ impl TryInto<Bytes> for Message {
             Message::Begin(begin) => begin.try_into(),
             Message::Commit => Commit.try_into(),
             Message::Rollback => Rollback.try_into(),
+            Message::Discard(discard) => discard.try_into(),
+            Message::Pull(pull) => pull.try_into(),
+            Message::Route(route) => route.try_into(),
+            Message::RouteWithMetadata(route_with_metadata) => (*route_with_metadata).try_into(),
         }
     }
 }
 
 impl Deserialize for Message {}
 
-impl TryFrom<Arc<Mutex<Bytes>>> for Message {
+impl TryFrom<MessageBytes> for Message {
     type Error = Error;
 
-    fn try_from(value: Arc<Mutex<Bytes>>) -> Result<Self> {
-        let message_bytes = MessageBytes::try_from(value)?;
-        Message::try_from(message_bytes)
+    fn try_from(message_bytes: MessageBytes) -> Result<Self> {
+        Message::try_from(&mut message_bytes.freeze())
     }
 }
 
-impl TryFrom<MessageBytes> for Message {
+impl TryFrom<&mut Bytes> for Message {
     type Error = Error;
 
-    fn try_from(mut message_bytes: MessageBytes) -> Result<Self> {
-        catch_unwind(move || {
-            let (marker, signature) = get_info_from_bytes(&mut message_bytes)?;
-            let remaining_bytes_arc =
-                Arc::new(Mutex::new(message_bytes.split_to(message_bytes.len())));
-
-            match signature {
-                init::SIGNATURE => {
-                    // Equal to hello::SIGNATURE, so we have to check for metadata.
-                    // INIT has 2 fields, while HELLO has 1.
-                    if marker == init::MARKER {
-                        Ok(Message::Init(Init::try_from(remaining_bytes_arc)?))
-                    } else {
-                        Ok(Message::Hello(Hello::try_from(remaining_bytes_arc)?))
-                    }
+    fn try_from(input_bytes: &mut Bytes) -> Result<Self> {
+        let (marker, signature) = get_info_from_bytes(input_bytes)?;
+
+        match signature {
+            init::SIGNATURE => {
+                // Equal to hello::SIGNATURE, so we have to check for metadata.
+                // INIT has 2 fields, while HELLO has 1.
+                if marker == init::MARKER {
+                    Ok(Message::Init(Init::try_from(input_bytes)?))
+                } else {
+                    Ok(Message::Hello(Hello::try_from(input_bytes)?))
                 }
-                run::SIGNATURE => {
-                    // Equal to run_with_metadata::SIGNATURE, so we have to check for metadata.
-                    // RUN has 2 fields, while RUN_WITH_METADATA has 3.
-                    if marker == run::MARKER {
-                        Ok(Message::Run(Run::try_from(remaining_bytes_arc)?))
-                    } else {
-                        Ok(Message::RunWithMetadata(RunWithMetadata::try_from(
-                            remaining_bytes_arc,
-                        )?))
-                    }
+            }
+            run::SIGNATURE => {
+                // Equal to run_with_metadata::SIGNATURE, so we have to check for metadata.
+                // RUN has 2 fields, while RUN_WITH_METADATA has 3.
+                if marker == run::MARKER {
+                    Ok(Message::Run(Run::try_from(input_bytes)?))
+                } else {
+                    Ok(Message::RunWithMetadata(RunWithMetadata::try_from(
+                        input_bytes,
+                    )?))
+                }
+            }
+            discard_all::SIGNATURE => {
+                // Equal to discard::SIGNATURE, so we have to check for metadata.
+                // DISCARD_ALL has 0 fields, while DISCARD has 1.
+                if marker == discard_all::MARKER {
+                    Ok(Message::DiscardAll)
+                } else {
+                    Ok(Message::Discard(Discard::try_from(input_bytes)?))
                 }
-                discard_all::SIGNATURE => Ok(Message::DiscardAll),
-                pull_all::SIGNATURE => Ok(Message::PullAll),
-                ack_failure::SIGNATURE => Ok(Message::AckFailure),
-                reset::SIGNATURE => Ok(Message::Reset),
-                record::SIGNATURE => Ok(Message::Record(Record::try_from(remaining_bytes_arc)?)),
-                success::SIGNATURE => Ok(Message::Success(Success::try_from(remaining_bytes_arc)?)),
-                failure::SIGNATURE => Ok(Message::Failure(Failure::try_from(remaining_bytes_arc)?)),
-                ignored::SIGNATURE => Ok(Message::Ignored),
-                goodbye::SIGNATURE => Ok(Message::Goodbye),
-                begin::SIGNATURE => Ok(Message::Begin(Begin::try_from(remaining_bytes_arc)?)),
-                commit::SIGNATURE => Ok(Message::Commit),
-                rollback::SIGNATURE => Ok(Message::Rollback),
-                _ => Err(DeserializationError::InvalidSignatureByte(signature).into()),
             }
-        })
-        .map_err(|_| DeserializationError::Panicked)?
+            pull_all::SIGNATURE => {
+                // Equal to pull::SIGNATURE, so we have to check for metadata.
+                // PULL_ALL has 0 fields, while PULL has 1.
+                if marker == pull_all::MARKER {
+                    Ok(Message::PullAll)
+                } else {
+                    Ok(Message::Pull(Pull::try_from(input_bytes)?))
+                }
+            }
+            ack_failure::SIGNATURE => Ok(Message::AckFailure),
+            reset::SIGNATURE => Ok(Message::Reset),
+            record::SIGNATURE => Ok(Message::Record(Record::try_from(input_bytes)?)),
+            success::SIGNATURE => Ok(Message::Success(Success::try_from(input_bytes)?)),
+            failure::SIGNATURE => Ok(Message::Failure(Failure::try_from(input_bytes)?)),
+            ignored::SIGNATURE => Ok(Message::Ignored),
+            goodbye::SIGNATURE => Ok(Message::Goodbye),
+            begin::SIGNATURE => Ok(Message::Begin(Begin::try_from(input_bytes)?)),
+            commit::SIGNATURE => Ok(Message::Commit),
+            rollback::SIGNATURE => Ok(Message::Rollback),
+            route::SIGNATURE => {
+                // Equal to route_with_metadata::SIGNATURE, and both have 3 fields, so we have to check the type of the
+                // last field. ROUTE (v4.3) ends with the database name or null, while ROUTE_WITH_METADATA (v4.4+)
+                // ends with a map.
+                match Route::try_from(&mut input_bytes.clone()) {
+                    Ok(route) => Ok(Message::Route(route)),
+                    Err(_) => Ok(Message::RouteWithMetadata(Box::new(
+                        RouteWithMetadata::try_from(input_bytes)?,
+                    ))),
+                }
+            }
+            _ => Err(DeserializationError::InvalidSignatureByte(signature).into()),
+        }
     }
 }
 
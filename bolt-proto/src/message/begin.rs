@@ -17,6 +17,24 @@ impl Begin {
         Self { metadata }
     }
 
+    /// Add the given bookmarks to the metadata, so the transaction only begins once the server has caught up with
+    /// the transactions they refer to. Nothing is added if there are no bookmarks.
+    pub fn with_bookmarks(mut self, bookmarks: Vec<String>) -> Self {
+        if !bookmarks.is_empty() {
+            self.metadata
+                .insert("bookmarks".to_string(), Value::from(bookmarks));
+        }
+        self
+    }
+
+    /// Set the database to begin the transaction against (Bolt v4+). If no database is set, the server's default database is
+    /// used.
+    pub fn with_db(mut self, db: impl Into<String>) -> Self {
+        self.metadata
+            .insert("db".to_string(), Value::from(db.into()));
+        self
+    }
+
     pub fn metadata(&self) -> &HashMap<String, Value> {
         &self.metadata
     }
@@ -29,7 +47,6 @@ mod tests {
     use bytes::Bytes;
     use std::convert::TryFrom;
     use std::iter::FromIterator;
-    use std::sync::{Arc, Mutex};
 
     use crate::serialization::*;
     use crate::value::*;
@@ -101,8 +118,25 @@ mod tests {
             b'h',
         ];
         assert_eq!(
-            Begin::try_from(Arc::new(Mutex::new(Bytes::from_static(msg_bytes)))).unwrap(),
+            Begin::try_from(&mut Bytes::from_static(msg_bytes)).unwrap(),
             msg
         );
     }
+
+    #[test]
+    fn with_bookmarks() {
+        let msg = Begin::new(HashMap::new()).with_bookmarks(vec!["bm:1".to_string()]);
+        assert_eq!(
+            msg.metadata().get("bookmarks"),
+            Some(&Value::from(vec!["bm:1"]))
+        );
+        let msg = Begin::new(HashMap::new()).with_bookmarks(vec![]);
+        assert!(msg.metadata().is_empty());
+    }
+
+    #[test]
+    fn with_db() {
+        let msg = Begin::new(HashMap::new()).with_db("people");
+        assert_eq!(msg.metadata().get("db"), Some(&Value::from("people")));
+    }
 }
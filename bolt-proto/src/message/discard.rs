@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use bolt_proto_derive::*;
+
+use crate::{impl_try_from_message, Value};
+
+pub(crate) const MARKER: u8 = 0xB1;
+pub(crate) const SIGNATURE: u8 = 0x2F;
+
+#[derive(Debug, Clone, Eq, PartialEq, Signature, Marker, Serialize, Deserialize)]
+pub struct Discard {
+    pub(crate) metadata: HashMap<String, Value>,
+}
+
+impl Discard {
+    pub fn new(metadata: HashMap<String, Value>) -> Self {
+        Self { metadata }
+    }
+
+    /// Set the number of records to discard. `-1` discards all remaining records.
+    pub fn with_n(mut self, n: i64) -> Self {
+        self.metadata.insert("n".to_string(), Value::from(n));
+        self
+    }
+
+    /// Set the id of the statement to discard records from, as returned in the `SUCCESS` metadata of a `RUN` inside
+    /// an explicit transaction. `-1` refers to the most recent statement.
+    pub fn with_qid(mut self, qid: i64) -> Self {
+        self.metadata.insert("qid".to_string(), Value::from(qid));
+        self
+    }
+
+    /// Get the number of records to discard, if set.
+    pub fn n(&self) -> Option<i64> {
+        self.metadata
+            .get("n")
+            .cloned()
+            .and_then(|n| i64::try_from(n).ok())
+    }
+
+    /// Get the id of the statement to discard records from, if set.
+    pub fn qid(&self) -> Option<i64> {
+        self.metadata
+            .get("qid")
+            .cloned()
+            .and_then(|qid| i64::try_from(qid).ok())
+    }
+
+    pub fn metadata(&self) -> &HashMap<String, Value> {
+        &self.metadata
+    }
+}
+
+impl_try_from_message!(Discard, Discard);
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+    use std::iter::FromIterator;
+
+    use bytes::Bytes;
+
+    use crate::serialization::*;
+    use crate::value::*;
+
+    use super::*;
+
+    fn new_msg() -> Discard {
+        Discard::new(HashMap::from_iter(vec![(
+            "n".to_string(),
+            Value::from(1000_i16),
+        )]))
+    }
+
+    #[test]
+    fn get_marker() {
+        assert_eq!(new_msg().get_marker().unwrap(), MARKER);
+    }
+
+    #[test]
+    fn get_signature() {
+        assert_eq!(new_msg().get_signature(), SIGNATURE);
+    }
+
+    #[test]
+    fn try_into_bytes() {
+        let msg = new_msg();
+        assert_eq!(
+            msg.try_into_bytes().unwrap(),
+            Bytes::from_static(&[
+                MARKER,
+                SIGNATURE,
+                map::MARKER_TINY | 1,
+                string::MARKER_TINY | 1,
+                b'n',
+                integer::MARKER_INT_16,
+                0x03,
+                0xE8,
+            ])
+        );
+    }
+
+    #[test]
+    fn try_from_bytes() {
+        let msg = new_msg();
+        let msg_bytes = &[
+            map::MARKER_TINY | 1,
+            string::MARKER_TINY | 1,
+            b'n',
+            integer::MARKER_INT_16,
+            0x03,
+            0xE8,
+        ];
+        assert_eq!(
+            Discard::try_from(&mut Bytes::from_static(msg_bytes)).unwrap(),
+            msg
+        );
+    }
+
+    #[test]
+    fn n_and_qid() {
+        assert_eq!(new_msg().n(), Some(1000));
+        assert_eq!(new_msg().qid(), None);
+        let msg = Discard::new(HashMap::new()).with_n(-1).with_qid(2);
+        assert_eq!(msg.n(), Some(-1));
+        assert_eq!(msg.qid(), Some(2));
+    }
+}
@@ -28,7 +28,6 @@ impl_try_from_message!(Failure, Failure);
 mod tests {
     use std::convert::TryFrom;
     use std::iter::FromIterator;
-    use std::sync::{Arc, Mutex};
 
     use bytes::Bytes;
 
@@ -112,7 +111,7 @@ mod tests {
             0x17,
         ];
         assert_eq!(
-            Failure::try_from(Arc::new(Mutex::new(Bytes::from_static(msg_bytes)))).unwrap(),
+            Failure::try_from(&mut Bytes::from_static(msg_bytes)).unwrap(),
             msg
         );
     }
@@ -17,6 +17,15 @@ impl Hello {
         Self { metadata }
     }
 
+    /// Set the routing context (Bolt v4.1+), indicating that the server should route queries for the client. The
+    /// context is usually the set of query parameters from the connection URI, along with the initial server address
+    /// under the `address` key.
+    pub fn with_routing(mut self, context: HashMap<String, Value>) -> Self {
+        self.metadata
+            .insert("routing".to_string(), Value::from(context));
+        self
+    }
+
     pub fn metadata(&self) -> &HashMap<String, Value> {
         &self.metadata
     }
@@ -28,7 +37,6 @@ impl_try_from_message!(Hello, Hello);
 mod tests {
     use std::convert::TryFrom;
     use std::iter::FromIterator;
-    use std::sync::{Arc, Mutex};
 
     use bytes::Bytes;
 
@@ -122,8 +130,15 @@ mod tests {
             b'0',
         ];
         assert_eq!(
-            Hello::try_from(Arc::new(Mutex::new(Bytes::from_static(msg_bytes)))).unwrap(),
+            Hello::try_from(&mut Bytes::from_static(msg_bytes)).unwrap(),
             msg
         );
     }
+
+    #[test]
+    fn with_routing() {
+        let context = HashMap::from_iter(vec![("address".to_string(), Value::from("a:1"))]);
+        let msg = new_msg().with_routing(context.clone());
+        assert_eq!(msg.metadata().get("routing"), Some(&Value::from(context)));
+    }
 }
@@ -36,7 +36,6 @@ impl_try_from_message!(Init, Init);
 mod tests {
     use std::convert::TryFrom;
     use std::iter::FromIterator;
-    use std::sync::{Arc, Mutex};
 
     use bytes::Bytes;
 
@@ -134,7 +133,7 @@ mod tests {
             b'c',
         ];
         assert_eq!(
-            Init::try_from(Arc::new(Mutex::new(Bytes::from_static(msg_bytes)))).unwrap(),
+            Init::try_from(&mut Bytes::from_static(msg_bytes)).unwrap(),
             msg
         );
     }
@@ -1,9 +1,7 @@
 use std::convert::{TryFrom, TryInto};
 use std::mem;
-use std::sync::{Arc, Mutex};
 
 use bytes::{Buf, BufMut, Bytes, BytesMut};
-use tokio::io::BufStream;
 use tokio::prelude::*;
 
 use crate::error::*;
@@ -26,25 +24,23 @@ impl MessageBytes {
         self.bytes.len()
     }
 
-    pub(crate) fn split_to(&mut self, at: usize) -> Bytes {
-        self.bytes.split_to(at).freeze()
+    pub(crate) fn freeze(self) -> Bytes {
+        self.bytes.freeze()
     }
 
     fn add_chunk(&mut self, chunk: Chunk) {
         self.bytes.put(chunk.data);
     }
 
-    pub(crate) async fn from_stream<T: Unpin + AsyncRead + AsyncWrite>(
-        buf_stream: &mut BufStream<T>,
-    ) -> Result<MessageBytes> {
+    pub(crate) async fn from_stream(stream: &mut (impl AsyncRead + Unpin)) -> Result<MessageBytes> {
         let mut message = MessageBytes::new();
-        let mut remaining_bytes = buf_stream.read_u16().await? as usize;
+        let mut remaining_bytes = stream.read_u16().await? as usize;
         // Messages end in a 0_u16
         while remaining_bytes > 0 {
             let mut buf = vec![0; remaining_bytes];
-            buf_stream.read_exact(&mut buf).await?;
+            stream.read_exact(&mut buf).await?;
             message.add_chunk(Chunk::try_from(Bytes::from(buf))?);
-            remaining_bytes = buf_stream.read_u16().await? as usize;
+            remaining_bytes = stream.read_u16().await? as usize;
         }
         Ok(message)
     }
@@ -81,12 +77,11 @@ impl TryInto<Bytes> for MessageBytes {
 
 impl Deserialize for MessageBytes {}
 
-impl TryFrom<Arc<Mutex<Bytes>>> for MessageBytes {
+impl TryFrom<&mut Bytes> for MessageBytes {
     type Error = Error;
 
-    fn try_from(value: Arc<Mutex<Bytes>>) -> Result<Self> {
-        let bytes: &[u8] = &*value.lock().unwrap().clone();
-        let bytes = BytesMut::from(bytes);
+    fn try_from(input_bytes: &mut Bytes) -> Result<Self> {
+        let bytes = BytesMut::from(input_bytes.split_to(input_bytes.len()).as_ref());
         Ok(Self { bytes })
     }
 }
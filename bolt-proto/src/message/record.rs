@@ -27,10 +27,10 @@ mod tests {
     use std::collections::HashMap;
     use std::convert::TryFrom;
     use std::iter::FromIterator;
-    use std::sync::{Arc, Mutex};
 
     use bytes::Bytes;
 
+    use crate::error::*;
     use crate::serialization::*;
     use crate::value::*;
 
@@ -123,8 +123,20 @@ mod tests {
             b'e',
         ];
         assert_eq!(
-            Record::try_from(Arc::new(Mutex::new(Bytes::from_static(msg_bytes)))).unwrap(),
+            Record::try_from(&mut Bytes::from_static(msg_bytes)).unwrap(),
             msg
         );
     }
+
+    #[test]
+    fn try_from_truncated_bytes() {
+        let msg_bytes = new_msg().try_into_bytes().unwrap();
+        // Skip the marker and signature, and leave off the last byte
+        assert!(matches!(
+            Record::try_from(&mut msg_bytes.slice(2..msg_bytes.len() - 1)),
+            Err(Error::DeserializationError(
+                DeserializationError::UnexpectedEof
+            ))
+        ));
+    }
 }
@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+
+use bolt_proto_derive::*;
+
+use crate::{impl_try_from_message, Value};
+
+pub(crate) const MARKER: u8 = 0xB3;
+pub(crate) const SIGNATURE: u8 = 0x66;
+
+#[derive(Debug, Clone, Eq, PartialEq, Signature, Marker, Serialize, Deserialize)]
+pub struct Route {
+    pub(crate) context: HashMap<String, Value>,
+    pub(crate) bookmarks: Vec<String>,
+    pub(crate) db: Option<String>,
+}
+
+impl Route {
+    pub fn new(
+        context: HashMap<String, Value>,
+        bookmarks: Vec<String>,
+        db: Option<String>,
+    ) -> Self {
+        Self {
+            context,
+            bookmarks,
+            db,
+        }
+    }
+
+    pub fn context(&self) -> &HashMap<String, Value> {
+        &self.context
+    }
+
+    pub fn bookmarks(&self) -> &[String] {
+        &self.bookmarks
+    }
+
+    pub fn db(&self) -> Option<&str> {
+        self.db.as_deref()
+    }
+}
+
+impl_try_from_message!(Route, Route);
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+    use std::iter::FromIterator;
+
+    use bytes::Bytes;
+
+    use crate::serialization::*;
+    use crate::value::*;
+
+    use super::*;
+
+    fn new_msg() -> Route {
+        Route::new(
+            HashMap::from_iter(vec![("address".to_string(), Value::from("a:1"))]),
+            vec!["bm".to_string()],
+            None,
+        )
+    }
+
+    #[test]
+    fn get_marker() {
+        assert_eq!(new_msg().get_marker().unwrap(), MARKER);
+    }
+
+    #[test]
+    fn get_signature() {
+        assert_eq!(new_msg().get_signature(), SIGNATURE);
+    }
+
+    #[test]
+    fn try_into_bytes() {
+        let msg = new_msg();
+        assert_eq!(
+            msg.try_into_bytes().unwrap(),
+            Bytes::from_static(&[
+                MARKER,
+                SIGNATURE,
+                map::MARKER_TINY | 1,
+                string::MARKER_TINY | 7,
+                b'a',
+                b'd',
+                b'd',
+                b'r',
+                b'e',
+                b's',
+                b's',
+                string::MARKER_TINY | 3,
+                b'a',
+                b':',
+                b'1',
+                list::MARKER_TINY | 1,
+                string::MARKER_TINY | 2,
+                b'b',
+                b'm',
+                null::MARKER,
+            ])
+        );
+    }
+
+    #[test]
+    fn try_from_bytes() {
+        let msg = new_msg();
+        let msg_bytes = &[
+            map::MARKER_TINY | 1,
+            string::MARKER_TINY | 7,
+            b'a',
+            b'd',
+            b'd',
+            b'r',
+            b'e',
+            b's',
+            b's',
+            string::MARKER_TINY | 3,
+            b'a',
+            b':',
+            b'1',
+            list::MARKER_TINY | 1,
+            string::MARKER_TINY | 2,
+            b'b',
+            b'm',
+            null::MARKER,
+        ];
+        assert_eq!(
+            Route::try_from(&mut Bytes::from_static(msg_bytes)).unwrap(),
+            msg
+        );
+
+        let msg_bytes = &[
+            map::MARKER_TINY,
+            list::MARKER_TINY,
+            string::MARKER_TINY | 3,
+            b'f',
+            b'o',
+            b'o',
+        ];
+        assert_eq!(
+            Route::try_from(&mut Bytes::from_static(msg_bytes)).unwrap(),
+            Route::new(HashMap::new(), vec![], Some("foo".to_string()))
+        );
+    }
+}
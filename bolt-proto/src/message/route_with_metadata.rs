@@ -0,0 +1,184 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use bolt_proto_derive::*;
+
+use crate::error::*;
+use crate::{Message, Value};
+
+pub(crate) const MARKER: u8 = 0xB3;
+pub(crate) const SIGNATURE: u8 = 0x66;
+
+#[derive(Debug, Clone, Eq, PartialEq, Signature, Marker, Serialize, Deserialize)]
+pub struct RouteWithMetadata {
+    pub(crate) context: HashMap<String, Value>,
+    pub(crate) bookmarks: Vec<String>,
+    pub(crate) metadata: HashMap<String, Value>,
+}
+
+impl RouteWithMetadata {
+    pub fn new(
+        context: HashMap<String, Value>,
+        bookmarks: Vec<String>,
+        metadata: HashMap<String, Value>,
+    ) -> Self {
+        Self {
+            context,
+            bookmarks,
+            metadata,
+        }
+    }
+
+    pub fn context(&self) -> &HashMap<String, Value> {
+        &self.context
+    }
+
+    pub fn bookmarks(&self) -> &[String] {
+        &self.bookmarks
+    }
+
+    /// Set the database to retrieve the routing table for. If no database is set, the routing table for the server's
+    /// default database is returned.
+    pub fn with_db(mut self, db: impl Into<String>) -> Self {
+        self.metadata
+            .insert("db".to_string(), Value::from(db.into()));
+        self
+    }
+
+    pub fn metadata(&self) -> &HashMap<String, Value> {
+        &self.metadata
+    }
+}
+
+impl TryFrom<Message> for RouteWithMetadata {
+    type Error = Error;
+
+    fn try_from(message: Message) -> Result<Self> {
+        match message {
+            Message::RouteWithMetadata(route_with_metadata) => Ok(*route_with_metadata),
+            _ => Err(ConversionError::FromMessage(message).into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::iter::FromIterator;
+
+    use bytes::Bytes;
+
+    use crate::message::Route;
+    use crate::serialization::*;
+    use crate::value::*;
+
+    use super::*;
+
+    fn new_msg() -> RouteWithMetadata {
+        RouteWithMetadata::new(
+            HashMap::from_iter(vec![("address".to_string(), Value::from("a:1"))]),
+            vec!["bm".to_string()],
+            HashMap::new(),
+        )
+        .with_db("foo")
+    }
+
+    #[test]
+    fn get_marker() {
+        assert_eq!(new_msg().get_marker().unwrap(), MARKER);
+    }
+
+    #[test]
+    fn get_signature() {
+        assert_eq!(new_msg().get_signature(), SIGNATURE);
+    }
+
+    #[test]
+    fn try_into_bytes() {
+        let msg = new_msg();
+        assert_eq!(
+            msg.try_into_bytes().unwrap(),
+            Bytes::from_static(&[
+                MARKER,
+                SIGNATURE,
+                map::MARKER_TINY | 1,
+                string::MARKER_TINY | 7,
+                b'a',
+                b'd',
+                b'd',
+                b'r',
+                b'e',
+                b's',
+                b's',
+                string::MARKER_TINY | 3,
+                b'a',
+                b':',
+                b'1',
+                list::MARKER_TINY | 1,
+                string::MARKER_TINY | 2,
+                b'b',
+                b'm',
+                map::MARKER_TINY | 1,
+                string::MARKER_TINY | 2,
+                b'd',
+                b'b',
+                string::MARKER_TINY | 3,
+                b'f',
+                b'o',
+                b'o',
+            ])
+        );
+    }
+
+    #[test]
+    fn try_from_bytes() {
+        let msg = new_msg();
+        let msg_bytes = &[
+            map::MARKER_TINY | 1,
+            string::MARKER_TINY | 7,
+            b'a',
+            b'd',
+            b'd',
+            b'r',
+            b'e',
+            b's',
+            b's',
+            string::MARKER_TINY | 3,
+            b'a',
+            b':',
+            b'1',
+            list::MARKER_TINY | 1,
+            string::MARKER_TINY | 2,
+            b'b',
+            b'm',
+            map::MARKER_TINY | 1,
+            string::MARKER_TINY | 2,
+            b'd',
+            b'b',
+            string::MARKER_TINY | 3,
+            b'f',
+            b'o',
+            b'o',
+        ];
+        assert_eq!(
+            RouteWithMetadata::try_from(&mut Bytes::from_static(msg_bytes)).unwrap(),
+            msg
+        );
+    }
+
+    #[test]
+    fn message_try_from_bytes() {
+        // ROUTE and ROUTE_WITH_METADATA share a signature, and are told apart by their last field
+        let mut bytes = new_msg().try_into_bytes().unwrap();
+        assert_eq!(
+            Message::try_from(&mut bytes).unwrap(),
+            Message::RouteWithMetadata(Box::new(new_msg()))
+        );
+
+        let route = Route::new(HashMap::new(), vec![], Some("foo".to_string()));
+        let mut bytes = route.clone().try_into_bytes().unwrap();
+        assert_eq!(
+            Message::try_from(&mut bytes).unwrap(),
+            Message::Route(route)
+        );
+    }
+}
@@ -36,7 +36,6 @@ impl_try_from_message!(Run, Run);
 mod tests {
     use std::convert::TryFrom;
     use std::iter::FromIterator;
-    use std::sync::{Arc, Mutex};
 
     use bytes::Bytes;
 
@@ -142,7 +141,7 @@ mod tests {
             0x23,
         ];
         assert_eq!(
-            Run::try_from(Arc::new(Mutex::new(Bytes::from_static(msg_bytes)))).unwrap(),
+            Run::try_from(&mut Bytes::from_static(msg_bytes)).unwrap(),
             msg
         );
     }
@@ -35,6 +35,24 @@ impl RunWithMetadata {
         &self.parameters
     }
 
+    /// Add the given bookmarks to the metadata, so the statement is only executed once the server has caught up with
+    /// the transactions they refer to. Nothing is added if there are no bookmarks.
+    pub fn with_bookmarks(mut self, bookmarks: Vec<String>) -> Self {
+        if !bookmarks.is_empty() {
+            self.metadata
+                .insert("bookmarks".to_string(), Value::from(bookmarks));
+        }
+        self
+    }
+
+    /// Set the database to execute the statement against (Bolt v4+). If no database is set, the server's default database is
+    /// used.
+    pub fn with_db(mut self, db: impl Into<String>) -> Self {
+        self.metadata
+            .insert("db".to_string(), Value::from(db.into()));
+        self
+    }
+
     pub fn metadata(&self) -> &HashMap<String, Value> {
         &self.metadata
     }
@@ -46,7 +64,6 @@ impl_try_from_message!(RunWithMetadata, RunWithMetadata);
 mod tests {
     use std::convert::TryFrom;
     use std::iter::FromIterator;
-    use std::sync::{Arc, Mutex};
 
     use bytes::Bytes;
 
@@ -145,8 +162,25 @@ mod tests {
             b'y',
         ];
         assert_eq!(
-            RunWithMetadata::try_from(Arc::new(Mutex::new(Bytes::from_static(msg_bytes)))).unwrap(),
+            RunWithMetadata::try_from(&mut Bytes::from_static(msg_bytes)).unwrap(),
             msg
         );
     }
+
+    #[test]
+    fn with_bookmarks() {
+        let msg = new_msg().with_bookmarks(vec!["bm:1".to_string(), "bm:2".to_string()]);
+        assert_eq!(
+            msg.metadata().get("bookmarks"),
+            Some(&Value::from(vec!["bm:1", "bm:2"]))
+        );
+        assert_eq!(new_msg().with_bookmarks(vec![]), new_msg());
+    }
+
+    #[test]
+    fn with_db() {
+        let msg = new_msg().with_db("people");
+        assert_eq!(msg.metadata().get("db"), Some(&Value::from("people")));
+        assert_eq!(msg.metadata().get("arbitrary"), Some(&Value::from("any")));
+    }
 }
@@ -20,6 +20,14 @@ impl Success {
     pub fn metadata(&self) -> &HashMap<String, Value> {
         &self.metadata
     }
+
+    /// Get the bookmark identifying the transaction committed by the request this message responds to, if any.
+    pub fn bookmark(&self) -> Option<&str> {
+        match self.metadata.get("bookmark") {
+            Some(Value::String(bookmark)) => Some(bookmark),
+            _ => None,
+        }
+    }
 }
 
 impl_try_from_message!(Success, Success);
@@ -28,7 +36,6 @@ impl_try_from_message!(Success, Success);
 mod tests {
     use std::convert::TryFrom;
     use std::iter::FromIterator;
-    use std::sync::{Arc, Mutex};
 
     use bytes::Bytes;
 
@@ -100,8 +107,18 @@ mod tests {
             0x03,
         ];
         assert_eq!(
-            Success::try_from(Arc::new(Mutex::new(Bytes::from_static(msg_bytes)))).unwrap(),
+            Success::try_from(&mut Bytes::from_static(msg_bytes)).unwrap(),
             msg
         );
     }
+
+    #[test]
+    fn bookmark() {
+        assert_eq!(new_msg().bookmark(), None);
+        let msg = Success::new(HashMap::from_iter(vec![(
+            "bookmark".to_string(),
+            Value::from("bm:1"),
+        )]));
+        assert_eq!(msg.bookmark(), Some("bm:1"));
+    }
 }
@@ -1,5 +1,5 @@
 use std::convert::{TryFrom, TryInto};
-use std::sync::{Arc, Mutex};
+use std::mem;
 
 use bytes::{Buf, Bytes};
 
@@ -11,7 +11,7 @@ pub trait Serialize: TryInto<Bytes, Error = Error> {
     }
 }
 
-pub trait Deserialize: TryFrom<Arc<Mutex<Bytes>>, Error = Error> {}
+pub trait Deserialize: for<'a> TryFrom<&'a mut Bytes, Error = Error> {}
 
 pub trait Marker: Serialize + Deserialize {
     fn get_marker(&self) -> Result<u8>;
@@ -25,19 +25,67 @@ pub(crate) const STRUCT_MARKER_TINY: u8 = 0xB0;
 pub(crate) const STRUCT_MARKER_SMALL: u8 = 0xDC;
 pub(crate) const STRUCT_MARKER_MEDIUM: u8 = 0xDD;
 
-// Might panic. Use this inside a catch_unwind block
 pub(crate) fn get_info_from_bytes(bytes: &mut impl Buf) -> Result<(u8, u8)> {
-    let marker = bytes.get_u8();
+    let marker = bytes.try_get_u8()?;
     let _size = match marker {
         marker if (STRUCT_MARKER_TINY..=(STRUCT_MARKER_TINY | 0x0F)).contains(&marker) => {
             0x0F & marker as usize
         }
-        STRUCT_MARKER_SMALL => bytes.get_u8() as usize,
-        STRUCT_MARKER_MEDIUM => bytes.get_u16() as usize,
+        STRUCT_MARKER_SMALL => bytes.try_get_u8()? as usize,
+        STRUCT_MARKER_MEDIUM => bytes.try_get_u16()? as usize,
         _ => {
             return Err(DeserializationError::InvalidMarkerByte(marker).into());
         }
     };
-    let signature = bytes.get_u8();
+    let signature = bytes.try_get_u8()?;
     Ok((marker, signature))
 }
+
+macro_rules! impl_try_get {
+    ($($name:ident => $get:ident: $T:ty),*) => {
+        $(
+            fn $name(&mut self) -> Result<$T> {
+                self.ensure_remaining(mem::size_of::<$T>())?;
+                Ok(self.$get())
+            }
+        )*
+    };
+}
+
+// Bounds-checked versions of the Buf getters, which return an error on truncated input instead of panicking
+pub(crate) trait BufExt: Buf {
+    fn ensure_remaining(&self, size: usize) -> Result<()> {
+        if self.remaining() < size {
+            Err(DeserializationError::UnexpectedEof.into())
+        } else {
+            Ok(())
+        }
+    }
+
+    impl_try_get!(
+        try_get_u8 => get_u8: u8,
+        try_get_i8 => get_i8: i8,
+        try_get_u16 => get_u16: u16,
+        try_get_i16 => get_i16: i16,
+        try_get_u32 => get_u32: u32,
+        try_get_i32 => get_i32: i32,
+        try_get_i64 => get_i64: i64,
+        try_get_f64 => get_f64: f64
+    );
+}
+
+impl<B: Buf> BufExt for B {}
+
+// Split off the next `size` bytes without copying them
+pub(crate) fn try_split_to(bytes: &mut Bytes, size: usize) -> Result<Bytes> {
+    bytes.ensure_remaining(size)?;
+    Ok(bytes.split_to(size))
+}
+
+// Look at the next byte without consuming it
+pub(crate) fn peek_u8(bytes: &Bytes) -> Result<u8> {
+    bytes
+        .first()
+        .copied()
+        .ok_or_else(|| DeserializationError::UnexpectedEof.into())
+}
@@ -1,7 +1,6 @@
+use std::cell::Cell;
 use std::convert::{TryFrom, TryInto};
 use std::hash::{Hash, Hasher};
-use std::panic::catch_unwind;
-use std::sync::{Arc, Mutex};
 
 use bytes::{Buf, Bytes};
 
@@ -10,6 +9,7 @@ pub(crate) use byte_array::ByteArray;
 pub use date::Date;
 pub use date_time_offset::DateTimeOffset;
 pub use date_time_zoned::DateTimeZoned;
+pub use de::{from_record, from_value};
 pub use duration::Duration;
 pub(crate) use float::Float;
 pub(crate) use integer::Integer;
@@ -23,6 +23,7 @@ pub use path::Path;
 pub use point_2d::Point2D;
 pub use point_3d::Point3D;
 pub use relationship::Relationship;
+pub use ser::{to_parameters, to_value};
 pub(crate) use string::String;
 pub use time::Time;
 pub use unbound_relationship::UnboundRelationship;
@@ -36,6 +37,7 @@ pub(crate) mod conversions;
 pub(crate) mod date;
 pub(crate) mod date_time_offset;
 pub(crate) mod date_time_zoned;
+pub(crate) mod de;
 pub(crate) mod duration;
 pub(crate) mod float;
 pub(crate) mod integer;
@@ -49,6 +51,7 @@ pub(crate) mod path;
 pub(crate) mod point_2d;
 pub(crate) mod point_3d;
 pub(crate) mod relationship;
+pub(crate) mod ser;
 pub(crate) mod string;
 pub(crate) mod time;
 pub(crate) mod unbound_relationship;
@@ -182,96 +185,123 @@ impl TryInto<Bytes> for Value {
 
 impl Deserialize for Value {}
 
-impl TryFrom<Arc<Mutex<Bytes>>> for Value {
-    type Error = Error;
+// Lists, maps, and structures are decoded recursively, so malformed input could otherwise overflow the stack
+const MAX_NESTING_DEPTH: usize = 128;
+
+thread_local! {
+    static NESTING_DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+// Counts a value that is being decoded on this thread towards the nesting depth, until dropped
+struct NestingGuard;
 
-    fn try_from(input_arc: Arc<Mutex<Bytes>>) -> Result<Self> {
-        catch_unwind(move || {
-            let marker = input_arc.lock().unwrap().clone().get_u8();
-
-            match marker {
-                null::MARKER => {
-                    input_arc.lock().unwrap().advance(1);
-                    Ok(Value::Null)
-                }
-                boolean::MARKER_FALSE => {
-                    input_arc.lock().unwrap().advance(1);
-                    Ok(Value::Boolean(false))
-                }
-                boolean::MARKER_TRUE => {
-                    input_arc.lock().unwrap().advance(1);
-                    Ok(Value::Boolean(true))
-                }
-                // Tiny int
-                marker if (-16..=127).contains(&(marker as i8)) => {
-                    input_arc.lock().unwrap().advance(1);
-                    Ok(Value::Integer(Integer::from(marker as i8)))
-                }
-                // Other int types
-                integer::MARKER_INT_8
-                | integer::MARKER_INT_16
-                | integer::MARKER_INT_32
-                | integer::MARKER_INT_64 => Ok(Value::Integer(Integer::try_from(input_arc)?)),
-                float::MARKER => Ok(Value::Float(Float::try_from(input_arc)?.value)),
-                byte_array::MARKER_SMALL | byte_array::MARKER_MEDIUM | byte_array::MARKER_LARGE => {
-                    Ok(Value::Bytes(ByteArray::try_from(input_arc)?))
-                }
-                // Tiny string
-                marker
-                    if (string::MARKER_TINY..=(string::MARKER_TINY | 0x0F)).contains(&marker) =>
-                {
-                    Ok(Value::String(String::try_from(input_arc)?.value))
-                }
-                string::MARKER_SMALL | string::MARKER_MEDIUM | string::MARKER_LARGE => {
-                    Ok(Value::String(String::try_from(input_arc)?.value))
-                }
-                // Tiny list
-                marker if (list::MARKER_TINY..=(list::MARKER_TINY | 0x0F)).contains(&marker) => {
-                    Ok(Value::List(List::try_from(input_arc)?))
-                }
-                list::MARKER_SMALL | list::MARKER_MEDIUM | list::MARKER_LARGE => {
-                    Ok(Value::List(List::try_from(input_arc)?))
-                }
-                // Tiny map
-                marker if (map::MARKER_TINY..=(map::MARKER_TINY | 0x0F)).contains(&marker) => {
-                    Ok(Value::Map(Map::try_from(input_arc)?))
-                }
-                map::MARKER_SMALL | map::MARKER_MEDIUM | map::MARKER_LARGE => {
-                    Ok(Value::Map(Map::try_from(input_arc)?))
-                }
-                // Tiny structure
-                marker if (STRUCT_MARKER_TINY..=(STRUCT_MARKER_TINY | 0x0F)).contains(&marker) => {
-                    deserialize_structure(input_arc)
-                }
-                STRUCT_MARKER_SMALL | STRUCT_MARKER_MEDIUM => deserialize_structure(input_arc),
-                _ => Err(DeserializationError::InvalidMarkerByte(marker).into()),
+impl NestingGuard {
+    fn enter() -> Result<Self> {
+        NESTING_DEPTH.with(|depth| {
+            if depth.get() >= MAX_NESTING_DEPTH {
+                return Err(DeserializationError::NestingTooDeep.into());
             }
+            depth.set(depth.get() + 1);
+            Ok(NestingGuard)
         })
-        .map_err(|_| DeserializationError::Panicked)?
     }
 }
 
-// Might panic. Use this inside a catch_unwind block
-fn deserialize_structure(input_arc: Arc<Mutex<Bytes>>) -> Result<Value> {
-    let (_marker, signature) = get_info_from_bytes(&mut *input_arc.lock().unwrap())?;
+impl Drop for NestingGuard {
+    fn drop(&mut self) {
+        NESTING_DEPTH.with(|depth| depth.set(depth.get() - 1));
+    }
+}
+
+impl TryFrom<&mut Bytes> for Value {
+    type Error = Error;
+
+    fn try_from(input_bytes: &mut Bytes) -> Result<Self> {
+        let _guard = NestingGuard::enter()?;
+        let marker = peek_u8(input_bytes)?;
+
+        match marker {
+            null::MARKER => {
+                input_bytes.advance(1);
+                Ok(Value::Null)
+            }
+            boolean::MARKER_FALSE => {
+                input_bytes.advance(1);
+                Ok(Value::Boolean(false))
+            }
+            boolean::MARKER_TRUE => {
+                input_bytes.advance(1);
+                Ok(Value::Boolean(true))
+            }
+            // Tiny int
+            marker if (-16..=127).contains(&(marker as i8)) => {
+                input_bytes.advance(1);
+                Ok(Value::Integer(Integer::from(marker as i8)))
+            }
+            // Other int types
+            integer::MARKER_INT_8
+            | integer::MARKER_INT_16
+            | integer::MARKER_INT_32
+            | integer::MARKER_INT_64 => Ok(Value::Integer(Integer::try_from(input_bytes)?)),
+            float::MARKER => Ok(Value::Float(Float::try_from(input_bytes)?.value)),
+            byte_array::MARKER_SMALL | byte_array::MARKER_MEDIUM | byte_array::MARKER_LARGE => {
+                Ok(Value::Bytes(ByteArray::try_from(input_bytes)?))
+            }
+            // Tiny string
+            marker if (string::MARKER_TINY..=(string::MARKER_TINY | 0x0F)).contains(&marker) => {
+                Ok(Value::String(String::try_from(input_bytes)?.value))
+            }
+            string::MARKER_SMALL | string::MARKER_MEDIUM | string::MARKER_LARGE => {
+                Ok(Value::String(String::try_from(input_bytes)?.value))
+            }
+            // Tiny list
+            marker if (list::MARKER_TINY..=(list::MARKER_TINY | 0x0F)).contains(&marker) => {
+                Ok(Value::List(List::try_from(input_bytes)?))
+            }
+            list::MARKER_SMALL | list::MARKER_MEDIUM | list::MARKER_LARGE => {
+                Ok(Value::List(List::try_from(input_bytes)?))
+            }
+            // Tiny map
+            marker if (map::MARKER_TINY..=(map::MARKER_TINY | 0x0F)).contains(&marker) => {
+                Ok(Value::Map(Map::try_from(input_bytes)?))
+            }
+            map::MARKER_SMALL | map::MARKER_MEDIUM | map::MARKER_LARGE => {
+                Ok(Value::Map(Map::try_from(input_bytes)?))
+            }
+            // Tiny structure
+            marker if (STRUCT_MARKER_TINY..=(STRUCT_MARKER_TINY | 0x0F)).contains(&marker) => {
+                deserialize_structure(input_bytes)
+            }
+            STRUCT_MARKER_SMALL | STRUCT_MARKER_MEDIUM => deserialize_structure(input_bytes),
+            _ => Err(DeserializationError::InvalidMarkerByte(marker).into()),
+        }
+    }
+}
+
+fn deserialize_structure(input_bytes: &mut Bytes) -> Result<Value> {
+    let (_marker, signature) = get_info_from_bytes(input_bytes)?;
     match signature {
-        node::SIGNATURE => Ok(Value::Node(Node::try_from(input_arc)?)),
-        relationship::SIGNATURE => Ok(Value::Relationship(Relationship::try_from(input_arc)?)),
-        path::SIGNATURE => Ok(Value::Path(Path::try_from(input_arc)?)),
+        node::SIGNATURE => Ok(Value::Node(Node::try_from(input_bytes)?)),
+        relationship::SIGNATURE => Ok(Value::Relationship(Relationship::try_from(input_bytes)?)),
+        path::SIGNATURE => Ok(Value::Path(Path::try_from(input_bytes)?)),
         unbound_relationship::SIGNATURE => Ok(Value::UnboundRelationship(
-            UnboundRelationship::try_from(input_arc)?,
+            UnboundRelationship::try_from(input_bytes)?,
         )),
-        date::SIGNATURE => Ok(Value::Date(Date::try_from(input_arc)?)),
-        time::SIGNATURE => Ok(Value::Time(Time::try_from(input_arc)?)),
-        date_time_offset::SIGNATURE => {
-            Ok(Value::DateTimeOffset(DateTimeOffset::try_from(input_arc)?))
+        date::SIGNATURE => Ok(Value::Date(Date::try_from(input_bytes)?)),
+        time::SIGNATURE => Ok(Value::Time(Time::try_from(input_bytes)?)),
+        date_time_offset::SIGNATURE => Ok(Value::DateTimeOffset(DateTimeOffset::try_from(
+            input_bytes,
+        )?)),
+        date_time_zoned::SIGNATURE => {
+            Ok(Value::DateTimeZoned(DateTimeZoned::try_from(input_bytes)?))
+        }
+        local_time::SIGNATURE => Ok(Value::LocalTime(LocalTime::try_from(input_bytes)?)),
+        local_date_time::SIGNATURE => {
+            Ok(Value::LocalDateTime(LocalDateTime::try_from(input_bytes)?))
         }
-        date_time_zoned::SIGNATURE => Ok(Value::DateTimeZoned(DateTimeZoned::try_from(input_arc)?)),
-        local_time::SIGNATURE => Ok(Value::LocalTime(LocalTime::try_from(input_arc)?)),
-        local_date_time::SIGNATURE => Ok(Value::LocalDateTime(LocalDateTime::try_from(input_arc)?)),
-        duration::SIGNATURE => Ok(Value::Duration(Duration::try_from(input_arc)?)),
-        point_2d::SIGNATURE => Ok(Value::Point2D(Point2D::try_from(input_arc)?)),
-        point_3d::SIGNATURE => Ok(Value::Point3D(Point3D::try_from(input_arc)?)),
+        duration::SIGNATURE => Ok(Value::Duration(Duration::try_from(input_bytes)?)),
+        point_2d::SIGNATURE => Ok(Value::Point2D(Point2D::try_from(input_bytes)?)),
+        point_3d::SIGNATURE => Ok(Value::Point3D(Point3D::try_from(input_bytes)?)),
         _ => Err(DeserializationError::InvalidSignatureByte(signature).into()),
     }
 }
@@ -285,23 +315,20 @@ mod tests {
 
     #[test]
     fn null_from_bytes() {
-        let null_bytes = Null.try_into_bytes().unwrap();
-        assert_eq!(
-            Value::try_from(Arc::new(Mutex::new(null_bytes))).unwrap(),
-            Value::Null
-        );
+        let mut null_bytes = Null.try_into_bytes().unwrap();
+        assert_eq!(Value::try_from(&mut null_bytes).unwrap(), Value::Null);
     }
 
     #[test]
     fn boolean_from_bytes() {
-        let true_bytes = Boolean::from(true).try_into_bytes().unwrap();
-        let false_bytes = Boolean::from(false).try_into_bytes().unwrap();
+        let mut true_bytes = Boolean::from(true).try_into_bytes().unwrap();
+        let mut false_bytes = Boolean::from(false).try_into_bytes().unwrap();
         assert_eq!(
-            Value::try_from(Arc::new(Mutex::new(true_bytes))).unwrap(),
+            Value::try_from(&mut true_bytes).unwrap(),
             Value::Boolean(true)
         );
         assert_eq!(
-            Value::try_from(Arc::new(Mutex::new(false_bytes))).unwrap(),
+            Value::try_from(&mut false_bytes).unwrap(),
             Value::Boolean(false)
         );
     }
@@ -309,39 +336,39 @@ mod tests {
     #[test]
     fn integer_from_bytes() {
         let tiny = Integer::from(110_i8);
-        let tiny_bytes = tiny.clone().try_into_bytes().unwrap();
+        let mut tiny_bytes = tiny.clone().try_into_bytes().unwrap();
         let small = Integer::from(-50_i8);
-        let small_bytes = small.clone().try_into_bytes().unwrap();
+        let mut small_bytes = small.clone().try_into_bytes().unwrap();
         let medium = Integer::from(8000_i16);
-        let medium_bytes = medium.clone().try_into_bytes().unwrap();
+        let mut medium_bytes = medium.clone().try_into_bytes().unwrap();
         let medium_negative = Integer::from(-18621_i16);
-        let medium_negative_bytes = medium_negative.clone().try_into_bytes().unwrap();
+        let mut medium_negative_bytes = medium_negative.clone().try_into_bytes().unwrap();
         let large = Integer::from(-1_000_000_000_i32);
-        let large_bytes = large.clone().try_into_bytes().unwrap();
+        let mut large_bytes = large.clone().try_into_bytes().unwrap();
         let very_large = Integer::from(9_000_000_000_000_000_000_i64);
-        let very_large_bytes = very_large.clone().try_into_bytes().unwrap();
+        let mut very_large_bytes = very_large.clone().try_into_bytes().unwrap();
         assert_eq!(
-            Value::try_from(Arc::new(Mutex::new(tiny_bytes))).unwrap(),
+            Value::try_from(&mut tiny_bytes).unwrap(),
             Value::Integer(tiny)
         );
         assert_eq!(
-            Value::try_from(Arc::new(Mutex::new(small_bytes))).unwrap(),
+            Value::try_from(&mut small_bytes).unwrap(),
             Value::Integer(small)
         );
         assert_eq!(
-            Value::try_from(Arc::new(Mutex::new(medium_bytes))).unwrap(),
+            Value::try_from(&mut medium_bytes).unwrap(),
             Value::Integer(medium)
         );
         assert_eq!(
-            Value::try_from(Arc::new(Mutex::new(medium_negative_bytes))).unwrap(),
+            Value::try_from(&mut medium_negative_bytes).unwrap(),
             Value::Integer(medium_negative)
         );
         assert_eq!(
-            Value::try_from(Arc::new(Mutex::new(large_bytes))).unwrap(),
+            Value::try_from(&mut large_bytes).unwrap(),
             Value::Integer(large)
         );
         assert_eq!(
-            Value::try_from(Arc::new(Mutex::new(very_large_bytes))).unwrap(),
+            Value::try_from(&mut very_large_bytes).unwrap(),
             Value::Integer(very_large)
         );
     }
@@ -349,27 +376,27 @@ mod tests {
     #[test]
     fn float_from_bytes() {
         let min = Float::from(std::f64::MIN_POSITIVE);
-        let min_bytes = min.clone().try_into_bytes().unwrap();
+        let mut min_bytes = min.clone().try_into_bytes().unwrap();
         let max = Float::from(std::f64::MAX);
-        let max_bytes = max.clone().try_into_bytes().unwrap();
+        let mut max_bytes = max.clone().try_into_bytes().unwrap();
         let e = Float::from(std::f64::consts::E);
-        let e_bytes = e.clone().try_into_bytes().unwrap();
+        let mut e_bytes = e.clone().try_into_bytes().unwrap();
         let pi = Float::from(std::f64::consts::PI);
-        let pi_bytes = pi.clone().try_into_bytes().unwrap();
+        let mut pi_bytes = pi.clone().try_into_bytes().unwrap();
         assert_eq!(
-            Value::try_from(Arc::new(Mutex::new(min_bytes))).unwrap(),
+            Value::try_from(&mut min_bytes).unwrap(),
             Value::Float(min.value)
         );
         assert_eq!(
-            Value::try_from(Arc::new(Mutex::new(max_bytes))).unwrap(),
+            Value::try_from(&mut max_bytes).unwrap(),
             Value::Float(max.value)
         );
         assert_eq!(
-            Value::try_from(Arc::new(Mutex::new(e_bytes))).unwrap(),
+            Value::try_from(&mut e_bytes).unwrap(),
             Value::Float(e.value)
         );
         assert_eq!(
-            Value::try_from(Arc::new(Mutex::new(pi_bytes))).unwrap(),
+            Value::try_from(&mut pi_bytes).unwrap(),
             Value::Float(pi.value)
         );
     }
@@ -377,27 +404,27 @@ mod tests {
     #[test]
     fn byte_array_from_bytes() {
         let empty_arr: ByteArray = Vec::<u8>::new().into();
-        let empty_arr_bytes = empty_arr.clone().try_into_bytes().unwrap();
+        let mut empty_arr_bytes = empty_arr.clone().try_into_bytes().unwrap();
         let small_arr: ByteArray = vec![1_u8; 100].into();
-        let small_arr_bytes = small_arr.clone().try_into_bytes().unwrap();
+        let mut small_arr_bytes = small_arr.clone().try_into_bytes().unwrap();
         let medium_arr: ByteArray = vec![99_u8; 1000].into();
-        let medium_arr_bytes = medium_arr.clone().try_into_bytes().unwrap();
+        let mut medium_arr_bytes = medium_arr.clone().try_into_bytes().unwrap();
         let large_arr: ByteArray = vec![1_u8; 100_000].into();
-        let large_arr_bytes = large_arr.clone().try_into_bytes().unwrap();
+        let mut large_arr_bytes = large_arr.clone().try_into_bytes().unwrap();
         assert_eq!(
-            Value::try_from(Arc::new(Mutex::new(empty_arr_bytes))).unwrap(),
+            Value::try_from(&mut empty_arr_bytes).unwrap(),
             Value::Bytes(empty_arr)
         );
         assert_eq!(
-            Value::try_from(Arc::new(Mutex::new(small_arr_bytes))).unwrap(),
+            Value::try_from(&mut small_arr_bytes).unwrap(),
             Value::Bytes(small_arr)
         );
         assert_eq!(
-            Value::try_from(Arc::new(Mutex::new(medium_arr_bytes))).unwrap(),
+            Value::try_from(&mut medium_arr_bytes).unwrap(),
             Value::Bytes(medium_arr)
         );
         assert_eq!(
-            Value::try_from(Arc::new(Mutex::new(large_arr_bytes))).unwrap(),
+            Value::try_from(&mut large_arr_bytes).unwrap(),
             Value::Bytes(large_arr)
         );
     }
@@ -405,27 +432,27 @@ mod tests {
     #[test]
     fn string_from_bytes() {
         let tiny = String::from("string".repeat(1));
-        let tiny_bytes = tiny.clone().try_into_bytes().unwrap();
+        let mut tiny_bytes = tiny.clone().try_into_bytes().unwrap();
         let small = String::from("string".repeat(10));
-        let small_bytes = small.clone().try_into_bytes().unwrap();
+        let mut small_bytes = small.clone().try_into_bytes().unwrap();
         let medium = String::from("string".repeat(1000));
-        let medium_bytes = medium.clone().try_into_bytes().unwrap();
+        let mut medium_bytes = medium.clone().try_into_bytes().unwrap();
         let large = String::from("string".repeat(100_000));
-        let large_bytes = large.clone().try_into_bytes().unwrap();
+        let mut large_bytes = large.clone().try_into_bytes().unwrap();
         assert_eq!(
-            Value::try_from(Arc::new(Mutex::new(tiny_bytes))).unwrap(),
+            Value::try_from(&mut tiny_bytes).unwrap(),
             Value::String(tiny.value)
         );
         assert_eq!(
-            Value::try_from(Arc::new(Mutex::new(small_bytes))).unwrap(),
+            Value::try_from(&mut small_bytes).unwrap(),
             Value::String(small.value)
         );
         assert_eq!(
-            Value::try_from(Arc::new(Mutex::new(medium_bytes))).unwrap(),
+            Value::try_from(&mut medium_bytes).unwrap(),
             Value::String(medium.value)
         );
         assert_eq!(
-            Value::try_from(Arc::new(Mutex::new(large_bytes))).unwrap(),
+            Value::try_from(&mut large_bytes).unwrap(),
             Value::String(large.value)
         );
     }
@@ -433,27 +460,27 @@ mod tests {
     #[test]
     fn list_from_bytes() {
         let empty_list: List = Vec::<i32>::new().into();
-        let empty_list_bytes = empty_list.clone().try_into_bytes().unwrap();
+        let mut empty_list_bytes = empty_list.clone().try_into_bytes().unwrap();
         let tiny_list: List = vec![100_000_000_000_i64; 10].into();
-        let tiny_list_bytes = tiny_list.clone().try_into_bytes().unwrap();
+        let mut tiny_list_bytes = tiny_list.clone().try_into_bytes().unwrap();
         let small_list: List = vec!["item"; 100].into();
-        let small_list_bytes = small_list.clone().try_into_bytes().unwrap();
+        let mut small_list_bytes = small_list.clone().try_into_bytes().unwrap();
         let medium_list: List = vec![false; 1000].into();
-        let medium_list_bytes = medium_list.clone().try_into_bytes().unwrap();
+        let mut medium_list_bytes = medium_list.clone().try_into_bytes().unwrap();
         assert_eq!(
-            Value::try_from(Arc::new(Mutex::new(empty_list_bytes))).unwrap(),
+            Value::try_from(&mut empty_list_bytes).unwrap(),
             Value::List(empty_list)
         );
         assert_eq!(
-            Value::try_from(Arc::new(Mutex::new(tiny_list_bytes))).unwrap(),
+            Value::try_from(&mut tiny_list_bytes).unwrap(),
             Value::List(tiny_list)
         );
         assert_eq!(
-            Value::try_from(Arc::new(Mutex::new(small_list_bytes))).unwrap(),
+            Value::try_from(&mut small_list_bytes).unwrap(),
             Value::List(small_list)
         );
         assert_eq!(
-            Value::try_from(Arc::new(Mutex::new(medium_list_bytes))).unwrap(),
+            Value::try_from(&mut medium_list_bytes).unwrap(),
             Value::List(medium_list)
         );
     }
@@ -462,9 +489,9 @@ mod tests {
     #[ignore]
     fn large_list_from_bytes() {
         let large_list: List = vec![1_i8; 70_000].into();
-        let large_list_bytes = large_list.clone().try_into_bytes().unwrap();
+        let mut large_list_bytes = large_list.clone().try_into_bytes().unwrap();
         assert_eq!(
-            Value::try_from(Arc::new(Mutex::new(large_list_bytes))).unwrap(),
+            Value::try_from(&mut large_list_bytes).unwrap(),
             Value::List(large_list)
         );
     }
@@ -472,9 +499,9 @@ mod tests {
     #[test]
     fn map_from_bytes() {
         let empty_map: Map = HashMap::<&str, i8>::new().into();
-        let empty_map_bytes = empty_map.clone().try_into_bytes().unwrap();
+        let mut empty_map_bytes = empty_map.clone().try_into_bytes().unwrap();
         let tiny_map: Map = HashMap::<&str, i8>::from_iter(vec![("a", 1_i8)]).into();
-        let tiny_map_bytes = tiny_map.clone().try_into_bytes().unwrap();
+        let mut tiny_map_bytes = tiny_map.clone().try_into_bytes().unwrap();
         let small_map: Map = HashMap::<&str, i8>::from_iter(vec![
             ("a", 1_i8),
             ("b", 1_i8),
@@ -494,17 +521,17 @@ mod tests {
             ("p", 6_i8),
         ])
         .into();
-        let small_map_bytes = small_map.clone().try_into_bytes().unwrap();
+        let mut small_map_bytes = small_map.clone().try_into_bytes().unwrap();
         assert_eq!(
-            Value::try_from(Arc::new(Mutex::new(empty_map_bytes))).unwrap(),
+            Value::try_from(&mut empty_map_bytes).unwrap(),
             Value::Map(empty_map)
         );
         assert_eq!(
-            Value::try_from(Arc::new(Mutex::new(tiny_map_bytes))).unwrap(),
+            Value::try_from(&mut tiny_map_bytes).unwrap(),
             Value::Map(tiny_map)
         );
         assert_eq!(
-            Value::try_from(Arc::new(Mutex::new(small_map_bytes))).unwrap(),
+            Value::try_from(&mut small_map_bytes).unwrap(),
             Value::Map(small_map)
         );
     }
@@ -546,20 +573,20 @@ mod tests {
 
     #[test]
     fn node_from_bytes() {
-        let node_bytes: Bytes = get_node().try_into_bytes().unwrap();
+        let mut node_bytes: Bytes = get_node().try_into_bytes().unwrap();
 
         assert_eq!(
-            Value::try_from(Arc::new(Mutex::new(node_bytes))).unwrap(),
+            Value::try_from(&mut node_bytes).unwrap(),
             Value::Node(get_node())
         );
     }
 
     #[test]
     fn relationship_from_bytes() {
-        let rel_bytes: Bytes = get_rel().try_into_bytes().unwrap();
+        let mut rel_bytes: Bytes = get_rel().try_into_bytes().unwrap();
 
         assert_eq!(
-            Value::try_from(Arc::new(Mutex::new(rel_bytes))).unwrap(),
+            Value::try_from(&mut rel_bytes).unwrap(),
             Value::Relationship(get_rel())
         );
     }
@@ -567,20 +594,17 @@ mod tests {
     #[test]
     fn path_from_bytes() {
         let path = Path::new(vec![get_node()], vec![get_unbound_rel()], 100_i64);
-        let path_bytes: Bytes = path.clone().try_into_bytes().unwrap();
+        let mut path_bytes: Bytes = path.clone().try_into_bytes().unwrap();
 
-        assert_eq!(
-            Value::try_from(Arc::new(Mutex::new(path_bytes))).unwrap(),
-            Value::Path(path)
-        );
+        assert_eq!(Value::try_from(&mut path_bytes).unwrap(), Value::Path(path));
     }
 
     #[test]
     fn unbound_relationship_from_bytes() {
-        let unbound_rel_bytes: Bytes = get_unbound_rel().try_into_bytes().unwrap();
+        let mut unbound_rel_bytes: Bytes = get_unbound_rel().try_into_bytes().unwrap();
 
         assert_eq!(
-            Value::try_from(Arc::new(Mutex::new(unbound_rel_bytes))).unwrap(),
+            Value::try_from(&mut unbound_rel_bytes).unwrap(),
             Value::UnboundRelationship(get_unbound_rel())
         );
     }
@@ -588,9 +612,9 @@ mod tests {
     #[test]
     fn date_from_bytes() {
         let christmas = Date::new(2020, 12, 25).unwrap();
-        let christmas_bytes: Bytes = christmas.clone().try_into_bytes().unwrap();
+        let mut christmas_bytes: Bytes = christmas.clone().try_into_bytes().unwrap();
         assert_eq!(
-            Value::try_from(Arc::new(Mutex::new(christmas_bytes))).unwrap(),
+            Value::try_from(&mut christmas_bytes).unwrap(),
             Value::Date(christmas)
         );
     }
@@ -598,15 +622,16 @@ mod tests {
     #[test]
     fn time_from_bytes() {
         let midnight_utc = Time::new(0, 0, 0, 0, (0, 0)).unwrap();
-        let midnight_utc_bytes = midnight_utc.clone().try_into_bytes().unwrap();
+        let mut midnight_utc_bytes = midnight_utc.clone().try_into_bytes().unwrap();
         let about_four_pm_pacific = Time::new(16, 4, 35, 235, (-8, 0)).unwrap();
-        let about_four_pm_pacific_bytes = about_four_pm_pacific.clone().try_into_bytes().unwrap();
+        let mut about_four_pm_pacific_bytes =
+            about_four_pm_pacific.clone().try_into_bytes().unwrap();
         assert_eq!(
-            Value::try_from(Arc::new(Mutex::new(midnight_utc_bytes))).unwrap(),
+            Value::try_from(&mut midnight_utc_bytes).unwrap(),
             Value::Time(midnight_utc)
         );
         assert_eq!(
-            Value::try_from(Arc::new(Mutex::new(about_four_pm_pacific_bytes))).unwrap(),
+            Value::try_from(&mut about_four_pm_pacific_bytes).unwrap(),
             Value::Time(about_four_pm_pacific)
         );
     }
@@ -614,9 +639,9 @@ mod tests {
     #[test]
     fn date_time_offset_from_bytes() {
         let date_time = DateTimeOffset::new(2050, 12, 31, 23, 59, 59, 10, (-5, 0)).unwrap();
-        let date_time_bytes = date_time.clone().try_into_bytes().unwrap();
+        let mut date_time_bytes = date_time.clone().try_into_bytes().unwrap();
         assert_eq!(
-            Value::try_from(Arc::new(Mutex::new(date_time_bytes))).unwrap(),
+            Value::try_from(&mut date_time_bytes).unwrap(),
             Value::DateTimeOffset(date_time)
         );
     }
@@ -625,9 +650,9 @@ mod tests {
     fn date_time_zoned_from_bytes() {
         let date_time =
             DateTimeZoned::new(2030, 8, 3, 14, 30, 0, 0, "Asia/Ulaanbaatar".to_string()).unwrap();
-        let date_time_bytes = date_time.clone().try_into_bytes().unwrap();
+        let mut date_time_bytes = date_time.clone().try_into_bytes().unwrap();
         assert_eq!(
-            Value::try_from(Arc::new(Mutex::new(date_time_bytes))).unwrap(),
+            Value::try_from(&mut date_time_bytes).unwrap(),
             Value::DateTimeZoned(date_time)
         );
     }
@@ -635,9 +660,9 @@ mod tests {
     #[test]
     fn local_time_from_bytes() {
         let local_time = LocalTime::new(23, 59, 59, 999).unwrap();
-        let local_time_bytes = local_time.clone().try_into_bytes().unwrap();
+        let mut local_time_bytes = local_time.clone().try_into_bytes().unwrap();
         assert_eq!(
-            Value::try_from(Arc::new(Mutex::new(local_time_bytes))).unwrap(),
+            Value::try_from(&mut local_time_bytes).unwrap(),
             Value::LocalTime(local_time)
         );
     }
@@ -645,9 +670,9 @@ mod tests {
     #[test]
     fn local_date_time_from_bytes() {
         let local_date_time = LocalDateTime::new(1999, 2, 27, 1, 0, 0, 9999).unwrap();
-        let local_date_time_bytes = local_date_time.clone().try_into_bytes().unwrap();
+        let mut local_date_time_bytes = local_date_time.clone().try_into_bytes().unwrap();
         assert_eq!(
-            Value::try_from(Arc::new(Mutex::new(local_date_time_bytes))).unwrap(),
+            Value::try_from(&mut local_date_time_bytes).unwrap(),
             Value::LocalDateTime(local_date_time)
         );
     }
@@ -655,9 +680,9 @@ mod tests {
     #[test]
     fn duration_from_bytes() {
         let duration = Duration::new(9876, 12345, 65332, 23435);
-        let duration_bytes = duration.clone().try_into_bytes().unwrap();
+        let mut duration_bytes = duration.clone().try_into_bytes().unwrap();
         assert_eq!(
-            Value::try_from(Arc::new(Mutex::new(duration_bytes))).unwrap(),
+            Value::try_from(&mut duration_bytes).unwrap(),
             Value::Duration(duration)
         );
     }
@@ -665,17 +690,91 @@ mod tests {
     #[test]
     fn point_from_bytes() {
         let point2d = Point2D::new(9876, 12.312_345, 134_564.123_567_543);
-        let point2d_bytes = point2d.clone().try_into_bytes().unwrap();
+        let mut point2d_bytes = point2d.clone().try_into_bytes().unwrap();
         assert_eq!(
-            Value::try_from(Arc::new(Mutex::new(point2d_bytes))).unwrap(),
+            Value::try_from(&mut point2d_bytes).unwrap(),
             Value::Point2D(point2d)
         );
 
         let point3d = Point3D::new(249, 543.598_387, 2_945_732_849.293_85, 45_438.874_385);
-        let point3d_bytes = point3d.clone().try_into_bytes().unwrap();
+        let mut point3d_bytes = point3d.clone().try_into_bytes().unwrap();
         assert_eq!(
-            Value::try_from(Arc::new(Mutex::new(point3d_bytes))).unwrap(),
+            Value::try_from(&mut point3d_bytes).unwrap(),
             Value::Point3D(point3d)
         );
     }
+
+    fn assert_unexpected_eof(result: Result<Value>) {
+        assert!(matches!(
+            result,
+            Err(Error::DeserializationError(
+                DeserializationError::UnexpectedEof
+            ))
+        ));
+    }
+
+    #[test]
+    fn truncated_input() {
+        let values = vec![
+            Value::from(-1_000_000_000_i32),
+            Value::from(std::f64::consts::PI),
+            Value::Bytes(ByteArray::from(vec![1_u8, 2, 3])),
+            Value::from("string".repeat(10)),
+            Value::from(vec![Value::from(1200_i16), Value::from("hi there")]),
+            Value::from(HashMap::from_iter(vec![("key", Value::from(true))])),
+            Value::Node(get_node()),
+            Value::Date(Date::new(2020, 12, 31).unwrap()),
+        ];
+        for value in values {
+            let bytes = value.try_into_bytes().unwrap();
+            // Every proper prefix of a value's bytes is incomplete
+            for len in 0..bytes.len() {
+                assert_unexpected_eof(Value::try_from(&mut bytes.slice(..len)));
+            }
+        }
+    }
+
+    #[test]
+    fn oversized_collection() {
+        // Sizes larger than the remaining input must not be trusted
+        let list_bytes = &[list::MARKER_LARGE, 0xFF, 0xFF, 0xFF, 0xFF, 0x01];
+        assert_unexpected_eof(Value::try_from(&mut Bytes::from_static(list_bytes)));
+        let map_bytes = &[map::MARKER_LARGE, 0xFF, 0xFF, 0xFF, 0xFF, 0x81, b'a'];
+        assert_unexpected_eof(Value::try_from(&mut Bytes::from_static(map_bytes)));
+        let string_bytes = &[string::MARKER_LARGE, 0xFF, 0xFF, 0xFF, 0xFF, b'a'];
+        assert_unexpected_eof(Value::try_from(&mut Bytes::from_static(string_bytes)));
+    }
+
+    #[test]
+    fn non_string_map_key() {
+        // Only strings can be map keys, other values might not be hashable
+        let mut map_bytes = vec![map::MARKER_TINY | 1, float::MARKER];
+        map_bytes.extend_from_slice(&1.5_f64.to_be_bytes());
+        map_bytes.push(null::MARKER);
+        assert!(matches!(
+            Value::try_from(&mut Bytes::from(map_bytes)),
+            Err(Error::DeserializationError(
+                DeserializationError::InvalidMapKey(Value::Float(_))
+            ))
+        ));
+    }
+
+    fn nested_lists(depth: usize) -> Bytes {
+        let mut bytes = vec![list::MARKER_TINY | 1; depth];
+        bytes.push(null::MARKER);
+        Bytes::from(bytes)
+    }
+
+    #[test]
+    fn nesting_depth() {
+        assert!(Value::try_from(&mut nested_lists(MAX_NESTING_DEPTH - 1)).is_ok());
+        assert!(matches!(
+            Value::try_from(&mut nested_lists(200_000)),
+            Err(Error::DeserializationError(
+                DeserializationError::NestingTooDeep
+            ))
+        ));
+        // The depth is reset after a failure
+        assert!(Value::try_from(&mut nested_lists(MAX_NESTING_DEPTH - 1)).is_ok());
+    }
 }
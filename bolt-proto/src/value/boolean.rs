@@ -1,8 +1,6 @@
 use std::convert::{TryFrom, TryInto};
-use std::panic::catch_unwind;
-use std::sync::{Arc, Mutex};
 
-use bytes::{Buf, Bytes};
+use bytes::Bytes;
 
 use crate::error::*;
 use crate::serialization::*;
@@ -39,28 +37,22 @@ impl TryInto<Bytes> for Boolean {
 
 impl Deserialize for Boolean {}
 
-impl TryFrom<Arc<Mutex<Bytes>>> for Boolean {
+impl TryFrom<&mut Bytes> for Boolean {
     type Error = Error;
 
-    fn try_from(input_arc: Arc<Mutex<Bytes>>) -> Result<Self> {
-        catch_unwind(move || {
-            let mut input_bytes = input_arc.lock().unwrap();
-            let marker = input_bytes.get_u8();
-            debug_assert!(!input_bytes.has_remaining());
-            match marker {
-                MARKER_TRUE => Ok(Boolean::from(true)),
-                MARKER_FALSE => Ok(Boolean::from(false)),
-                _ => Err(DeserializationError::InvalidMarkerByte(marker).into()),
-            }
-        })
-        .map_err(|_| DeserializationError::Panicked)?
+    fn try_from(input_bytes: &mut Bytes) -> Result<Self> {
+        let marker = input_bytes.try_get_u8()?;
+        match marker {
+            MARKER_TRUE => Ok(Boolean::from(true)),
+            MARKER_FALSE => Ok(Boolean::from(false)),
+            _ => Err(DeserializationError::InvalidMarkerByte(marker).into()),
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use std::convert::TryFrom;
-    use std::sync::{Arc, Mutex};
 
     use bytes::Bytes;
 
@@ -92,14 +84,14 @@ mod tests {
     fn try_from_bytes() {
         let f = Boolean::from(false);
         assert_eq!(
-            Boolean::try_from(Arc::new(Mutex::new(f.clone().try_into_bytes().unwrap()))).unwrap(),
+            Boolean::try_from(&mut f.clone().try_into_bytes().unwrap()).unwrap(),
             f
         );
         let t = Boolean::from(true);
         assert_eq!(
-            Boolean::try_from(Arc::new(Mutex::new(t.clone().try_into_bytes().unwrap()))).unwrap(),
+            Boolean::try_from(&mut t.clone().try_into_bytes().unwrap()).unwrap(),
             t
         );
-        assert!(Boolean::try_from(Arc::new(Mutex::new(Bytes::from_static(&[0x01])))).is_err());
+        assert!(Boolean::try_from(&mut Bytes::from_static(&[0x01])).is_err());
     }
 }
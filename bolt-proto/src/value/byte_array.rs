@@ -1,9 +1,7 @@
 use std::convert::{TryFrom, TryInto};
 use std::mem;
-use std::panic::catch_unwind;
-use std::sync::{Arc, Mutex};
 
-use bytes::{Buf, BufMut, Bytes, BytesMut};
+use bytes::{BufMut, Bytes, BytesMut};
 
 use crate::error::*;
 use crate::serialization::*;
@@ -54,28 +52,20 @@ impl TryInto<Bytes> for ByteArray {
 
 impl Deserialize for ByteArray {}
 
-impl TryFrom<Arc<Mutex<Bytes>>> for ByteArray {
+impl TryFrom<&mut Bytes> for ByteArray {
     type Error = Error;
 
-    fn try_from(input_arc: Arc<Mutex<Bytes>>) -> Result<Self> {
-        catch_unwind(move || {
-            let mut input_bytes = input_arc.lock().unwrap();
-            let marker = input_bytes.get_u8();
-            let size = match marker {
-                MARKER_SMALL => input_bytes.get_u8() as usize,
-                MARKER_MEDIUM => input_bytes.get_u16() as usize,
-                MARKER_LARGE => input_bytes.get_u32() as usize,
-                _ => {
-                    return Err(DeserializationError::InvalidMarkerByte(marker).into());
-                }
-            };
-            let mut byte_arr = BytesMut::with_capacity(size);
-            // We resize here so that the length of byte_arr is nonzero, which allows us to use copy_to_slice
-            byte_arr.resize(size, 0);
-            input_bytes.copy_to_slice(&mut byte_arr);
-            Ok(ByteArray::from(byte_arr.as_ref()))
-        })
-        .map_err(|_| DeserializationError::Panicked)?
+    fn try_from(input_bytes: &mut Bytes) -> Result<Self> {
+        let marker = input_bytes.try_get_u8()?;
+        let size = match marker {
+            MARKER_SMALL => input_bytes.try_get_u8()? as usize,
+            MARKER_MEDIUM => input_bytes.try_get_u16()? as usize,
+            MARKER_LARGE => input_bytes.try_get_u32()? as usize,
+            _ => {
+                return Err(DeserializationError::InvalidMarkerByte(marker).into());
+            }
+        };
+        Ok(ByteArray::from(try_split_to(input_bytes, size)?.as_ref()))
     }
 }
 
@@ -146,27 +136,24 @@ mod tests {
     #[test]
     fn try_from_bytes() {
         let empty_arr: ByteArray = Vec::<u8>::new().into();
-        let empty_arr_bytes = empty_arr.clone().try_into_bytes().unwrap();
+        let mut empty_arr_bytes = empty_arr.clone().try_into_bytes().unwrap();
         let tiny_arr: ByteArray = vec![25_u8; 10].into();
-        let tiny_arr_bytes = tiny_arr.clone().try_into_bytes().unwrap();
+        let mut tiny_arr_bytes = tiny_arr.clone().try_into_bytes().unwrap();
         let small_arr: ByteArray = vec![1_u8; 100].into();
-        let small_arr_bytes = small_arr.clone().try_into_bytes().unwrap();
+        let mut small_arr_bytes = small_arr.clone().try_into_bytes().unwrap();
         let medium_arr: ByteArray = vec![99_u8; 1000].into();
-        let medium_arr_bytes = medium_arr.clone().try_into_bytes().unwrap();
+        let mut medium_arr_bytes = medium_arr.clone().try_into_bytes().unwrap();
         assert_eq!(
-            ByteArray::try_from(Arc::new(Mutex::new(empty_arr_bytes))).unwrap(),
+            ByteArray::try_from(&mut empty_arr_bytes).unwrap(),
             empty_arr
         );
+        assert_eq!(ByteArray::try_from(&mut tiny_arr_bytes).unwrap(), tiny_arr);
         assert_eq!(
-            ByteArray::try_from(Arc::new(Mutex::new(tiny_arr_bytes))).unwrap(),
-            tiny_arr
-        );
-        assert_eq!(
-            ByteArray::try_from(Arc::new(Mutex::new(small_arr_bytes))).unwrap(),
+            ByteArray::try_from(&mut small_arr_bytes).unwrap(),
             small_arr
         );
         assert_eq!(
-            ByteArray::try_from(Arc::new(Mutex::new(medium_arr_bytes))).unwrap(),
+            ByteArray::try_from(&mut medium_arr_bytes).unwrap(),
             medium_arr
         );
     }
@@ -175,9 +162,9 @@ mod tests {
     #[ignore]
     fn try_from_large_bytes() {
         let large_arr: ByteArray = vec![1_u8; 100_000].into();
-        let large_arr_bytes = large_arr.clone().try_into_bytes().unwrap();
+        let mut large_arr_bytes = large_arr.clone().try_into_bytes().unwrap();
         assert_eq!(
-            ByteArray::try_from(Arc::new(Mutex::new(large_arr_bytes))).unwrap(),
+            ByteArray::try_from(&mut large_arr_bytes).unwrap(),
             large_arr
         );
     }
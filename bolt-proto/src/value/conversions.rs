@@ -179,8 +179,34 @@ impl From<Point3D> for Value {
     }
 }
 
+impl<T> From<Option<T>> for Value
+where
+    T: Into<Value>,
+{
+    fn from(value: Option<T>) -> Self {
+        match value {
+            Some(value) => value.into(),
+            None => Value::Null,
+        }
+    }
+}
+
 // ----------------------- INTO -----------------------
 
+impl<T> TryInto<Option<T>> for Value
+where
+    T: TryFrom<Value, Error = Error>,
+{
+    type Error = Error;
+
+    fn try_into(self) -> Result<Option<T>> {
+        match self {
+            Value::Null => Ok(None),
+            _ => Ok(Some(T::try_from(self)?)),
+        }
+    }
+}
+
 impl<T> TryInto<Vec<T>> for Value
 where
     T: TryFrom<Value, Error = Error>,
@@ -26,7 +26,6 @@ impl Date {
 #[cfg(test)]
 mod tests {
     use std::convert::TryFrom;
-    use std::sync::{Arc, Mutex};
 
     use bytes::Bytes;
 
@@ -57,11 +56,11 @@ mod tests {
         let future_date = Date::new(3000, 5, 23).unwrap();
         let future_bytes = &[MARKER_INT_32, 0x00, 0x05, 0xBE, 0x16];
         assert_eq!(
-            Date::try_from(Arc::new(Mutex::new(Bytes::from_static(past_bytes)))).unwrap(),
+            Date::try_from(&mut Bytes::from_static(past_bytes)).unwrap(),
             past_date
         );
         assert_eq!(
-            Date::try_from(Arc::new(Mutex::new(Bytes::from_static(future_bytes)))).unwrap(),
+            Date::try_from(&mut Bytes::from_static(future_bytes)).unwrap(),
             future_date
         );
     }
@@ -47,7 +47,6 @@ impl DateTimeOffset {
 #[cfg(test)]
 mod tests {
     use std::convert::TryFrom;
-    use std::sync::{Arc, Mutex};
 
     use bytes::Bytes;
     use chrono::{DateTime, FixedOffset, NaiveDateTime};
@@ -106,8 +105,7 @@ mod tests {
             0x50,
         ];
         assert_eq!(
-            DateTimeOffset::try_from(Arc::new(Mutex::new(Bytes::from_static(date_time_bytes))))
-                .unwrap(),
+            DateTimeOffset::try_from(&mut Bytes::from_static(date_time_bytes)).unwrap(),
             date_time_offset
         );
     }
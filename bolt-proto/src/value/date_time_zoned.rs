@@ -49,7 +49,6 @@ impl DateTimeZoned {
 #[cfg(test)]
 mod tests {
     use std::convert::TryFrom;
-    use std::sync::{Arc, Mutex};
 
     use bytes::Bytes;
 
@@ -156,8 +155,7 @@ mod tests {
             b'a',
         ];
         assert_eq!(
-            DateTimeZoned::try_from(Arc::new(Mutex::new(Bytes::from_static(date_time_bytes))))
-                .unwrap(),
+            DateTimeZoned::try_from(&mut Bytes::from_static(date_time_bytes)).unwrap(),
             date_time_offset
         );
     }
@@ -0,0 +1,655 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt::Display;
+use std::iter::FromIterator;
+
+use chrono::{FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, Offset, TimeZone};
+use chrono_tz::Tz;
+use serde::de::value::{MapAccessDeserializer, MapDeserializer, SeqDeserializer};
+use serde::de::{self, DeserializeOwned, IntoDeserializer, Visitor};
+use serde::forward_to_deserialize_any;
+
+use crate::error::*;
+use crate::message::Record;
+use crate::value::*;
+
+/// Deserialize an instance of type `T` from a [`Value`](crate::Value).
+///
+/// Maps, as well as the properties of nodes and relationships, can be deserialized into structs. Temporal values are
+/// deserialized from the string representations used by [chrono](https://docs.rs/chrono)'s `serde` support, so a
+/// `Date` can be deserialized into a `NaiveDate`, a `DateTimeOffset` or `DateTimeZoned` into a
+/// `DateTime<FixedOffset>`, and so on. Durations and points are deserialized from maps of their components, e.g.
+/// `{srid, x, y}` for a `Point2D`.
+pub fn from_value<T: DeserializeOwned>(value: Value) -> Result<T> {
+    T::deserialize(value)
+}
+
+/// Deserialize an instance of type `T` from a [`Record`](crate::message::Record), given the names of its fields (as
+/// returned in the `SUCCESS` message following a `RUN`).
+///
+/// The record can be deserialized into a struct or map keyed by field name, or into a tuple or sequence of its
+/// values.
+pub fn from_record<T: DeserializeOwned>(
+    record: Record,
+    fields: &[std::string::String],
+) -> Result<T> {
+    if record.fields.len() != fields.len() {
+        return Err(de::Error::invalid_length(
+            record.fields.len(),
+            &format!("{} fields", fields.len()).as_str(),
+        ));
+    }
+    T::deserialize(RecordDeserializer {
+        fields: fields.to_vec(),
+        values: record.fields,
+    })
+}
+
+impl de::Error for Error {
+    fn custom<T: Display>(msg: T) -> Self {
+        ConversionError::Serde(msg.to_string()).into()
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for Value {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        self
+    }
+}
+
+fn properties_map(properties: HashMap<std::string::String, Value>) -> Value {
+    Value::Map(Map::from(properties))
+}
+
+fn struct_map(entries: Vec<(&str, Value)>) -> Value {
+    Value::Map(Map {
+        value: HashMap::from_iter(entries.into_iter().map(|(k, v)| (Value::from(k), v))),
+    })
+}
+
+impl<'de> de::Deserializer<'de> for Value {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self {
+            Value::Boolean(boolean) => visitor.visit_bool(boolean),
+            Value::Integer(integer) => visitor.visit_i64(i64::from(integer)),
+            Value::Float(float) => visitor.visit_f64(float),
+            Value::Bytes(byte_array) => visitor.visit_byte_buf(byte_array.value),
+            Value::List(list) => {
+                let mut seq = SeqDeserializer::new(list.value.into_iter());
+                let value = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+                Ok(value)
+            }
+            Value::Map(map) => {
+                let mut map = MapDeserializer::new(map.value.into_iter());
+                let value = visitor.visit_map(&mut map)?;
+                map.end()?;
+                Ok(value)
+            }
+            Value::Null => visitor.visit_unit(),
+            Value::String(string) => visitor.visit_string(string),
+            // Graph entities are deserialized from their properties
+            Value::Node(node) => properties_map(node.properties).deserialize_any(visitor),
+            Value::Relationship(rel) => properties_map(rel.properties).deserialize_any(visitor),
+            Value::UnboundRelationship(rel) => {
+                properties_map(rel.properties).deserialize_any(visitor)
+            }
+            Value::Path(path) => struct_map(vec![
+                ("nodes", Value::from(path.nodes)),
+                ("relationships", Value::from(path.relationships)),
+                ("sequence", Value::from(path.sequence)),
+            ])
+            .deserialize_any(visitor),
+            // Temporal values use the same string formats as chrono's serde support
+            Value::Date(date) => match naive_date(date.days_since_epoch) {
+                Some(naive_date) => visitor.visit_string(naive_date.to_string()),
+                None => Err(ConversionError::FromValue(Value::Date(date)).into()),
+            },
+            Value::Time(time) => match (
+                naive_time(time.nanos_since_midnight),
+                FixedOffset::east_opt(time.zone_offset),
+            ) {
+                (Some(naive_time), Some(offset)) => {
+                    visitor.visit_string(format!("{}{}", naive_time, offset))
+                }
+                _ => Err(ConversionError::FromValue(Value::Time(time)).into()),
+            },
+            Value::DateTimeOffset(date_time_offset) => match (
+                naive_date_time(date_time_offset.epoch_seconds, date_time_offset.nanos),
+                FixedOffset::east_opt(date_time_offset.offset_seconds),
+            ) {
+                (Some(naive_date_time), Some(offset)) => {
+                    visitor.visit_string(offset.from_utc_datetime(&naive_date_time).to_rfc3339())
+                }
+                _ => {
+                    Err(ConversionError::FromValue(Value::DateTimeOffset(date_time_offset)).into())
+                }
+            },
+            Value::DateTimeZoned(date_time_zoned) => match (
+                naive_date_time(date_time_zoned.epoch_seconds, date_time_zoned.nanos),
+                date_time_zoned.zone_id.parse::<Tz>(),
+            ) {
+                (Some(naive_date_time), Ok(timezone)) => {
+                    let date_time = timezone.from_utc_datetime(&naive_date_time);
+                    let offset = date_time.offset().fix();
+                    visitor.visit_string(date_time.with_timezone(&offset).to_rfc3339())
+                }
+                _ => Err(ConversionError::FromValue(Value::DateTimeZoned(date_time_zoned)).into()),
+            },
+            Value::LocalTime(local_time) => match naive_time(local_time.nanos_since_midnight) {
+                Some(naive_time) => visitor.visit_string(naive_time.to_string()),
+                None => Err(ConversionError::FromValue(Value::LocalTime(local_time)).into()),
+            },
+            Value::LocalDateTime(local_date_time) => {
+                match naive_date_time(local_date_time.epoch_seconds, local_date_time.nanos) {
+                    Some(naive_date_time) => visitor
+                        .visit_string(naive_date_time.format("%Y-%m-%dT%H:%M:%S%.f").to_string()),
+                    None => Err(
+                        ConversionError::FromValue(Value::LocalDateTime(local_date_time)).into(),
+                    ),
+                }
+            }
+            Value::Duration(duration) => struct_map(vec![
+                ("months", Value::from(duration.months)),
+                ("days", Value::from(duration.days)),
+                ("seconds", Value::from(duration.seconds)),
+                ("nanos", Value::from(duration.nanos)),
+            ])
+            .deserialize_any(visitor),
+            Value::Point2D(point) => struct_map(vec![
+                ("srid", Value::from(point.srid)),
+                ("x", Value::from(point.x)),
+                ("y", Value::from(point.y)),
+            ])
+            .deserialize_any(visitor),
+            Value::Point3D(point) => struct_map(vec![
+                ("srid", Value::from(point.srid)),
+                ("x", Value::from(point.x)),
+                ("y", Value::from(point.y)),
+                ("z", Value::from(point.z)),
+            ])
+            .deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self {
+            Value::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    // Unit variants are represented as strings, and other variants as single-entry maps
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        match self {
+            Value::String(variant) => visitor.visit_enum(variant.into_deserializer()),
+            Value::Map(map) if map.value.len() == 1 => visitor.visit_enum(
+                MapAccessDeserializer::new(MapDeserializer::new(map.value.into_iter())),
+            ),
+            _ => Err(de::Error::invalid_type(self.unexpected(), &"an enum")),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+impl Value {
+    fn unexpected(&self) -> de::Unexpected<'_> {
+        match self {
+            Value::Boolean(boolean) => de::Unexpected::Bool(*boolean),
+            Value::Integer(integer) => de::Unexpected::Signed(i64::from(integer.clone())),
+            Value::Float(float) => de::Unexpected::Float(*float),
+            Value::Bytes(byte_array) => de::Unexpected::Bytes(&byte_array.value),
+            Value::List(_) => de::Unexpected::Seq,
+            Value::Map(_) => de::Unexpected::Map,
+            Value::Null => de::Unexpected::Unit,
+            Value::String(string) => de::Unexpected::Str(string),
+            _ => de::Unexpected::Other("a structure"),
+        }
+    }
+}
+
+// Times received from the server aren't validated, so they may be out of range
+fn naive_time(nanos_since_midnight: i64) -> Option<NaiveTime> {
+    if nanos_since_midnight < 0 {
+        return None;
+    }
+    NaiveTime::from_num_seconds_from_midnight_opt(
+        u32::try_from(nanos_since_midnight / 1_000_000_000).ok()?,
+        (nanos_since_midnight % 1_000_000_000) as u32,
+    )
+}
+
+// The same goes for dates and date-times, which may fall outside the range chrono supports
+fn naive_date(days_since_epoch: i64) -> Option<NaiveDate> {
+    // 1970-01-01 is day 719,163 of the Common Era
+    let days_from_ce = days_since_epoch.checked_add(719_163)?;
+    NaiveDate::from_num_days_from_ce_opt(i32::try_from(days_from_ce).ok()?)
+}
+
+fn naive_date_time(epoch_seconds: i64, nanos: i64) -> Option<NaiveDateTime> {
+    let date = naive_date(epoch_seconds.div_euclid(86_400))?;
+    let time = NaiveTime::from_num_seconds_from_midnight_opt(
+        epoch_seconds.rem_euclid(86_400) as u32,
+        u32::try_from(nanos).ok()?,
+    )?;
+    Some(date.and_time(time))
+}
+
+struct RecordDeserializer {
+    fields: Vec<std::string::String>,
+    values: Vec<Value>,
+}
+
+impl<'de> de::Deserializer<'de> for RecordDeserializer {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let mut map = MapDeserializer::new(self.fields.into_iter().zip(self.values));
+        let value = visitor.visit_map(&mut map)?;
+        map.end()?;
+        Ok(value)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let mut seq = SeqDeserializer::new(self.values.into_iter());
+        let value = visitor.visit_seq(&mut seq)?;
+        seq.end()?;
+        Ok(value)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_seq(visitor)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct map struct enum
+        identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::iter::FromIterator;
+
+    use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+    use serde::Deserialize;
+
+    use crate::message::Record;
+    use crate::value::*;
+
+    use super::*;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Person {
+        name: std::string::String,
+        age: u8,
+        nickname: Option<std::string::String>,
+        tags: Vec<std::string::String>,
+    }
+
+    fn person_properties() -> HashMap<std::string::String, Value> {
+        HashMap::from_iter(vec![
+            ("name".to_string(), Value::from("Alice")),
+            ("age".to_string(), Value::from(42_i8)),
+            ("nickname".to_string(), Value::Null),
+            ("tags".to_string(), Value::from(vec!["a", "b"])),
+        ])
+    }
+
+    fn alice() -> Person {
+        Person {
+            name: "Alice".to_string(),
+            age: 42,
+            nickname: None,
+            tags: vec!["a".to_string(), "b".to_string()],
+        }
+    }
+
+    #[test]
+    fn primitives() {
+        assert!(from_value::<bool>(Value::from(true)).unwrap());
+        assert_eq!(from_value::<i32>(Value::from(-300_i16)).unwrap(), -300);
+        assert_eq!(from_value::<f64>(Value::from(1.5)).unwrap(), 1.5);
+        assert_eq!(
+            from_value::<std::string::String>(Value::from("hi")).unwrap(),
+            "hi"
+        );
+        assert_eq!(from_value::<Option<i64>>(Value::Null).unwrap(), None);
+        assert_eq!(
+            from_value::<Option<i64>>(Value::from(1_i8)).unwrap(),
+            Some(1)
+        );
+        assert_eq!(
+            from_value::<Vec<i64>>(Value::from(vec![1_i8, 2, 3])).unwrap(),
+            vec![1, 2, 3]
+        );
+    }
+
+    #[test]
+    fn invalid_types() {
+        assert!(from_value::<u8>(Value::from(-1_i8)).is_err());
+        assert!(from_value::<u8>(Value::from(300_i16)).is_err());
+        assert!(from_value::<bool>(Value::from("true")).is_err());
+        assert!(from_value::<Person>(Value::from(vec![1_i8])).is_err());
+    }
+
+    #[test]
+    fn map_to_struct() {
+        let value = Value::from(person_properties());
+        assert_eq!(from_value::<Person>(value).unwrap(), alice());
+    }
+
+    #[test]
+    fn node_to_struct() {
+        let node = Node::new(1, vec!["Person".to_string()], person_properties());
+        assert_eq!(from_value::<Person>(Value::from(node)).unwrap(), alice());
+    }
+
+    #[test]
+    fn relationship_to_map() {
+        let rel = Relationship::new(
+            1,
+            2,
+            3,
+            "KNOWS".to_string(),
+            HashMap::from_iter(vec![("since".to_string(), Value::from(2010_i16))]),
+        );
+        let properties: HashMap<std::string::String, i32> = from_value(Value::from(rel)).unwrap();
+        assert_eq!(properties.get("since"), Some(&2010));
+    }
+
+    #[test]
+    fn enums() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        enum Shape {
+            Empty,
+            Circle { radius: f64 },
+        }
+
+        assert_eq!(
+            from_value::<Shape>(Value::from("Empty")).unwrap(),
+            Shape::Empty
+        );
+        let circle = Value::from(HashMap::from_iter(vec![(
+            "Circle",
+            Value::from(HashMap::from_iter(vec![("radius", 2.0)])),
+        )]));
+        assert_eq!(
+            from_value::<Shape>(circle).unwrap(),
+            Shape::Circle { radius: 2.0 }
+        );
+    }
+
+    #[test]
+    fn temporal() {
+        let date = NaiveDate::from_ymd_opt(2020, 5, 17).unwrap();
+        assert_eq!(from_value::<NaiveDate>(Value::from(date)).unwrap(), date);
+
+        let time = NaiveTime::from_hms_opt(12, 34, 56).unwrap();
+        assert_eq!(from_value::<NaiveTime>(Value::from(time)).unwrap(), time);
+
+        let date_time =
+            NaiveDateTime::new(date, NaiveTime::from_hms_nano_opt(12, 34, 56, 789).unwrap());
+        assert_eq!(
+            from_value::<NaiveDateTime>(Value::from(date_time)).unwrap(),
+            date_time
+        );
+
+        let date_time = FixedOffset::east_opt(3600)
+            .unwrap()
+            .from_utc_datetime(&date_time);
+        assert_eq!(
+            from_value::<DateTime<FixedOffset>>(Value::from(date_time)).unwrap(),
+            date_time
+        );
+
+        let zoned =
+            DateTimeZoned::new(2020, 5, 17, 12, 0, 0, 0, "Europe/Paris".to_string()).unwrap();
+        assert_eq!(
+            from_value::<DateTime<FixedOffset>>(Value::from(zoned.clone())).unwrap(),
+            DateTime::<FixedOffset>::from(zoned)
+        );
+    }
+
+    #[test]
+    fn invalid_temporal() {
+        // Offsets beyond 24 hours can be constructed, but can't be represented as a FixedOffset
+        let time = Time::new(1, 0, 0, 0, (25, 0)).unwrap();
+        assert!(matches!(
+            from_value::<std::string::String>(Value::from(time)),
+            Err(Error::ConversionError(ConversionError::FromValue(
+                Value::Time(_)
+            )))
+        ));
+
+        let time = Time {
+            nanos_since_midnight: -1,
+            zone_offset: 0,
+        };
+        assert!(matches!(
+            from_value::<std::string::String>(Value::from(time)),
+            Err(Error::ConversionError(ConversionError::FromValue(
+                Value::Time(_)
+            )))
+        ));
+
+        let local_time = LocalTime {
+            nanos_since_midnight: -1,
+        };
+        assert!(matches!(
+            from_value::<NaiveTime>(Value::from(local_time)),
+            Err(Error::ConversionError(ConversionError::FromValue(
+                Value::LocalTime(_)
+            )))
+        ));
+
+        let date = Date {
+            days_since_epoch: i64::MAX,
+        };
+        assert!(matches!(
+            from_value::<std::string::String>(Value::from(date)),
+            Err(Error::ConversionError(ConversionError::FromValue(
+                Value::Date(_)
+            )))
+        ));
+
+        let date_time_offset = DateTimeOffset {
+            epoch_seconds: i64::MAX,
+            nanos: 0,
+            offset_seconds: 0,
+        };
+        assert!(matches!(
+            from_value::<std::string::String>(Value::from(date_time_offset)),
+            Err(Error::ConversionError(ConversionError::FromValue(
+                Value::DateTimeOffset(_)
+            )))
+        ));
+
+        let date_time_offset = DateTimeOffset {
+            epoch_seconds: 0,
+            nanos: 0,
+            offset_seconds: 90_000,
+        };
+        assert!(matches!(
+            from_value::<std::string::String>(Value::from(date_time_offset)),
+            Err(Error::ConversionError(ConversionError::FromValue(
+                Value::DateTimeOffset(_)
+            )))
+        ));
+
+        let date_time_zoned = DateTimeZoned {
+            epoch_seconds: i64::MIN,
+            nanos: 0,
+            zone_id: "Europe/Paris".to_string(),
+        };
+        assert!(matches!(
+            from_value::<std::string::String>(Value::from(date_time_zoned)),
+            Err(Error::ConversionError(ConversionError::FromValue(
+                Value::DateTimeZoned(_)
+            )))
+        ));
+
+        let date_time_zoned = DateTimeZoned {
+            epoch_seconds: 0,
+            nanos: 0,
+            zone_id: "Not/A_Zone".to_string(),
+        };
+        assert!(matches!(
+            from_value::<std::string::String>(Value::from(date_time_zoned)),
+            Err(Error::ConversionError(ConversionError::FromValue(
+                Value::DateTimeZoned(_)
+            )))
+        ));
+
+        let local_date_time = LocalDateTime {
+            epoch_seconds: i64::MAX,
+            nanos: 0,
+        };
+        assert!(matches!(
+            from_value::<std::string::String>(Value::from(local_date_time)),
+            Err(Error::ConversionError(ConversionError::FromValue(
+                Value::LocalDateTime(_)
+            )))
+        ));
+
+        let local_date_time = LocalDateTime {
+            epoch_seconds: 0,
+            nanos: -1,
+        };
+        assert!(matches!(
+            from_value::<NaiveDateTime>(Value::from(local_date_time)),
+            Err(Error::ConversionError(ConversionError::FromValue(
+                Value::LocalDateTime(_)
+            )))
+        ));
+    }
+
+    #[test]
+    fn temporal_range_limits() {
+        // Dates far from the epoch in either direction are still converted
+        let date = NaiveDate::from_ymd_opt(262_000, 12, 31).unwrap();
+        assert_eq!(from_value::<NaiveDate>(Value::from(date)).unwrap(), date);
+        let date_time = NaiveDate::from_ymd_opt(-262_000, 1, 1)
+            .unwrap()
+            .and_hms_nano_opt(0, 0, 0, 1)
+            .unwrap();
+        assert_eq!(
+            from_value::<NaiveDateTime>(Value::from(date_time)).unwrap(),
+            date_time
+        );
+        let date_time_offset = DateTimeOffset {
+            epoch_seconds: 8_210_266_876_799,
+            nanos: 0,
+            offset_seconds: 3600,
+        };
+        assert!(from_value::<std::string::String>(Value::from(date_time_offset)).is_ok());
+    }
+
+    #[test]
+    fn duration_and_points() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Point {
+            srid: i32,
+            x: f64,
+            y: f64,
+        }
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Components {
+            months: i64,
+            days: i64,
+            seconds: i64,
+            nanos: i32,
+        }
+
+        let point = Point2D::new(7203, 1.0, 2.0);
+        assert_eq!(
+            from_value::<Point>(Value::from(point)).unwrap(),
+            Point {
+                srid: 7203,
+                x: 1.0,
+                y: 2.0
+            }
+        );
+
+        let duration = Duration::new(1, 2, 3, 4);
+        assert_eq!(
+            from_value::<Components>(Value::from(duration)).unwrap(),
+            Components {
+                months: 1,
+                days: 2,
+                seconds: 3,
+                nanos: 4
+            }
+        );
+    }
+
+    #[test]
+    fn record_to_struct() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Row {
+            person: Person,
+            score: f64,
+        }
+
+        let fields = vec!["person".to_string(), "score".to_string()];
+        let node = Node::new(1, vec![], person_properties());
+        let record = Record::new(vec![Value::from(node), Value::from(0.5)]);
+        assert_eq!(
+            from_record::<Row>(record, &fields).unwrap(),
+            Row {
+                person: alice(),
+                score: 0.5
+            }
+        );
+    }
+
+    #[test]
+    fn record_to_tuple() {
+        let fields = vec!["n".to_string(), "name".to_string()];
+        let record = Record::new(vec![Value::from(1_i8), Value::from("one")]);
+        assert_eq!(
+            from_record::<(i64, std::string::String)>(record, &fields).unwrap(),
+            (1, "one".to_string())
+        );
+    }
+
+    #[test]
+    fn record_field_count_mismatch() {
+        let record = Record::new(vec![Value::from(1_i8)]);
+        assert!(from_record::<HashMap<std::string::String, i64>>(record, &[]).is_err());
+    }
+}
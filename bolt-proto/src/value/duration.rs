@@ -27,7 +27,6 @@ impl Duration {
 #[cfg(test)]
 mod tests {
     use std::convert::TryFrom;
-    use std::sync::{Arc, Mutex};
 
     use bytes::Bytes;
 
@@ -100,7 +99,7 @@ mod tests {
             0x57,
         ];
         assert_eq!(
-            Duration::try_from(Arc::new(Mutex::new(Bytes::from_static(duration_bytes)))).unwrap(),
+            Duration::try_from(&mut Bytes::from_static(duration_bytes)).unwrap(),
             duration
         );
     }
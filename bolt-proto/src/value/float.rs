@@ -1,9 +1,7 @@
 use std::convert::{TryFrom, TryInto};
 use std::mem;
-use std::panic::catch_unwind;
-use std::sync::{Arc, Mutex};
 
-use bytes::{Buf, BufMut, Bytes, BytesMut};
+use bytes::{BufMut, Bytes, BytesMut};
 
 use crate::error::*;
 use crate::serialization::*;
@@ -38,27 +36,22 @@ impl TryInto<Bytes> for Float {
 
 impl Deserialize for Float {}
 
-impl TryFrom<Arc<Mutex<Bytes>>> for Float {
+impl TryFrom<&mut Bytes> for Float {
     type Error = Error;
 
-    fn try_from(input_arc: Arc<Mutex<Bytes>>) -> Result<Self> {
-        catch_unwind(move || {
-            let mut input_bytes = input_arc.lock().unwrap();
-            let marker = input_bytes.get_u8();
-
-            match marker {
-                MARKER => Ok(Float::from(input_bytes.get_f64())),
-                _ => Err(DeserializationError::InvalidMarkerByte(marker).into()),
-            }
-        })
-        .map_err(|_| DeserializationError::Panicked)?
+    fn try_from(input_bytes: &mut Bytes) -> Result<Self> {
+        let marker = input_bytes.try_get_u8()?;
+
+        match marker {
+            MARKER => Ok(Float::from(input_bytes.try_get_f64()?)),
+            _ => Err(DeserializationError::InvalidMarkerByte(marker).into()),
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use std::convert::TryFrom;
-    use std::sync::{Arc, Mutex};
 
     use bytes::Bytes;
 
@@ -85,14 +78,14 @@ mod tests {
     fn try_from_bytes() {
         let pi = Float::from(std::f64::consts::PI);
         assert_eq!(
-            Float::try_from(Arc::new(Mutex::new(pi.clone().try_into_bytes().unwrap()))).unwrap(),
+            Float::try_from(&mut pi.clone().try_into_bytes().unwrap()).unwrap(),
             pi
         );
         let max = Float::from(std::f64::MAX);
         assert_eq!(
-            Float::try_from(Arc::new(Mutex::new(max.clone().try_into_bytes().unwrap()))).unwrap(),
+            Float::try_from(&mut max.clone().try_into_bytes().unwrap()).unwrap(),
             max
         );
-        assert!(Float::try_from(Arc::new(Mutex::new(Bytes::from_static(&[0x01])))).is_err());
+        assert!(Float::try_from(&mut Bytes::from_static(&[0x01])).is_err());
     }
 }
@@ -1,7 +1,5 @@
 use std::convert::{TryFrom, TryInto};
 use std::mem;
-use std::panic::catch_unwind;
-use std::sync::{Arc, Mutex};
 
 use bytes::{Buf, BufMut, Bytes, BytesMut};
 
@@ -66,24 +64,20 @@ impl TryInto<Bytes> for Integer {
 
 impl Deserialize for Integer {}
 
-impl TryFrom<Arc<Mutex<Bytes>>> for Integer {
+impl TryFrom<&mut Bytes> for Integer {
     type Error = Error;
 
-    fn try_from(input_arc: Arc<Mutex<Bytes>>) -> Result<Self> {
-        catch_unwind(move || {
-            let mut input_bytes = input_arc.lock().unwrap();
-            let marker = input_bytes.get_u8();
-
-            match marker {
-                marker if (-16..=127).contains(&(marker as i8)) => Ok(Integer::from(marker as i8)),
-                MARKER_INT_8 => Ok(Integer::from(input_bytes.get_i8())),
-                MARKER_INT_16 => Ok(Integer::from(input_bytes.get_i16())),
-                MARKER_INT_32 => Ok(Integer::from(input_bytes.get_i32())),
-                MARKER_INT_64 => Ok(Integer::from(input_bytes.get_i64())),
-                _ => Err(DeserializationError::InvalidMarkerByte(marker).into()),
-            }
-        })
-        .map_err(|_| DeserializationError::Panicked)?
+    fn try_from(input_bytes: &mut Bytes) -> Result<Self> {
+        let marker = input_bytes.try_get_u8()?;
+
+        match marker {
+            marker if (-16..=127).contains(&(marker as i8)) => Ok(Integer::from(marker as i8)),
+            MARKER_INT_8 => Ok(Integer::from(input_bytes.try_get_i8()?)),
+            MARKER_INT_16 => Ok(Integer::from(input_bytes.try_get_i16()?)),
+            MARKER_INT_32 => Ok(Integer::from(input_bytes.try_get_i32()?)),
+            MARKER_INT_64 => Ok(Integer::from(input_bytes.try_get_i64()?)),
+            _ => Err(DeserializationError::InvalidMarkerByte(marker).into()),
+        }
     }
 }
 
@@ -157,40 +151,27 @@ mod tests {
     fn try_from_bytes() {
         let tiny = Integer::from(-16_i8);
         assert_eq!(
-            Integer::try_from(Arc::new(Mutex::new(tiny.clone().try_into_bytes().unwrap())))
-                .unwrap(),
+            Integer::try_from(&mut tiny.clone().try_into_bytes().unwrap()).unwrap(),
             tiny
         );
         let small = Integer::from(-50_i8);
         assert_eq!(
-            Integer::try_from(Arc::new(Mutex::new(
-                small.clone().try_into_bytes().unwrap()
-            )))
-            .unwrap(),
+            Integer::try_from(&mut small.clone().try_into_bytes().unwrap()).unwrap(),
             small
         );
         let medium = Integer::from(-8000_i16);
         assert_eq!(
-            Integer::try_from(Arc::new(Mutex::new(
-                medium.clone().try_into_bytes().unwrap()
-            )))
-            .unwrap(),
+            Integer::try_from(&mut medium.clone().try_into_bytes().unwrap()).unwrap(),
             medium
         );
         let large = Integer::from(-1_000_000_000_i32);
         assert_eq!(
-            Integer::try_from(Arc::new(Mutex::new(
-                large.clone().try_into_bytes().unwrap()
-            )))
-            .unwrap(),
+            Integer::try_from(&mut large.clone().try_into_bytes().unwrap()).unwrap(),
             large
         );
         let very_large = Integer::from(-9_000_000_000_000_000_000_i64);
         assert_eq!(
-            Integer::try_from(Arc::new(Mutex::new(
-                very_large.clone().try_into_bytes().unwrap()
-            )))
-            .unwrap(),
+            Integer::try_from(&mut very_large.clone().try_into_bytes().unwrap()).unwrap(),
             very_large
         );
     }
@@ -204,15 +185,11 @@ mod tests {
             number_bytes,
         );
         assert_eq!(
-            i32::from(
-                Integer::try_from(Arc::new(Mutex::new(Bytes::from_static(number_bytes)))).unwrap()
-            ),
+            i32::from(Integer::try_from(&mut Bytes::from_static(number_bytes)).unwrap()),
             number as i32
         );
         assert_eq!(
-            i64::from(
-                Integer::try_from(Arc::new(Mutex::new(Bytes::from_static(number_bytes)))).unwrap()
-            ),
+            i64::from(Integer::try_from(&mut Bytes::from_static(number_bytes)).unwrap()),
             number as i64
         );
     }
@@ -226,15 +203,11 @@ mod tests {
             number_bytes,
         );
         assert_eq!(
-            i32::from(
-                Integer::try_from(Arc::new(Mutex::new(Bytes::from_static(number_bytes)))).unwrap()
-            ),
+            i32::from(Integer::try_from(&mut Bytes::from_static(number_bytes)).unwrap()),
             number as i32
         );
         assert_eq!(
-            i64::from(
-                Integer::try_from(Arc::new(Mutex::new(Bytes::from_static(number_bytes)))).unwrap()
-            ),
+            i64::from(Integer::try_from(&mut Bytes::from_static(number_bytes)).unwrap()),
             number as i64
         );
     }
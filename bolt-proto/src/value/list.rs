@@ -1,7 +1,5 @@
 use std::convert::{TryFrom, TryInto};
 use std::mem;
-use std::panic::catch_unwind;
-use std::sync::{Arc, Mutex};
 
 use bytes::{Buf, BufMut, Bytes, BytesMut};
 
@@ -70,30 +68,28 @@ impl TryInto<Bytes> for List {
 
 impl Deserialize for List {}
 
-impl TryFrom<Arc<Mutex<Bytes>>> for List {
+impl TryFrom<&mut Bytes> for List {
     type Error = Error;
 
-    fn try_from(input_arc: Arc<Mutex<Bytes>>) -> Result<Self> {
-        catch_unwind(move || {
-            let marker = input_arc.lock().unwrap().get_u8();
-            let size = match marker {
-                marker if (MARKER_TINY..=(MARKER_TINY | 0x0F)).contains(&marker) => {
-                    0x0F & marker as usize
-                }
-                MARKER_SMALL => input_arc.lock().unwrap().get_u8() as usize,
-                MARKER_MEDIUM => input_arc.lock().unwrap().get_u16() as usize,
-                MARKER_LARGE => input_arc.lock().unwrap().get_u32() as usize,
-                _ => {
-                    return Err(DeserializationError::InvalidMarkerByte(marker).into());
-                }
-            };
-            let mut list: Vec<Value> = Vec::with_capacity(size);
-            for _ in 0..size {
-                list.push(Value::try_from(Arc::clone(&input_arc))?);
+    fn try_from(input_bytes: &mut Bytes) -> Result<Self> {
+        let marker = input_bytes.try_get_u8()?;
+        let size = match marker {
+            marker if (MARKER_TINY..=(MARKER_TINY | 0x0F)).contains(&marker) => {
+                0x0F & marker as usize
             }
-            Ok(List::from(list))
-        })
-        .map_err(|_| DeserializationError::Panicked)?
+            MARKER_SMALL => input_bytes.try_get_u8()? as usize,
+            MARKER_MEDIUM => input_bytes.try_get_u16()? as usize,
+            MARKER_LARGE => input_bytes.try_get_u32()? as usize,
+            _ => {
+                return Err(DeserializationError::InvalidMarkerByte(marker).into());
+            }
+        };
+        // Each value takes up at least one byte, so don't trust the size more than the remaining input
+        let mut list: Vec<Value> = Vec::with_capacity(size.min(input_bytes.remaining()));
+        for _ in 0..size {
+            list.push(Value::try_from(&mut *input_bytes)?);
+        }
+        Ok(List::from(list))
     }
 }
 
@@ -182,39 +178,24 @@ mod tests {
     #[test]
     fn try_from_bytes() {
         let empty_list: List = Vec::<i32>::new().into();
-        let empty_list_bytes = empty_list.clone().try_into_bytes().unwrap();
+        let mut empty_list_bytes = empty_list.clone().try_into_bytes().unwrap();
         let tiny_list: List = vec![100_000_000_000_i64; 10].into();
-        let tiny_list_bytes = tiny_list.clone().try_into_bytes().unwrap();
+        let mut tiny_list_bytes = tiny_list.clone().try_into_bytes().unwrap();
         let small_list: List = vec!["item"; 100].into();
-        let small_list_bytes = small_list.clone().try_into_bytes().unwrap();
+        let mut small_list_bytes = small_list.clone().try_into_bytes().unwrap();
         let medium_list: List = vec![false; 1000].into();
-        let medium_list_bytes = medium_list.clone().try_into_bytes().unwrap();
-        assert_eq!(
-            List::try_from(Arc::new(Mutex::new(empty_list_bytes))).unwrap(),
-            empty_list
-        );
-        assert_eq!(
-            List::try_from(Arc::new(Mutex::new(tiny_list_bytes))).unwrap(),
-            tiny_list
-        );
-        assert_eq!(
-            List::try_from(Arc::new(Mutex::new(small_list_bytes))).unwrap(),
-            small_list
-        );
-        assert_eq!(
-            List::try_from(Arc::new(Mutex::new(medium_list_bytes))).unwrap(),
-            medium_list
-        );
+        let mut medium_list_bytes = medium_list.clone().try_into_bytes().unwrap();
+        assert_eq!(List::try_from(&mut empty_list_bytes).unwrap(), empty_list);
+        assert_eq!(List::try_from(&mut tiny_list_bytes).unwrap(), tiny_list);
+        assert_eq!(List::try_from(&mut small_list_bytes).unwrap(), small_list);
+        assert_eq!(List::try_from(&mut medium_list_bytes).unwrap(), medium_list);
     }
 
     #[test]
     #[ignore]
     fn try_from_large_bytes() {
         let large_list: List = vec![1_i8; 100_000].into();
-        let large_list_bytes = large_list.clone().try_into_bytes().unwrap();
-        assert_eq!(
-            List::try_from(Arc::new(Mutex::new(large_list_bytes))).unwrap(),
-            large_list
-        );
+        let mut large_list_bytes = large_list.clone().try_into_bytes().unwrap();
+        assert_eq!(List::try_from(&mut large_list_bytes).unwrap(), large_list);
     }
 }
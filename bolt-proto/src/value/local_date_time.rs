@@ -41,7 +41,6 @@ impl LocalDateTime {
 #[cfg(test)]
 mod tests {
     use std::convert::TryFrom;
-    use std::sync::{Arc, Mutex};
 
     use bytes::Bytes;
 
@@ -102,10 +101,7 @@ mod tests {
             0xA4,
         ];
         assert_eq!(
-            LocalDateTime::try_from(Arc::new(Mutex::new(Bytes::from_static(
-                local_date_time_bytes
-            ))))
-            .unwrap(),
+            LocalDateTime::try_from(&mut Bytes::from_static(local_date_time_bytes)).unwrap(),
             local_date_time
         );
     }
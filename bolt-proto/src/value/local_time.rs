@@ -27,7 +27,6 @@ impl LocalTime {
 #[cfg(test)]
 mod tests {
     use std::convert::TryFrom;
-    use std::sync::{Arc, Mutex};
 
     use bytes::Bytes;
 
@@ -82,7 +81,7 @@ mod tests {
             0x00,
         ];
         assert_eq!(
-            LocalTime::try_from(Arc::new(Mutex::new(Bytes::from_static(time_bytes)))).unwrap(),
+            LocalTime::try_from(&mut Bytes::from_static(time_bytes)).unwrap(),
             time
         );
     }
@@ -2,8 +2,6 @@ use std::collections::hash_map::RandomState;
 use std::collections::HashMap;
 use std::convert::{TryFrom, TryInto};
 use std::mem;
-use std::panic::catch_unwind;
-use std::sync::{Arc, Mutex};
 
 use bytes::{Buf, BufMut, Bytes, BytesMut};
 
@@ -100,32 +98,34 @@ impl TryInto<Bytes> for Map {
 
 impl Deserialize for Map {}
 
-impl TryFrom<Arc<Mutex<Bytes>>> for Map {
+impl TryFrom<&mut Bytes> for Map {
     type Error = Error;
 
-    fn try_from(input_arc: Arc<Mutex<Bytes>>) -> Result<Self> {
-        catch_unwind(move || {
-            let marker = input_arc.lock().unwrap().get_u8();
-            let size = match marker {
-                marker if (MARKER_TINY..=(MARKER_TINY | 0x0F)).contains(&marker) => {
-                    0x0F & marker as usize
-                }
-                MARKER_SMALL => input_arc.lock().unwrap().get_u8() as usize,
-                MARKER_MEDIUM => input_arc.lock().unwrap().get_u16() as usize,
-                MARKER_LARGE => input_arc.lock().unwrap().get_u32() as usize,
-                _ => {
-                    return Err(DeserializationError::InvalidMarkerByte(marker).into());
-                }
-            };
-            let mut hash_map: HashMap<Value, Value> = HashMap::with_capacity(size);
-            for _ in 0..size {
-                let key = Value::try_from(Arc::clone(&input_arc))?;
-                let value = Value::try_from(Arc::clone(&input_arc))?;
-                hash_map.insert(key, value);
+    fn try_from(input_bytes: &mut Bytes) -> Result<Self> {
+        let marker = input_bytes.try_get_u8()?;
+        let size = match marker {
+            marker if (MARKER_TINY..=(MARKER_TINY | 0x0F)).contains(&marker) => {
+                0x0F & marker as usize
+            }
+            MARKER_SMALL => input_bytes.try_get_u8()? as usize,
+            MARKER_MEDIUM => input_bytes.try_get_u16()? as usize,
+            MARKER_LARGE => input_bytes.try_get_u32()? as usize,
+            _ => {
+                return Err(DeserializationError::InvalidMarkerByte(marker).into());
             }
-            Ok(Map::from(hash_map))
-        })
-        .map_err(|_| DeserializationError::Panicked)?
+        };
+        // Each entry takes up at least two bytes, so don't trust the size more than the remaining input
+        let mut hash_map: HashMap<Value, Value> =
+            HashMap::with_capacity(size.min(input_bytes.remaining() / 2));
+        for _ in 0..size {
+            let key = match Value::try_from(&mut *input_bytes)? {
+                key @ Value::String(_) => key,
+                key => return Err(DeserializationError::InvalidMapKey(key).into()),
+            };
+            let value = Value::try_from(&mut *input_bytes)?;
+            hash_map.insert(key, value);
+        }
+        Ok(Map::from(hash_map))
     }
 }
 
@@ -135,7 +135,6 @@ mod tests {
     use std::collections::HashMap;
     use std::convert::TryFrom;
     use std::iter::FromIterator;
-    use std::sync::{Arc, Mutex};
 
     use bytes::Bytes;
 
@@ -196,9 +195,9 @@ mod tests {
     #[test]
     fn try_from_bytes() {
         let empty_map: Map = HashMap::<&str, i8>::new().into();
-        let empty_map_bytes = empty_map.clone().try_into_bytes().unwrap();
+        let mut empty_map_bytes = empty_map.clone().try_into_bytes().unwrap();
         let tiny_map: Map = HashMap::<&str, i8>::from_iter(vec![("a", 1_i8)]).into();
-        let tiny_map_bytes = tiny_map.clone().try_into_bytes().unwrap();
+        let mut tiny_map_bytes = tiny_map.clone().try_into_bytes().unwrap();
         let small_map: Map = HashMap::<&str, i8>::from_iter(vec![
             ("a", 1_i8),
             ("b", 1_i8),
@@ -218,25 +217,16 @@ mod tests {
             ("p", 6_i8),
         ])
         .into();
-        let small_map_bytes = small_map.clone().try_into_bytes().unwrap();
+        let mut small_map_bytes = small_map.clone().try_into_bytes().unwrap();
 
-        assert_eq!(
-            Map::try_from(Arc::new(Mutex::new(empty_map_bytes))).unwrap(),
-            empty_map
-        );
-        assert_eq!(
-            Map::try_from(Arc::new(Mutex::new(tiny_map_bytes))).unwrap(),
-            tiny_map
-        );
-        assert_eq!(
-            Map::try_from(Arc::new(Mutex::new(small_map_bytes))).unwrap(),
-            small_map
-        );
+        assert_eq!(Map::try_from(&mut empty_map_bytes).unwrap(), empty_map);
+        assert_eq!(Map::try_from(&mut tiny_map_bytes).unwrap(), tiny_map);
+        assert_eq!(Map::try_from(&mut small_map_bytes).unwrap(), small_map);
     }
 
     #[test]
     fn deep_nested_map_is_ok() {
-        let bytes = Bytes::from_static(&[
+        let mut bytes = Bytes::from_static(&[
             // From https://boltprotocol.org/v1/#accessing_notifications
             0xA4, 0x84, 0x74, 0x79, 0x70, 0x65, 0x81, 0x72, 0xD0, 0x15, 0x72, 0x65, 0x73, 0x75,
             0x6C, 0x74, 0x5F, 0x63, 0x6F, 0x6E, 0x73, 0x75, 0x6D, 0x65, 0x64, 0x5F, 0x61, 0x66,
@@ -320,6 +310,6 @@ mod tests {
             0x73, 0x65, 0x74, 0x00, 0x86, 0x63, 0x6F, 0x6C, 0x75, 0x6D, 0x6E, 0x01, 0x84, 0x6C,
             0x69, 0x6E, 0x65, 0x01,
         ]);
-        assert!(Map::try_from(Arc::new(Mutex::new(bytes))).is_ok());
+        assert!(Map::try_from(&mut bytes).is_ok());
     }
 }
@@ -1,8 +1,6 @@
 use std::convert::{TryFrom, TryInto};
-use std::panic::catch_unwind;
-use std::sync::{Arc, Mutex};
 
-use bytes::{Buf, Bytes};
+use bytes::Bytes;
 
 use crate::error::*;
 use crate::serialization::*;
@@ -30,28 +28,22 @@ impl TryInto<Bytes> for Null {
 
 impl Deserialize for Null {}
 
-impl TryFrom<Arc<Mutex<Bytes>>> for Null {
+impl TryFrom<&mut Bytes> for Null {
     type Error = Error;
 
-    fn try_from(input_arc: Arc<Mutex<Bytes>>) -> Result<Self> {
-        catch_unwind(move || {
-            let mut input_bytes = input_arc.lock().unwrap();
-            let marker = input_bytes.get_u8();
-            debug_assert!(!input_bytes.has_remaining());
-            if marker == MARKER {
-                Ok(Null)
-            } else {
-                Err(DeserializationError::InvalidMarkerByte(marker).into())
-            }
-        })
-        .map_err(|_| DeserializationError::Panicked)?
+    fn try_from(input_bytes: &mut Bytes) -> Result<Self> {
+        let marker = input_bytes.try_get_u8()?;
+        if marker == MARKER {
+            Ok(Null)
+        } else {
+            Err(DeserializationError::InvalidMarkerByte(marker).into())
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use std::convert::TryFrom;
-    use std::sync::{Arc, Mutex};
 
     use bytes::Bytes;
 
@@ -73,9 +65,9 @@ mod tests {
     #[test]
     fn try_from_bytes() {
         assert_eq!(
-            Null::try_from(Arc::new(Mutex::new(Null.try_into_bytes().unwrap()))).unwrap(),
+            Null::try_from(&mut Null.try_into_bytes().unwrap()).unwrap(),
             Null
         );
-        assert!(Null::try_from(Arc::new(Mutex::new(Bytes::from_static(&[0x01])))).is_err());
+        assert!(Null::try_from(&mut Bytes::from_static(&[0x01])).is_err());
     }
 }
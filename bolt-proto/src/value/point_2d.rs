@@ -35,7 +35,6 @@ impl_try_from_value!(Point2D, Point2D);
 #[cfg(test)]
 mod tests {
     use std::convert::TryFrom;
-    use std::sync::{Arc, Mutex};
 
     use bytes::Bytes;
 
@@ -110,7 +109,7 @@ mod tests {
             0x24,
         ];
         assert_eq!(
-            Point2D::try_from(Arc::new(Mutex::new(Bytes::from_static(point_bytes)))).unwrap(),
+            Point2D::try_from(&mut Bytes::from_static(point_bytes)).unwrap(),
             point
         );
     }
@@ -40,7 +40,6 @@ impl_try_from_value!(Point3D, Point3D);
 #[cfg(test)]
 mod tests {
     use std::convert::TryFrom;
-    use std::sync::{Arc, Mutex};
 
     use bytes::Bytes;
 
@@ -133,7 +132,7 @@ mod tests {
             0x24,
         ];
         assert_eq!(
-            Point3D::try_from(Arc::new(Mutex::new(Bytes::from_static(point_bytes)))).unwrap(),
+            Point3D::try_from(&mut Bytes::from_static(point_bytes)).unwrap(),
             point
         );
     }
@@ -0,0 +1,532 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt::Display;
+
+use serde::ser::{self, Serialize};
+
+use crate::error::*;
+use crate::value::*;
+
+/// Serialize an instance of type `T` into a [`Value`](crate::Value).
+///
+/// Structs and maps become `Value::Map`s, and sequences and tuples become `Value::List`s. Unit enum variants are
+/// serialized as strings, and other variants as single-entry maps keyed by the variant name. Fails if a map key isn't
+/// a string, or if an integer doesn't fit in an `i64`.
+pub fn to_value<T: Serialize + ?Sized>(value: &T) -> Result<Value> {
+    value.serialize(Serializer)
+}
+
+/// Serialize an instance of type `T` into a map of query parameters, as accepted by `RUN` messages. Fails if `T`
+/// isn't serialized as a map.
+pub fn to_parameters<T: Serialize + ?Sized>(
+    value: &T,
+) -> Result<HashMap<std::string::String, Value>> {
+    match to_value(value)? {
+        Value::Map(map) => map
+            .value
+            .into_iter()
+            .map(|(k, v)| Ok((std::string::String::try_from(k)?, v)))
+            .collect(),
+        other => Err(ConversionError::FromValue(other).into()),
+    }
+}
+
+impl ser::Error for Error {
+    fn custom<T: Display>(msg: T) -> Self {
+        ConversionError::Serde(msg.to_string()).into()
+    }
+}
+
+fn map_value(entries: Vec<(Value, Value)>) -> Value {
+    Value::Map(Map {
+        value: entries.into_iter().collect(),
+    })
+}
+
+fn variant_value(variant: &'static str, value: Value) -> Value {
+    map_value(vec![(Value::from(variant), value)])
+}
+
+// Use the smallest integer that fits, as the value would have if it was received from the server
+fn integer(value: i64) -> Value {
+    if let Ok(value) = i8::try_from(value) {
+        Value::from(value)
+    } else if let Ok(value) = i16::try_from(value) {
+        Value::from(value)
+    } else if let Ok(value) = i32::try_from(value) {
+        Value::from(value)
+    } else {
+        Value::from(value)
+    }
+}
+
+fn out_of_range(value: impl Display) -> Error {
+    ConversionError::IntegerOutOfRange(value.to_string()).into()
+}
+
+struct Serializer;
+
+impl ser::Serializer for Serializer {
+    type Ok = Value;
+    type Error = Error;
+
+    type SerializeSeq = SerializeList;
+    type SerializeTuple = SerializeList;
+    type SerializeTupleStruct = SerializeList;
+    type SerializeTupleVariant = SerializeList;
+    type SerializeMap = SerializeMap;
+    type SerializeStruct = SerializeMap;
+    type SerializeStructVariant = SerializeMap;
+
+    fn serialize_bool(self, v: bool) -> Result<Value> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Value> {
+        Ok(integer(v as i64))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Value> {
+        Ok(integer(v as i64))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Value> {
+        Ok(integer(v as i64))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Value> {
+        Ok(integer(v))
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Value> {
+        i64::try_from(v).map(integer).map_err(|_| out_of_range(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Value> {
+        Ok(integer(v as i64))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Value> {
+        Ok(integer(v as i64))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Value> {
+        Ok(integer(v as i64))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Value> {
+        i64::try_from(v).map(integer).map_err(|_| out_of_range(v))
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Value> {
+        i64::try_from(v).map(integer).map_err(|_| out_of_range(v))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Value> {
+        Ok(Value::from(v as f64))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Value> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Value> {
+        Ok(Value::from(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Value> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Value> {
+        Ok(Value::Bytes(ByteArray::from(v)))
+    }
+
+    fn serialize_none(self) -> Result<Value> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Value> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Value> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Value> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Value> {
+        Ok(Value::from(variant))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Value> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Value> {
+        Ok(variant_value(variant, to_value(value)?))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeList> {
+        Ok(SerializeList {
+            variant: None,
+            values: Vec::with_capacity(len.unwrap_or_default()),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SerializeList> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<SerializeList> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeList> {
+        Ok(SerializeList {
+            variant: Some(variant),
+            values: Vec::with_capacity(len),
+        })
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<SerializeMap> {
+        Ok(SerializeMap {
+            variant: None,
+            entries: Vec::with_capacity(len.unwrap_or_default()),
+            next_key: None,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<SerializeMap> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeMap> {
+        Ok(SerializeMap {
+            variant: Some(variant),
+            entries: Vec::with_capacity(len),
+            next_key: None,
+        })
+    }
+}
+
+struct SerializeList {
+    variant: Option<&'static str>,
+    values: Vec<Value>,
+}
+
+impl SerializeList {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.values.push(to_value(value)?);
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Value> {
+        let list = Value::List(List::from(self.values));
+        match self.variant {
+            Some(variant) => Ok(variant_value(variant, list)),
+            None => Ok(list),
+        }
+    }
+}
+
+impl ser::SerializeSeq for SerializeList {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTuple for SerializeList {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeList {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleVariant for SerializeList {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value> {
+        self.finish()
+    }
+}
+
+struct SerializeMap {
+    variant: Option<&'static str>,
+    entries: Vec<(Value, Value)>,
+    next_key: Option<Value>,
+}
+
+impl SerializeMap {
+    fn insert<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<()> {
+        self.entries.push((Value::from(key), to_value(value)?));
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Value> {
+        let map = map_value(self.entries);
+        match self.variant {
+            Some(variant) => Ok(variant_value(variant, map)),
+            None => Ok(map),
+        }
+    }
+}
+
+impl ser::SerializeMap for SerializeMap {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<()> {
+        match to_value(key)? {
+            key @ Value::String(_) => {
+                self.next_key = Some(key);
+                Ok(())
+            }
+            key => Err(ConversionError::InvalidMapKey(key).into()),
+        }
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        let key = self.next_key.take().ok_or_else(|| {
+            <Error as ser::Error>::custom("serialize_value called before serialize_key")
+        })?;
+        self.entries.push((key, to_value(value)?));
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStruct for SerializeMap {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<Value> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStructVariant for SerializeMap {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<Value> {
+        self.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashMap};
+    use std::iter::FromIterator;
+
+    use serde::{Deserialize, Serialize};
+
+    use crate::value::*;
+
+    use super::*;
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Person {
+        name: std::string::String,
+        age: u8,
+        nickname: Option<std::string::String>,
+        tags: Vec<std::string::String>,
+    }
+
+    fn alice() -> Person {
+        Person {
+            name: "Alice".to_string(),
+            age: 42,
+            nickname: None,
+            tags: vec!["a".to_string(), "b".to_string()],
+        }
+    }
+
+    #[test]
+    fn primitives() {
+        assert_eq!(to_value(&true).unwrap(), Value::from(true));
+        assert_eq!(to_value(&-300_i32).unwrap(), Value::from(-300_i16));
+        assert_eq!(to_value(&200_u8).unwrap(), Value::from(200_i16));
+        assert_eq!(to_value(&1.5_f64).unwrap(), Value::from(1.5));
+        assert_eq!(to_value(&'x').unwrap(), Value::from("x"));
+        assert_eq!(to_value("hi").unwrap(), Value::from("hi"));
+        assert_eq!(to_value(&None::<i64>).unwrap(), Value::Null);
+        assert_eq!(to_value(&()).unwrap(), Value::Null);
+        assert_eq!(
+            to_value(&vec![1_i64, 2, 3]).unwrap(),
+            Value::from(vec![1_i8, 2, 3])
+        );
+        assert_eq!(
+            to_value(&(1_i64, "one")).unwrap(),
+            Value::List(List::from(vec![Value::from(1_i8), Value::from("one")]))
+        );
+    }
+
+    #[test]
+    fn integer_out_of_range() {
+        assert_eq!(to_value(&(i64::MAX as u64)).unwrap(), Value::from(i64::MAX));
+        assert!(matches!(
+            to_value(&u64::MAX),
+            Err(Error::ConversionError(ConversionError::IntegerOutOfRange(
+                _
+            )))
+        ));
+        assert!(matches!(
+            to_value(&i128::MIN),
+            Err(Error::ConversionError(ConversionError::IntegerOutOfRange(
+                _
+            )))
+        ));
+    }
+
+    #[test]
+    fn invalid_map_key() {
+        let map = BTreeMap::from_iter(vec![(1_i64, "one")]);
+        assert!(matches!(
+            to_value(&map),
+            Err(Error::ConversionError(ConversionError::InvalidMapKey(_)))
+        ));
+    }
+
+    #[test]
+    fn struct_to_map() {
+        let value = to_value(&alice()).unwrap();
+        assert_eq!(
+            value,
+            Value::from(HashMap::from_iter(vec![
+                ("name", Value::from("Alice")),
+                ("age", Value::from(42_i8)),
+                ("nickname", Value::Null),
+                ("tags", Value::from(vec!["a", "b"])),
+            ]))
+        );
+        assert_eq!(from_value::<Person>(value).unwrap(), alice());
+    }
+
+    #[test]
+    fn struct_to_parameters() {
+        let parameters = to_parameters(&alice()).unwrap();
+        assert_eq!(parameters.get("name"), Some(&Value::from("Alice")));
+        assert_eq!(parameters.len(), 4);
+        assert!(to_parameters(&vec![1_i64]).is_err());
+    }
+
+    #[test]
+    fn enums() {
+        #[derive(Debug, Serialize, Deserialize, PartialEq)]
+        enum Shape {
+            Empty,
+            Circle { radius: f64 },
+            Line(i64, i64),
+        }
+
+        assert_eq!(to_value(&Shape::Empty).unwrap(), Value::from("Empty"));
+        for shape in [
+            Shape::Empty,
+            Shape::Circle { radius: 2.0 },
+            Shape::Line(1, 2),
+        ] {
+            let value = to_value(&shape).unwrap();
+            assert_eq!(from_value::<Shape>(value).unwrap(), shape);
+        }
+    }
+
+    #[test]
+    fn bytes() {
+        struct Bytes(&'static [u8]);
+
+        impl Serialize for Bytes {
+            fn serialize<S: serde::Serializer>(
+                &self,
+                serializer: S,
+            ) -> std::result::Result<S::Ok, S::Error> {
+                serializer.serialize_bytes(self.0)
+            }
+        }
+
+        assert_eq!(
+            to_value(&Bytes(&[1, 2, 3])).unwrap(),
+            Value::Bytes(ByteArray::from(vec![1, 2, 3]))
+        );
+    }
+}
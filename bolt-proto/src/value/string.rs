@@ -1,10 +1,8 @@
 use std::convert::{TryFrom, TryInto};
 use std::mem;
-use std::panic::catch_unwind;
 use std::str;
-use std::sync::{Arc, Mutex};
 
-use bytes::{Buf, BufMut, Bytes, BytesMut};
+use bytes::{BufMut, Bytes, BytesMut};
 
 use crate::error::*;
 use crate::serialization::*;
@@ -59,39 +57,31 @@ impl TryInto<Bytes> for String {
 
 impl Deserialize for String {}
 
-impl TryFrom<Arc<Mutex<Bytes>>> for String {
+impl TryFrom<&mut Bytes> for String {
     type Error = Error;
 
-    fn try_from(input_arc: Arc<Mutex<Bytes>>) -> Result<Self> {
-        catch_unwind(move || {
-            let mut input_bytes = input_arc.lock().unwrap();
-            let marker = input_bytes.get_u8();
-            let size = match marker {
-                // Lower-order nibble of tiny string marker
-                0x80..=0x8F => 0x0F & marker as usize,
-                MARKER_SMALL => input_bytes.get_u8() as usize,
-                MARKER_MEDIUM => input_bytes.get_u16() as usize,
-                MARKER_LARGE => input_bytes.get_u32() as usize,
-                _ => {
-                    return Err(DeserializationError::InvalidMarkerByte(marker).into());
-                }
-            };
-            let mut string_bytes = BytesMut::with_capacity(size);
-            // We resize here so that the length of string_bytes is nonzero, which allows us to use copy_to_slice
-            string_bytes.resize(size, 0);
-            input_bytes.copy_to_slice(&mut string_bytes);
-            Ok(String::from(
-                str::from_utf8(&string_bytes).map_err(DeserializationError::InvalidUTF8)?,
-            ))
-        })
-        .map_err(|_| DeserializationError::Panicked)?
+    fn try_from(input_bytes: &mut Bytes) -> Result<Self> {
+        let marker = input_bytes.try_get_u8()?;
+        let size = match marker {
+            // Lower-order nibble of tiny string marker
+            0x80..=0x8F => 0x0F & marker as usize,
+            MARKER_SMALL => input_bytes.try_get_u8()? as usize,
+            MARKER_MEDIUM => input_bytes.try_get_u16()? as usize,
+            MARKER_LARGE => input_bytes.try_get_u32()? as usize,
+            _ => {
+                return Err(DeserializationError::InvalidMarkerByte(marker).into());
+            }
+        };
+        let string_bytes = try_split_to(input_bytes, size)?;
+        Ok(String::from(
+            str::from_utf8(&string_bytes).map_err(DeserializationError::InvalidUTF8)?,
+        ))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use std::convert::TryFrom;
-    use std::sync::{Arc, Mutex};
 
     use bytes::Bytes;
 
@@ -142,39 +132,27 @@ mod tests {
     fn try_from_bytes() {
         let tiny = String::from("string".repeat(1));
         assert_eq!(
-            String::try_from(Arc::new(Mutex::new(tiny.clone().try_into_bytes().unwrap()))).unwrap(),
+            String::try_from(&mut tiny.clone().try_into_bytes().unwrap()).unwrap(),
             tiny
         );
         let small = String::from("string".repeat(10));
         assert_eq!(
-            String::try_from(Arc::new(Mutex::new(
-                small.clone().try_into_bytes().unwrap()
-            )))
-            .unwrap(),
+            String::try_from(&mut small.clone().try_into_bytes().unwrap()).unwrap(),
             small
         );
         let medium = String::from("string".repeat(1000));
         assert_eq!(
-            String::try_from(Arc::new(Mutex::new(
-                medium.clone().try_into_bytes().unwrap()
-            )))
-            .unwrap(),
+            String::try_from(&mut medium.clone().try_into_bytes().unwrap()).unwrap(),
             medium
         );
         let large = String::from("string".repeat(100_000));
         assert_eq!(
-            String::try_from(Arc::new(Mutex::new(
-                large.clone().try_into_bytes().unwrap()
-            )))
-            .unwrap(),
+            String::try_from(&mut large.clone().try_into_bytes().unwrap()).unwrap(),
             large
         );
         let special = String::from("En å flöt över ängen");
         assert_eq!(
-            String::try_from(Arc::new(Mutex::new(
-                special.clone().try_into_bytes().unwrap()
-            )))
-            .unwrap(),
+            String::try_from(&mut special.clone().try_into_bytes().unwrap()).unwrap(),
             special
         );
     }
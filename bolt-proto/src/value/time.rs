@@ -44,7 +44,6 @@ impl Time {
 #[cfg(test)]
 mod tests {
     use std::convert::TryFrom;
-    use std::sync::{Arc, Mutex};
 
     use bytes::Bytes;
     use chrono::{DateTime, FixedOffset, NaiveDateTime};
@@ -109,7 +108,7 @@ mod tests {
             0x10,
         ];
         assert_eq!(
-            Time::try_from(Arc::new(Mutex::new(Bytes::from_static(time_bytes)))).unwrap(),
+            Time::try_from(&mut Bytes::from_static(time_bytes)).unwrap(),
             time
         );
     }
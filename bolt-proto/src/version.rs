@@ -0,0 +1,165 @@
+use std::convert::TryFrom;
+use std::fmt;
+
+use crate::error::*;
+
+/// A Bolt protocol version, consisting of a major and minor version number.
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub struct ProtocolVersion {
+    major: u8,
+    minor: u8,
+}
+
+impl ProtocolVersion {
+    pub const fn new(major: u8, minor: u8) -> Self {
+        Self { major, minor }
+    }
+
+    pub fn major(&self) -> u8 {
+        self.major
+    }
+
+    pub fn minor(&self) -> u8 {
+        self.minor
+    }
+}
+
+impl fmt::Display for ProtocolVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+impl From<ProtocolVersion> for u32 {
+    fn from(version: ProtocolVersion) -> Self {
+        u32::from(VersionRange::from(version))
+    }
+}
+
+// The server responds to a handshake with the chosen version encoded as 0x0000mmMM, with the minor version in the
+// second-lowest byte and the major version in the lowest byte. A response of all zeros means no version was chosen.
+impl TryFrom<u32> for ProtocolVersion {
+    type Error = Error;
+
+    fn try_from(value: u32) -> Result<Self> {
+        let [reserved, range, minor, major] = value.to_be_bytes();
+        if value == 0 || reserved != 0 || range != 0 {
+            return Err(Error::InvalidProtocolVersion(value));
+        }
+        Ok(Self::new(major, minor))
+    }
+}
+
+/// A version proposal sent by a client during the handshake. In addition to the maximum version, a proposal may
+/// specify a number of preceding minor versions that are also supported, e.g. a proposal of 4.4 with a range of 2
+/// covers versions 4.4, 4.3 and 4.2.
+///
+/// Version ranges are only understood by servers supporting Bolt v4.3 or later. When talking to older servers, it's
+/// best to include an exact proposal (with a range of 0) for the desired older version as well.
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
+pub struct VersionRange {
+    max: ProtocolVersion,
+    range: u8,
+}
+
+impl VersionRange {
+    pub const fn new(max: ProtocolVersion, range: u8) -> Self {
+        Self { max, range }
+    }
+
+    /// Create a proposal covering only the given version.
+    pub const fn exact(version: ProtocolVersion) -> Self {
+        Self::new(version, 0)
+    }
+
+    pub fn max(&self) -> ProtocolVersion {
+        self.max
+    }
+
+    pub fn min(&self) -> ProtocolVersion {
+        ProtocolVersion::new(self.max.major, self.max.minor.saturating_sub(self.range))
+    }
+
+    pub fn range(&self) -> u8 {
+        self.range
+    }
+
+    /// Check whether the given version is covered by this proposal.
+    pub fn contains(&self, version: ProtocolVersion) -> bool {
+        (self.min()..=self.max).contains(&version)
+    }
+}
+
+impl From<ProtocolVersion> for VersionRange {
+    fn from(version: ProtocolVersion) -> Self {
+        Self::exact(version)
+    }
+}
+
+// Proposals are encoded as 0x00RRmmMM: the range in the second-highest byte, followed by the minor and major version.
+impl From<VersionRange> for u32 {
+    fn from(proposal: VersionRange) -> Self {
+        u32::from_be_bytes([0, proposal.range, proposal.max.minor, proposal.max.major])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn version_into_u32() {
+        assert_eq!(u32::from(ProtocolVersion::new(1, 0)), 0x0000_0001);
+        assert_eq!(u32::from(ProtocolVersion::new(4, 1)), 0x0000_0104);
+    }
+
+    #[test]
+    fn version_from_u32() {
+        assert_eq!(
+            ProtocolVersion::try_from(0x0000_0003).unwrap(),
+            ProtocolVersion::new(3, 0)
+        );
+        assert_eq!(
+            ProtocolVersion::try_from(0x0000_0304).unwrap(),
+            ProtocolVersion::new(4, 3)
+        );
+        assert!(ProtocolVersion::try_from(0).is_err());
+        assert!(ProtocolVersion::try_from(0x0002_0404).is_err());
+        assert!(ProtocolVersion::try_from(0x4854_5450).is_err());
+    }
+
+    #[test]
+    fn version_ordering() {
+        assert!(ProtocolVersion::new(4, 0) > ProtocolVersion::new(3, 0));
+        assert!(ProtocolVersion::new(4, 1) > ProtocolVersion::new(4, 0));
+        assert!(ProtocolVersion::new(5, 0) > ProtocolVersion::new(4, 4));
+    }
+
+    #[test]
+    fn version_display() {
+        assert_eq!(ProtocolVersion::new(4, 2).to_string(), "4.2");
+    }
+
+    #[test]
+    fn range_into_u32() {
+        let proposal = VersionRange::new(ProtocolVersion::new(4, 4), 2);
+        assert_eq!(u32::from(proposal), 0x0002_0404);
+        let proposal = VersionRange::exact(ProtocolVersion::new(3, 0));
+        assert_eq!(u32::from(proposal), 0x0000_0003);
+    }
+
+    #[test]
+    fn range_contains() {
+        let proposal = VersionRange::new(ProtocolVersion::new(4, 4), 2);
+        assert_eq!(proposal.min(), ProtocolVersion::new(4, 2));
+        assert!(proposal.contains(ProtocolVersion::new(4, 4)));
+        assert!(proposal.contains(ProtocolVersion::new(4, 3)));
+        assert!(proposal.contains(ProtocolVersion::new(4, 2)));
+        assert!(!proposal.contains(ProtocolVersion::new(4, 1)));
+        assert!(!proposal.contains(ProtocolVersion::new(3, 4)));
+        assert!(!proposal.contains(ProtocolVersion::new(5, 4)));
+
+        let proposal = VersionRange::new(ProtocolVersion::new(4, 1), 5);
+        assert_eq!(proposal.min(), ProtocolVersion::new(4, 0));
+    }
+}
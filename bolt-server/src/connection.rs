@@ -0,0 +1,730 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::iter::FromIterator;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use futures::stream::Peekable;
+use futures::{SinkExt, StreamExt};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::sync::mpsc;
+use tokio_util::codec::{FramedRead, FramedWrite};
+
+use bolt_proto::message::*;
+use bolt_proto::{BoltCodec, Message, ProtocolVersion, Value};
+
+use crate::error::*;
+use crate::handler::{BoltHandler, HandlerError, RecordStream};
+
+// Messages are read ahead of time so that a RESET can interrupt the messages queued before it. This is how many
+// messages may be waiting to be processed before we stop reading from the client.
+const READ_AHEAD: usize = 32;
+
+// The states of the Bolt server state machine.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum State {
+    // The handshake is done, but the client hasn't been authenticated yet
+    Connected,
+    Ready,
+    Streaming,
+    TxReady,
+    TxStreaming,
+    // A request failed, and the client has yet to acknowledge the failure
+    Failed,
+    // A RESET has been received, but the messages queued before it are still being ignored
+    Interrupted,
+    // The connection should be closed
+    Defunct,
+}
+
+pub(crate) struct Connection<H, W> {
+    handler: H,
+    version: ProtocolVersion,
+    server_agent: String,
+    state: State,
+    in_transaction: bool,
+    result: Option<Peekable<RecordStream>>,
+    result_summary: HashMap<String, Value>,
+    messages: mpsc::Receiver<Result<Message>>,
+    pending_resets: Arc<AtomicUsize>,
+    writer: FramedWrite<W, BoltCodec>,
+}
+
+impl<H: BoltHandler, W: AsyncWrite + Unpin> Connection<H, W> {
+    pub(crate) fn new(
+        handler: H,
+        version: ProtocolVersion,
+        server_agent: String,
+        reader: impl AsyncRead + Unpin + Send + 'static,
+        writer: W,
+    ) -> Self {
+        let (sender, messages) = mpsc::channel(READ_AHEAD);
+        let pending_resets = Arc::new(AtomicUsize::new(0));
+        tokio::spawn(read_messages(
+            FramedRead::new(reader, BoltCodec::new()),
+            sender,
+            Arc::clone(&pending_resets),
+        ));
+        Self {
+            handler,
+            version,
+            server_agent,
+            state: State::Connected,
+            in_transaction: false,
+            result: None,
+            result_summary: HashMap::new(),
+            messages,
+            pending_resets,
+            writer: FramedWrite::new(writer, BoltCodec::new()),
+        }
+    }
+
+    pub(crate) async fn run(mut self) -> Result<()> {
+        let result = self.process_messages().await;
+        if self.in_transaction {
+            let _ = self.handler.rollback().await;
+        }
+        self.handler.goodbye().await;
+        // The read half is owned by the reader task, so shut down the write half to let the client know we're done
+        let _ = self.writer.close().await;
+        result
+    }
+
+    async fn process_messages(&mut self) -> Result<()> {
+        while let Some(message) = self.messages.recv().await {
+            let message = message?;
+            if !is_supported(&message, self.version) {
+                return Err(Error::UnsupportedMessage(self.version, message));
+            }
+            match message {
+                Message::Goodbye => return Ok(()),
+                Message::Reset if self.state != State::Connected => {
+                    self.pending_resets.fetch_sub(1, Ordering::SeqCst);
+                    self.reset().await?;
+                }
+                message => {
+                    if self.state != State::Connected
+                        && self.pending_resets.load(Ordering::SeqCst) > 0
+                    {
+                        self.state = State::Interrupted;
+                    }
+                    self.handle(message).await?;
+                }
+            }
+            if self.state == State::Defunct {
+                return Ok(());
+            }
+        }
+        Ok(())
+    }
+
+    async fn handle(&mut self, message: Message) -> Result<()> {
+        match (self.state, message) {
+            (State::Interrupted, _) => self.send(Message::Ignored).await,
+            (State::Failed, Message::AckFailure) => {
+                self.state = State::Ready;
+                self.send(success(HashMap::new())).await
+            }
+            (State::Failed, _) => self.send(Message::Ignored).await,
+
+            (State::Connected, Message::Init(init)) => {
+                let mut metadata = init.auth_token().clone();
+                metadata.insert("user_agent".to_string(), Value::from(init.client_name()));
+                self.authenticate(metadata).await
+            }
+            (State::Connected, Message::Hello(hello)) => {
+                self.authenticate(hello.metadata().clone()).await
+            }
+            // Anything other than authentication is a protocol violation at this point
+            (State::Connected, _) => {
+                self.state = State::Defunct;
+                Ok(())
+            }
+
+            (State::Ready, Message::Run(run)) | (State::TxReady, Message::Run(run)) => {
+                let (statement, parameters) = (run.statement(), run.parameters());
+                self.run_query(statement.to_string(), parameters.clone(), HashMap::new())
+                    .await
+            }
+            (State::Ready, Message::RunWithMetadata(run))
+            | (State::TxReady, Message::RunWithMetadata(run)) => {
+                let (statement, parameters) = (run.statement(), run.parameters());
+                self.run_query(
+                    statement.to_string(),
+                    parameters.clone(),
+                    run.metadata().clone(),
+                )
+                .await
+            }
+            (State::Ready, Message::Begin(begin)) => {
+                match self.handler.begin(begin.metadata().clone()).await {
+                    Ok(metadata) => {
+                        self.state = State::TxReady;
+                        self.in_transaction = true;
+                        self.send(success(metadata)).await
+                    }
+                    Err(error) => self.fail(error).await,
+                }
+            }
+            (State::TxReady, Message::Commit) => {
+                self.in_transaction = false;
+                match self.handler.commit().await {
+                    Ok(metadata) => {
+                        self.state = State::Ready;
+                        self.send(success(metadata)).await
+                    }
+                    Err(error) => self.fail(error).await,
+                }
+            }
+            (State::TxReady, Message::Rollback) => {
+                self.in_transaction = false;
+                match self.handler.rollback().await {
+                    Ok(()) => {
+                        self.state = State::Ready;
+                        self.send(success(HashMap::new())).await
+                    }
+                    Err(error) => self.fail(error).await,
+                }
+            }
+
+            (State::Streaming, Message::PullAll) | (State::TxStreaming, Message::PullAll) => {
+                self.stream(-1, false).await
+            }
+            (State::Streaming, Message::Pull(pull)) | (State::TxStreaming, Message::Pull(pull)) => {
+                self.stream(fetch_size(pull.metadata()), false).await
+            }
+            (State::Streaming, Message::DiscardAll) | (State::TxStreaming, Message::DiscardAll) => {
+                self.stream(-1, true).await
+            }
+            (State::Streaming, Message::Discard(discard))
+            | (State::TxStreaming, Message::Discard(discard)) => {
+                self.stream(fetch_size(discard.metadata()), true).await
+            }
+
+            (state, message) => {
+                let error = HandlerError::request_invalid(format!(
+                    "{:?} cannot be handled in the {:?} state",
+                    message, state
+                ));
+                self.fail(error).await
+            }
+        }
+    }
+
+    async fn authenticate(&mut self, metadata: HashMap<String, Value>) -> Result<()> {
+        match self.handler.hello(metadata).await {
+            Ok(mut metadata) => {
+                metadata
+                    .entry("server".to_string())
+                    .or_insert_with(|| Value::from(self.server_agent.as_str()));
+                self.state = State::Ready;
+                self.send(success(metadata)).await
+            }
+            Err(error) => {
+                // Clients that failed to authenticate are disconnected
+                self.send(Message::from(error)).await?;
+                self.state = State::Defunct;
+                Ok(())
+            }
+        }
+    }
+
+    async fn run_query(
+        &mut self,
+        statement: String,
+        parameters: HashMap<String, Value>,
+        metadata: HashMap<String, Value>,
+    ) -> Result<()> {
+        match self.handler.run(statement, parameters, metadata).await {
+            Ok(result) => {
+                let fields: Vec<Value> = result.fields.into_iter().map(Value::from).collect();
+                self.result = Some(result.records.peekable());
+                self.result_summary = result.summary;
+                self.state = if self.in_transaction {
+                    State::TxStreaming
+                } else {
+                    State::Streaming
+                };
+                self.send(success(HashMap::from_iter(vec![(
+                    "fields".to_string(),
+                    Value::from(fields),
+                )])))
+                .await
+            }
+            Err(error) => self.fail(error).await,
+        }
+    }
+
+    // Send (or discard) up to `n` records from the current result, or all of them if `n` is negative. Records are
+    // only pulled from the result as fast as they can be written to the client.
+    async fn stream(&mut self, n: i64, discard: bool) -> Result<()> {
+        let mut records = self.result.take().unwrap();
+        let mut sent = 0;
+        while n < 0 || sent < n {
+            if self.pending_resets.load(Ordering::SeqCst) > 0 {
+                self.state = State::Interrupted;
+                return self.send(Message::Ignored).await;
+            }
+            match records.next().await {
+                Some(Ok(record)) => {
+                    if !discard {
+                        self.writer.feed(Message::Record(record)).await?;
+                    }
+                    sent += 1;
+                }
+                Some(Err(error)) => return self.fail(error).await,
+                None => break,
+            }
+        }
+
+        if Pin::new(&mut records).peek().await.is_some() {
+            self.result = Some(records);
+            let has_more = vec![("has_more".to_string(), Value::from(true))];
+            return self.send(success(HashMap::from_iter(has_more))).await;
+        }
+        self.state = if self.in_transaction {
+            State::TxReady
+        } else {
+            State::Ready
+        };
+        let summary = std::mem::take(&mut self.result_summary);
+        self.send(success(summary)).await
+    }
+
+    async fn reset(&mut self) -> Result<()> {
+        self.result = None;
+        if self.in_transaction {
+            self.in_transaction = false;
+            let _ = self.handler.rollback().await;
+        }
+        self.handler.reset().await;
+        self.state = State::Ready;
+        self.send(success(HashMap::new())).await
+    }
+
+    // Report a failure to the client. Any open transaction is rolled back, and the client must acknowledge the failure
+    // (with ACK_FAILURE or RESET) before sending any other requests.
+    async fn fail(&mut self, error: HandlerError) -> Result<()> {
+        self.result = None;
+        if self.in_transaction {
+            self.in_transaction = false;
+            let _ = self.handler.rollback().await;
+        }
+        self.state = State::Failed;
+        self.send(Message::from(error)).await
+    }
+
+    async fn send(&mut self, message: Message) -> Result<()> {
+        Ok(self.writer.send(message).await?)
+    }
+}
+
+// Read messages from the client into the queue, keeping count of the RESET messages that haven't been processed yet.
+async fn read_messages(
+    mut reader: FramedRead<impl AsyncRead + Unpin, BoltCodec>,
+    mut sender: mpsc::Sender<Result<Message>>,
+    pending_resets: Arc<AtomicUsize>,
+) {
+    while let Some(message) = reader.next().await {
+        let message = message.map_err(Error::from);
+        let failed = message.is_err();
+        if let Ok(Message::Reset) = message {
+            pending_resets.fetch_add(1, Ordering::SeqCst);
+        }
+        if sender.send(message).await.is_err() || failed {
+            return;
+        }
+    }
+}
+
+// Check whether the client may send the given message when using the given protocol version.
+fn is_supported(message: &Message, version: ProtocolVersion) -> bool {
+    let major = version.major();
+    match message {
+        Message::Init(_) | Message::Run(_) | Message::AckFailure => major <= 2,
+        Message::DiscardAll | Message::PullAll => major <= 3,
+        Message::Hello(_)
+        | Message::Goodbye
+        | Message::RunWithMetadata(_)
+        | Message::Begin(_)
+        | Message::Commit
+        | Message::Rollback => major >= 3,
+        Message::Discard(_) | Message::Pull(_) => major >= 4,
+        Message::Route(_) => version == ProtocolVersion::new(4, 3),
+        Message::RouteWithMetadata(_) => version >= ProtocolVersion::new(4, 4),
+        Message::Reset => true,
+        // Server messages
+        Message::Record(_) | Message::Success(_) | Message::Failure(_) | Message::Ignored => false,
+    }
+}
+
+// Get the number of records requested by a PULL or DISCARD message, defaulting to all of them.
+fn fetch_size(metadata: &HashMap<String, Value>) -> i64 {
+    metadata
+        .get("n")
+        .and_then(|n| i64::try_from(n.clone()).ok())
+        .unwrap_or(-1)
+}
+
+fn success(metadata: HashMap<String, Value>) -> Message {
+    Message::Success(Success::new(metadata))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    use async_trait::async_trait;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio_util::codec::Framed;
+
+    use bolt_client::Client;
+    use bolt_proto::version::VersionRange;
+
+    use crate::{QueryResult, Server};
+
+    use super::*;
+
+    // Records the calls made to it, and answers queries based on their statement
+    #[derive(Clone, Default)]
+    struct TestHandler {
+        calls: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl TestHandler {
+        fn log(&self, call: &str) {
+            self.calls.lock().unwrap().push(call.to_string());
+        }
+    }
+
+    #[async_trait]
+    impl BoltHandler for TestHandler {
+        async fn hello(
+            &mut self,
+            metadata: HashMap<String, Value>,
+        ) -> std::result::Result<HashMap<String, Value>, HandlerError> {
+            self.log("hello");
+            match metadata.get("credentials") {
+                Some(credentials) if credentials == &Value::from("test") => Ok(HashMap::new()),
+                _ => Err(HandlerError::new(
+                    "Neo.ClientError.Security.Unauthorized",
+                    "Invalid credentials.",
+                )),
+            }
+        }
+
+        async fn run(
+            &mut self,
+            statement: String,
+            _parameters: HashMap<String, Value>,
+            _metadata: HashMap<String, Value>,
+        ) -> std::result::Result<QueryResult, HandlerError> {
+            self.log(&statement);
+            let numbers = (1..=5_i8).map(|n| Record::new(vec![Value::from(n)]));
+            match statement.as_str() {
+                "RETURN range(1, 5)" => Ok(QueryResult::from_records(
+                    vec!["n".to_string()],
+                    numbers.collect(),
+                )),
+                "FAIL" => Err(HandlerError::new(
+                    "Neo.ClientError.Statement.SyntaxError",
+                    "Invalid input.",
+                )),
+                "FAIL WHILE STREAMING" => Ok(QueryResult::new(
+                    vec!["n".to_string()],
+                    tokio::stream::iter(vec![
+                        Ok(Record::new(vec![Value::from(1_i8)])),
+                        Err(HandlerError::new(
+                            "Neo.DatabaseError.General.UnknownError",
+                            "Out of numbers.",
+                        )),
+                    ]),
+                )),
+                "SLOW" => {
+                    tokio::time::delay_for(Duration::from_millis(100)).await;
+                    Ok(QueryResult::empty())
+                }
+                _ => Ok(QueryResult::empty()),
+            }
+        }
+
+        async fn begin(
+            &mut self,
+            _metadata: HashMap<String, Value>,
+        ) -> std::result::Result<HashMap<String, Value>, HandlerError> {
+            self.log("begin");
+            Ok(HashMap::new())
+        }
+
+        async fn commit(&mut self) -> std::result::Result<HashMap<String, Value>, HandlerError> {
+            self.log("commit");
+            Ok(HashMap::from_iter(vec![(
+                "bookmark".to_string(),
+                Value::from("bookmark:1"),
+            )]))
+        }
+
+        async fn rollback(&mut self) -> std::result::Result<(), HandlerError> {
+            self.log("rollback");
+            Ok(())
+        }
+    }
+
+    async fn start_server(handler: TestHandler) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        tokio::spawn(Server::new(handler).serve(listener));
+        address
+    }
+
+    async fn new_client(address: &str, version: u8) -> Client {
+        let mut client = Client::new(address, None).await.unwrap();
+        client
+            .handshake(&[VersionRange::exact(ProtocolVersion::new(version, 0))])
+            .await
+            .unwrap();
+        client
+    }
+
+    fn auth_token(password: &str) -> HashMap<String, String> {
+        HashMap::from_iter(vec![
+            ("scheme".to_string(), "basic".to_string()),
+            ("principal".to_string(), "neo4j".to_string()),
+            ("credentials".to_string(), password.to_string()),
+        ])
+    }
+
+    async fn get_initialized_client(address: &str, version: u8) -> Client {
+        let mut client = new_client(address, version).await;
+        let response = if version < 3 {
+            client
+                .init("bolt-client/X.Y.Z".to_string(), auth_token("test"))
+                .await
+        } else {
+            client.hello(auth_token("test")).await
+        };
+        assert!(Success::try_from(response.unwrap()).is_ok());
+        client
+    }
+
+    // Connect without a Client, for sending messages that a Client would refuse to send
+    async fn raw_client(address: &str, version: u8) -> Framed<TcpStream, BoltCodec> {
+        let mut stream = TcpStream::connect(address).await.unwrap();
+        stream
+            .write_all(&[
+                0x60, 0x60, 0xB0, 0x17, 0, 0, 0, version, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            ])
+            .await
+            .unwrap();
+        assert_eq!(stream.read_u32().await.unwrap(), version as u32);
+        Framed::new(stream, BoltCodec::new())
+    }
+
+    async fn initialized_raw_client(address: &str) -> Framed<TcpStream, BoltCodec> {
+        let mut framed = raw_client(address, 3).await;
+        framed
+            .send(Message::Hello(Hello::new(HashMap::from_iter(vec![(
+                "credentials".to_string(),
+                Value::from("test"),
+            )]))))
+            .await
+            .unwrap();
+        assert!(Success::try_from(framed.next().await.unwrap().unwrap()).is_ok());
+        framed
+    }
+
+    async fn request(framed: &mut Framed<TcpStream, BoltCodec>, message: Message) -> Message {
+        framed.send(message).await.unwrap();
+        framed.next().await.unwrap().unwrap()
+    }
+
+    fn pull_n(n: i64) -> Option<HashMap<String, Value>> {
+        Some(HashMap::from_iter(vec![("n".to_string(), Value::from(n))]))
+    }
+
+    #[tokio::test]
+    async fn init_and_run() {
+        let address = start_server(TestHandler::default()).await;
+        let mut client = get_initialized_client(&address, 1).await;
+        let response = client
+            .run("RETURN range(1, 5)".to_string(), None)
+            .await
+            .unwrap();
+        let success = Success::try_from(response).unwrap();
+        assert_eq!(
+            success.metadata().get("fields"),
+            Some(&Value::from(vec!["n"]))
+        );
+        let (response, records) = client.pull_all().await.unwrap();
+        assert!(Success::try_from(response).is_ok());
+        assert_eq!(records.len(), 5);
+        assert_eq!(records[4].fields(), &[Value::from(5_i8)]);
+    }
+
+    #[tokio::test]
+    async fn hello_fail() {
+        let address = start_server(TestHandler::default()).await;
+        let mut framed = raw_client(&address, 3).await;
+        let hello = Message::Hello(Hello::new(HashMap::from_iter(vec![(
+            "credentials".to_string(),
+            Value::from("invalid"),
+        )])));
+        assert!(Failure::try_from(request(&mut framed, hello).await).is_ok());
+        // The server closes the connection after a failed authentication
+        framed.send(Message::Reset).await.unwrap();
+        assert!(framed.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn request_before_hello_closes_connection() {
+        let address = start_server(TestHandler::default()).await;
+        let mut framed = raw_client(&address, 3).await;
+        framed.send(Message::Reset).await.unwrap();
+        assert!(framed.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn failure_and_ack_failure() {
+        let address = start_server(TestHandler::default()).await;
+        let mut client = get_initialized_client(&address, 2).await;
+        let response = client.run("FAIL".to_string(), None).await.unwrap();
+        assert!(Failure::try_from(response).is_ok());
+        let response = client.run("RETURN 1".to_string(), None).await.unwrap();
+        assert_eq!(response, Message::Ignored);
+        let response = client.ack_failure().await.unwrap();
+        assert!(Success::try_from(response).is_ok());
+        let response = client.run("RETURN 1".to_string(), None).await.unwrap();
+        assert!(Success::try_from(response).is_ok());
+    }
+
+    #[tokio::test]
+    async fn failure_while_streaming() {
+        let address = start_server(TestHandler::default()).await;
+        let mut client = get_initialized_client(&address, 3).await;
+        client
+            .run_with_metadata("FAIL WHILE STREAMING".to_string(), None, None)
+            .await
+            .unwrap();
+        let (response, records) = client.pull_all().await.unwrap();
+        assert!(Failure::try_from(response).is_ok());
+        assert_eq!(records.len(), 1);
+        let response = client.reset().await.unwrap();
+        assert!(Success::try_from(response).is_ok());
+    }
+
+    #[tokio::test]
+    async fn pull_without_result_fails() {
+        let address = start_server(TestHandler::default()).await;
+        let mut framed = initialized_raw_client(&address).await;
+        assert!(Failure::try_from(request(&mut framed, Message::PullAll).await).is_ok());
+    }
+
+    #[tokio::test]
+    async fn transaction() {
+        let handler = TestHandler::default();
+        let address = start_server(handler.clone()).await;
+        let mut framed = initialized_raw_client(&address).await;
+        assert!(Failure::try_from(request(&mut framed, Message::Commit).await).is_ok());
+
+        let mut client = get_initialized_client(&address, 3).await;
+        client.begin(HashMap::<String, Value>::new()).await.unwrap();
+        client
+            .run_with_metadata("CREATE (n)".to_string(), None, None)
+            .await
+            .unwrap();
+        client.pull_all().await.unwrap();
+        let response = client.commit().await.unwrap();
+        let success = Success::try_from(response).unwrap();
+        assert_eq!(
+            success.metadata().get("bookmark"),
+            Some(&Value::from("bookmark:1"))
+        );
+
+        client.begin(HashMap::<String, Value>::new()).await.unwrap();
+        client.reset().await.unwrap();
+        assert_eq!(
+            *handler.calls.lock().unwrap(),
+            vec![
+                "hello",
+                "hello",
+                "begin",
+                "CREATE (n)",
+                "commit",
+                "begin",
+                "rollback"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn pull_in_batches() {
+        let address = start_server(TestHandler::default()).await;
+        let mut client = get_initialized_client(&address, 4).await;
+        client
+            .run_with_metadata("RETURN range(1, 5)".to_string(), None, None)
+            .await
+            .unwrap();
+        let (response, records) = client.pull(pull_n(2)).await.unwrap();
+        let success = Success::try_from(response).unwrap();
+        assert_eq!(success.metadata().get("has_more"), Some(&Value::from(true)));
+        assert_eq!(records.len(), 2);
+
+        let response = client.discard(pull_n(1)).await.unwrap();
+        let success = Success::try_from(response).unwrap();
+        assert_eq!(success.metadata().get("has_more"), Some(&Value::from(true)));
+
+        let (response, records) = client.pull(pull_n(-1)).await.unwrap();
+        let success = Success::try_from(response).unwrap();
+        assert!(success.metadata().get("has_more").is_none());
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].fields(), &[Value::from(4_i8)]);
+    }
+
+    #[tokio::test]
+    async fn reset_interrupts_queued_messages() {
+        let address = start_server(TestHandler::default()).await;
+        let mut framed = initialized_raw_client(&address).await;
+        framed
+            .send(Message::RunWithMetadata(RunWithMetadata::new(
+                "SLOW".to_string(),
+                HashMap::new(),
+                HashMap::new(),
+            )))
+            .await
+            .unwrap();
+        framed.send(Message::PullAll).await.unwrap();
+        // The RESET arrives while the server is still running the query
+        tokio::time::delay_for(Duration::from_millis(20)).await;
+        framed.send(Message::Reset).await.unwrap();
+
+        let mut responses = Vec::new();
+        for _ in 0..3 {
+            responses.push(framed.next().await.unwrap().unwrap());
+        }
+        assert!(Success::try_from(responses[0].clone()).is_ok());
+        assert_eq!(responses[1], Message::Ignored);
+        assert!(Success::try_from(responses[2].clone()).is_ok());
+    }
+
+    #[tokio::test]
+    async fn unsupported_message_closes_connection() {
+        let address = start_server(TestHandler::default()).await;
+        let mut framed = raw_client(&address, 1).await;
+        framed
+            .send(Message::Init(Init::new(
+                "bolt-client/X.Y.Z".to_string(),
+                HashMap::from_iter(vec![("credentials".to_string(), Value::from("test"))]),
+            )))
+            .await
+            .unwrap();
+        assert!(Success::try_from(framed.next().await.unwrap().unwrap()).is_ok());
+        framed
+            .send(Message::Begin(Begin::new(HashMap::new())))
+            .await
+            .unwrap();
+        assert!(framed.next().await.is_none());
+    }
+}
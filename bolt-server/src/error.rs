@@ -0,0 +1,19 @@
+use thiserror::Error;
+
+use bolt_proto::{Message, ProtocolVersion};
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(transparent)]
+    IOError(#[from] std::io::Error),
+    #[error("Invalid handshake preamble: {0:02X?}")]
+    InvalidPreamble([u8; 4]),
+    #[error("No supported version among those proposed by the client: {0:08X?}")]
+    NoCommonVersion([u32; 4]),
+    #[error("Message not supported by protocol version {0}: {1:?}")]
+    UnsupportedMessage(ProtocolVersion, Message),
+    #[error(transparent)]
+    ProtocolError(#[from] bolt_proto::error::Error),
+}
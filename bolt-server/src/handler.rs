@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::iter::FromIterator;
+use std::pin::Pin;
+
+use async_trait::async_trait;
+use tokio::stream::{self, Stream};
+
+use bolt_proto::message::*;
+use bolt_proto::{Message, Value};
+
+/// A stream of the records produced by a query. Records are only pulled from the stream as the client requests them.
+pub type RecordStream = Pin<Box<dyn Stream<Item = Result<Record, HandlerError>> + Send>>;
+
+/// The application logic behind a Bolt server.
+///
+/// Each connection gets its own copy of the handler, cloned from the one given to [`Server::new`](crate::Server::new),
+/// so any state shared between connections should be kept behind an `Arc`. Methods are only called when the server
+/// state machine allows it, e.g. [`commit`](BoltHandler::commit) is never called outside of an explicit transaction.
+///
+/// Apart from [`run`](BoltHandler::run), all methods have default implementations that accept the request.
+#[async_trait]
+pub trait BoltHandler: Send + Sync + 'static {
+    /// Authenticate a client. The metadata contains the authentication token sent by the client, along with its
+    /// `user_agent`. For clients using Bolt v1 or v2, the fields of the `INIT` message are combined in the same way.
+    ///
+    /// The returned metadata is sent to the client in a `SUCCESS` message.
+    async fn hello(
+        &mut self,
+        _metadata: HashMap<String, Value>,
+    ) -> Result<HashMap<String, Value>, HandlerError> {
+        Ok(HashMap::new())
+    }
+
+    /// Run a query, returning the names of its fields and a stream of its records. The metadata is empty for clients
+    /// using Bolt v1 or v2.
+    async fn run(
+        &mut self,
+        statement: String,
+        parameters: HashMap<String, Value>,
+        metadata: HashMap<String, Value>,
+    ) -> Result<QueryResult, HandlerError>;
+
+    /// Begin an explicit transaction (Bolt v3+).
+    async fn begin(
+        &mut self,
+        _metadata: HashMap<String, Value>,
+    ) -> Result<HashMap<String, Value>, HandlerError> {
+        Ok(HashMap::new())
+    }
+
+    /// Commit the current transaction (Bolt v3+). The returned metadata, e.g. a bookmark, is sent to the client in a
+    /// `SUCCESS` message.
+    async fn commit(&mut self) -> Result<HashMap<String, Value>, HandlerError> {
+        Ok(HashMap::new())
+    }
+
+    /// Roll back the current transaction (Bolt v3+). This is also called when a transaction is abandoned because of a
+    /// failure, a reset, or a closed connection.
+    async fn rollback(&mut self) -> Result<(), HandlerError> {
+        Ok(())
+    }
+
+    /// Called when the client resets the connection. Any open transaction has already been rolled back at this point.
+    async fn reset(&mut self) {}
+
+    /// Called when the connection is closed, whether or not the client said goodbye.
+    async fn goodbye(&mut self) {}
+}
+
+/// An error returned by a [`BoltHandler`](BoltHandler), which is sent to the client in a `FAILURE` message.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct HandlerError {
+    code: String,
+    message: String,
+}
+
+impl HandlerError {
+    /// Create a new error. Codes should follow the Neo4j convention, e.g. `Neo.ClientError.Statement.SyntaxError`.
+    pub fn new(code: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            code: code.into(),
+            message: message.into(),
+        }
+    }
+
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub(crate) fn request_invalid(message: impl Into<String>) -> Self {
+        Self::new("Neo.ClientError.Request.Invalid", message)
+    }
+}
+
+impl fmt::Display for HandlerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.code, self.message)
+    }
+}
+
+impl std::error::Error for HandlerError {}
+
+impl From<HandlerError> for Message {
+    fn from(error: HandlerError) -> Self {
+        Message::Failure(Failure::new(HashMap::from_iter(vec![
+            ("code".to_string(), Value::from(error.code)),
+            ("message".to_string(), Value::from(error.message)),
+        ])))
+    }
+}
+
+/// The result of running a query: the names of its fields, a stream of records, and the metadata sent to the client
+/// once all records have been pulled.
+pub struct QueryResult {
+    pub(crate) fields: Vec<String>,
+    pub(crate) records: RecordStream,
+    pub(crate) summary: HashMap<String, Value>,
+}
+
+impl QueryResult {
+    pub fn new(
+        fields: Vec<String>,
+        records: impl Stream<Item = Result<Record, HandlerError>> + Send + 'static,
+    ) -> Self {
+        Self {
+            fields,
+            records: Box::pin(records),
+            summary: HashMap::new(),
+        }
+    }
+
+    /// Create a result from records that are already available.
+    pub fn from_records(fields: Vec<String>, records: Vec<Record>) -> Self {
+        Self::new(fields, stream::iter(records.into_iter().map(Ok)))
+    }
+
+    /// Create a result with no fields or records, e.g. for a query that only writes data.
+    pub fn empty() -> Self {
+        Self::from_records(vec![], vec![])
+    }
+
+    /// Set the metadata sent to the client in the `SUCCESS` message following the last record.
+    pub fn with_summary(mut self, summary: HashMap<String, Value>) -> Self {
+        self.summary = summary;
+        self
+    }
+
+    pub fn fields(&self) -> &[String] {
+        &self.fields
+    }
+}
+
+impl fmt::Debug for QueryResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("QueryResult")
+            .field("fields", &self.fields)
+            .field("summary", &self.summary)
+            .finish()
+    }
+}
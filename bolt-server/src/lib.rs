@@ -0,0 +1,52 @@
+//! A framework for building servers that speak the Bolt protocol.
+//!
+//! The [`Server`](Server) accepts connections, negotiates a protocol version with each client, and enforces the Bolt
+//! server state machine. Authentication, queries and transactions are dispatched to a user-provided
+//! [`BoltHandler`](BoltHandler), and the records it produces are streamed back to the client as they are pulled.
+//!
+//! # Example
+//! ```no_run
+//! use std::collections::HashMap;
+//!
+//! use async_trait::async_trait;
+//! use tokio::net::TcpListener;
+//!
+//! use bolt_proto::message::Record;
+//! use bolt_proto::Value;
+//! use bolt_server::{BoltHandler, HandlerError, QueryResult, Server};
+//!
+//! // A "graph service" that echoes the query it was given
+//! #[derive(Clone)]
+//! struct Echo;
+//!
+//! #[async_trait]
+//! impl BoltHandler for Echo {
+//!     async fn run(
+//!         &mut self,
+//!         statement: String,
+//!         _parameters: HashMap<String, Value>,
+//!         _metadata: HashMap<String, Value>,
+//!     ) -> Result<QueryResult, HandlerError> {
+//!         Ok(QueryResult::from_records(
+//!             vec!["statement".to_string()],
+//!             vec![Record::new(vec![Value::from(statement)])],
+//!         ))
+//!     }
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let listener = TcpListener::bind("127.0.0.1:7687").await?;
+//!     Server::new(Echo).serve(listener).await?;
+//!     Ok(())
+//! }
+//! ```
+#[doc(inline)]
+pub use self::handler::{BoltHandler, HandlerError, QueryResult, RecordStream};
+#[doc(inline)]
+pub use self::server::Server;
+
+mod connection;
+pub mod error;
+mod handler;
+mod server;
@@ -0,0 +1,126 @@
+use tokio::io::{self, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+use bolt_proto::version::VersionRange;
+use bolt_proto::ProtocolVersion;
+
+use crate::connection::Connection;
+use crate::error::*;
+use crate::handler::BoltHandler;
+
+const PREAMBLE: [u8; 4] = [0x60, 0x60, 0xB0, 0x17];
+const DEFAULT_VERSIONS: &[ProtocolVersion] = &[
+    ProtocolVersion::new(4, 0),
+    ProtocolVersion::new(3, 0),
+    ProtocolVersion::new(2, 0),
+    ProtocolVersion::new(1, 0),
+];
+
+/// A Bolt server, which negotiates a protocol version with each client and dispatches its requests to a
+/// [`BoltHandler`](crate::BoltHandler).
+#[derive(Debug, Clone)]
+pub struct Server<H> {
+    handler: H,
+    versions: Vec<ProtocolVersion>,
+    agent: String,
+}
+
+impl<H: BoltHandler + Clone> Server<H> {
+    /// Create a new server. Each connection is handled by its own clone of the given handler.
+    pub fn new(handler: H) -> Self {
+        Self {
+            handler,
+            versions: DEFAULT_VERSIONS.to_vec(),
+            agent: format!("bolt-server/{}", env!("CARGO_PKG_VERSION")),
+        }
+    }
+
+    /// Set the protocol versions supported by the server, in order of preference. By default, versions 4.0, 3, 2 and
+    /// 1 are supported.
+    pub fn with_versions(mut self, versions: &[ProtocolVersion]) -> Self {
+        self.versions = versions.to_vec();
+        self
+    }
+
+    /// Set the server agent sent to clients once they have been authenticated, unless the handler provides one.
+    pub fn with_agent(mut self, agent: impl Into<String>) -> Self {
+        self.agent = agent.into();
+        self
+    }
+
+    /// Accept connections from the given listener until it fails, serving each one in a separate task. Errors on
+    /// individual connections are not reported.
+    pub async fn serve(self, mut listener: TcpListener) -> Result<()> {
+        loop {
+            let (stream, _) = listener.accept().await?;
+            let server = self.clone();
+            tokio::spawn(async move {
+                let _ = server.serve_connection(stream).await;
+            });
+        }
+    }
+
+    /// Serve a single connection until the client disconnects.
+    pub async fn serve_connection(
+        &self,
+        mut stream: impl AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    ) -> Result<()> {
+        let version = self.handshake(&mut stream).await?;
+        let (reader, writer) = io::split(stream);
+        Connection::new(
+            self.handler.clone(),
+            version,
+            self.agent.clone(),
+            reader,
+            writer,
+        )
+        .run()
+        .await
+    }
+
+    async fn handshake(
+        &self,
+        stream: &mut (impl AsyncRead + AsyncWrite + Unpin),
+    ) -> Result<ProtocolVersion> {
+        let mut preamble = [0; 4];
+        stream.read_exact(&mut preamble).await?;
+        if preamble != PREAMBLE {
+            return Err(Error::InvalidPreamble(preamble));
+        }
+        let mut proposals = [0; 4];
+        for proposal in proposals.iter_mut() {
+            *proposal = stream.read_u32().await?;
+        }
+
+        match self.choose_version(&proposals) {
+            Some(version) => {
+                stream.write_u32(version.into()).await?;
+                stream.flush().await?;
+                Ok(version)
+            }
+            None => {
+                stream.write_u32(0).await?;
+                stream.flush().await?;
+                Err(Error::NoCommonVersion(proposals))
+            }
+        }
+    }
+
+    // Go through the client's proposals in its order of preference, choosing our most preferred version that matches.
+    fn choose_version(&self, proposals: &[u32]) -> Option<ProtocolVersion> {
+        proposals
+            .iter()
+            .filter(|&&proposal| proposal != 0)
+            .find_map(|&proposal| {
+                let [reserved, range, minor, major] = proposal.to_be_bytes();
+                if reserved != 0 {
+                    return None;
+                }
+                let proposal = VersionRange::new(ProtocolVersion::new(major, minor), range);
+                self.versions
+                    .iter()
+                    .copied()
+                    .find(|&v| proposal.contains(v))
+            })
+    }
+}
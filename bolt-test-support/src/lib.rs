@@ -0,0 +1,494 @@
+//! A scriptable, in-process mock Bolt server, for testing Bolt clients without a running database.
+//!
+//! A [`Script`](Script) describes the protocol version the server should agree to during the handshake, the messages
+//! it expects to receive from a client (in order), and the messages it should send in response to each of them. A
+//! [`MockServer`](MockServer) listens on a random local port and plays the script against every client that connects.
+//!
+//! ```
+//! use std::collections::HashMap;
+//!
+//! use bolt_proto::message::*;
+//! use bolt_proto::{Message, ProtocolVersion};
+//! use bolt_test_support::{MockServer, Script};
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let script = Script::new(ProtocolVersion::new(3, 0))
+//!     .expect_with(
+//!         "HELLO",
+//!         |message| matches!(message, Message::Hello(_)),
+//!         vec![Message::Success(Success::new(HashMap::new()))],
+//!     )
+//!     .expect(Message::Reset, vec![Message::Success(Success::new(HashMap::new()))]);
+//! let server = MockServer::start(script).await;
+//! // Connect a client to `server.address()`...
+//! # }
+//! ```
+//!
+//! Any message that doesn't match the next step of the script (or arrives after the script has run out of steps) is
+//! recorded as a mismatch and causes the server to close the connection. A client disconnecting before it has sent
+//! every expected message is recorded as a mismatch too. Mismatches are reported by
+//! [`MockServer::verify`](MockServer::verify), which is also called when the server is dropped.
+//!
+//! Scripts can also be played over other kinds of connections with [`Script::play`](Script::play).
+
+use std::convert::TryInto;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use bytes::Bytes;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufStream};
+use tokio::net::TcpListener;
+
+use bolt_proto::version::VersionRange;
+use bolt_proto::{Message, ProtocolVersion};
+
+const PREAMBLE: [u8; 4] = [0x60, 0x60, 0xB0, 0x17];
+const MAX_PROPOSED_VERSIONS: usize = 4;
+
+type Predicate = dyn Fn(&Message) -> bool + Send + Sync;
+type Handler = dyn Fn(&Message) -> Option<Vec<Message>> + Send + Sync;
+// The descriptions of the messages a connected client has yet to send
+type Pending = Arc<Mutex<Vec<String>>>;
+
+#[derive(Clone)]
+enum Step {
+    Expect {
+        description: String,
+        predicate: Arc<Predicate>,
+        responses: Vec<Message>,
+    },
+    Close,
+}
+
+/// The sequence of messages a [`MockServer`](MockServer) expects from each client, along with its responses.
+#[derive(Clone)]
+pub struct Script {
+    version: ProtocolVersion,
+    steps: Vec<Step>,
+    fallback: Option<Arc<Handler>>,
+}
+
+impl Script {
+    /// Create an empty script. The server will agree to use the given protocol version if the client proposes it
+    /// during the handshake, and reject the handshake otherwise.
+    pub fn new(version: ProtocolVersion) -> Self {
+        Self {
+            version,
+            steps: Vec::new(),
+            fallback: None,
+        }
+    }
+
+    /// Get the protocol version the server will agree to.
+    pub fn version(&self) -> ProtocolVersion {
+        self.version
+    }
+
+    /// Expect the client to send exactly the given message next, and reply with the given responses.
+    pub fn expect(self, message: Message, responses: Vec<Message>) -> Self {
+        let description = format!("{:?}", message);
+        self.expect_with(description, move |m| *m == message, responses)
+    }
+
+    /// Expect the client to send a message satisfying the given predicate next, and reply with the given responses.
+    /// This is useful when a message contains values that can't be known ahead of time, like a user agent.
+    pub fn expect_with(
+        mut self,
+        description: impl Into<String>,
+        predicate: impl Fn(&Message) -> bool + Send + Sync + 'static,
+        responses: Vec<Message>,
+    ) -> Self {
+        self.steps.push(Step::Expect {
+            description: description.into(),
+            predicate: Arc::new(predicate),
+            responses,
+        });
+        self
+    }
+
+    /// Close the connection at this point of the script, without waiting for any further messages.
+    pub fn close(mut self) -> Self {
+        self.steps.push(Step::Close);
+        self
+    }
+
+    /// Once all other steps of the script have been played, answer each further message with the responses returned
+    /// by the given handler. A handler returning `None` indicates an unexpected message.
+    ///
+    /// This is useful for clients that send an unpredictable number of messages, like those in a connection pool.
+    pub fn then_respond_with(
+        mut self,
+        handler: impl Fn(&Message) -> Option<Vec<Message>> + Send + Sync + 'static,
+    ) -> Self {
+        self.fallback = Some(Arc::new(handler));
+        self
+    }
+
+    /// Play the script against a single client connected over the given stream, such as one end of a Unix socket pair,
+    /// returning a description of the first mismatch, if any.
+    pub async fn play(&self, stream: impl AsyncRead + AsyncWrite + Unpin) -> Result<(), String> {
+        let pending = Mutex::new(Vec::new());
+        serve(&mut BufStream::new(stream), self.clone(), &pending).await
+    }
+
+    fn accepts(&self, proposals: &[u32]) -> bool {
+        proposals.iter().any(|&proposal| {
+            let [_, range, minor, major] = proposal.to_be_bytes();
+            VersionRange::new(ProtocolVersion::new(major, minor), range).contains(self.version)
+        })
+    }
+}
+
+impl fmt::Debug for Script {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let steps: Vec<&str> = self
+            .steps
+            .iter()
+            .map(|step| match step {
+                Step::Expect { description, .. } => description.as_str(),
+                Step::Close => "<close>",
+            })
+            .collect();
+        f.debug_struct("Script")
+            .field("version", &self.version)
+            .field("steps", &steps)
+            .field("fallback", &self.fallback.is_some())
+            .finish()
+    }
+}
+
+/// A mock Bolt server running in the background, which plays a [`Script`](Script) against every client that
+/// connects to it. The server stops accepting connections when the Tokio runtime it was started on shuts down.
+#[derive(Debug)]
+pub struct MockServer {
+    address: String,
+    mismatches: Arc<Mutex<Vec<String>>>,
+    connections: Arc<Mutex<Vec<Pending>>>,
+}
+
+impl MockServer {
+    /// Start a server listening on a random port on the loopback interface.
+    pub async fn start(script: Script) -> Self {
+        let mut listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("failed to bind mock server");
+        let address = listener.local_addr().unwrap().to_string();
+        let mismatches = Arc::new(Mutex::new(Vec::new()));
+        let connections = Arc::new(Mutex::new(Vec::new()));
+
+        let connection_mismatches = Arc::clone(&mismatches);
+        let server_connections = Arc::clone(&connections);
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let script = script.clone();
+                let mismatches = Arc::clone(&connection_mismatches);
+                let pending: Pending = Arc::new(Mutex::new(Vec::new()));
+                server_connections
+                    .lock()
+                    .unwrap()
+                    .push(Arc::clone(&pending));
+                tokio::spawn(async move {
+                    // Record any mismatch before hanging up, so it's visible as soon as the client notices
+                    let mut stream = BufStream::new(stream);
+                    if let Err(mismatch) = serve(&mut stream, script, &pending).await {
+                        mismatches.lock().unwrap().push(mismatch);
+                    }
+                    pending.lock().unwrap().clear();
+                });
+            }
+        });
+
+        Self {
+            address,
+            mismatches,
+            connections,
+        }
+    }
+
+    /// Get the address the server is listening on, in `host:port` form.
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    /// Panic if any client sent a message that didn't match the script, or has not sent every expected message,
+    /// whether or not it is still connected.
+    pub fn verify(&self) {
+        let mut mismatches = self.mismatches.lock().unwrap().clone();
+        for pending in self.connections.lock().unwrap().iter() {
+            let pending = pending.lock().unwrap();
+            if !pending.is_empty() {
+                mismatches.push(format!(
+                    "connection still open while expecting {}",
+                    pending.join(", ")
+                ));
+            }
+        }
+        if !mismatches.is_empty() {
+            panic!(
+                "mock server received unexpected messages:\n{}",
+                mismatches.join("\n")
+            );
+        }
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        if !std::thread::panicking() {
+            self.verify();
+        }
+    }
+}
+
+// Describe the messages the client must still send given the remaining steps of the script, up to a scripted close
+fn pending_messages(remaining: &[Step]) -> Vec<String> {
+    remaining
+        .iter()
+        .take_while(|step| !matches!(step, Step::Close))
+        .map(|step| match step {
+            Step::Expect { description, .. } => description.clone(),
+            Step::Close => unreachable!(),
+        })
+        .collect()
+}
+
+// The outcome of a connection that was closed with the given steps of the script left to play
+fn closed(remaining: &[Step]) -> Result<(), String> {
+    let expected = pending_messages(remaining);
+    if expected.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "connection closed while expecting {}",
+            expected.join(", ")
+        ))
+    }
+}
+
+// Play the script against a single client, keeping track of the messages it has yet to send. Connection errors end
+// the script, and are only considered mismatches if the client was still expected to send messages.
+async fn serve<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut BufStream<S>,
+    script: Script,
+    pending: &Mutex<Vec<String>>,
+) -> Result<(), String> {
+    *pending.lock().unwrap() = pending_messages(&script.steps);
+    let mut preamble = [0; 4];
+    if stream.read_exact(&mut preamble).await.is_err() {
+        return closed(&script.steps);
+    }
+    if preamble != PREAMBLE {
+        return Err(format!("invalid preamble: {:02X?}", preamble));
+    }
+    let mut proposals = [0; MAX_PROPOSED_VERSIONS];
+    for proposal in proposals.iter_mut() {
+        match stream.read_u32().await {
+            Ok(value) => *proposal = value,
+            Err(_) => return closed(&script.steps),
+        }
+    }
+    if !script.accepts(&proposals) {
+        let _ = stream.write_u32(0).await;
+        let _ = stream.flush().await;
+        return Ok(());
+    }
+    if stream.write_u32(script.version.into()).await.is_err() || stream.flush().await.is_err() {
+        return closed(&script.steps);
+    }
+
+    let mut remaining = script.steps.as_slice();
+    loop {
+        let expected = remaining.first();
+        if let Some(Step::Close) = expected {
+            return Ok(());
+        }
+        let message = match Message::from_stream(stream).await {
+            Ok(message) => message,
+            Err(_) => return closed(remaining),
+        };
+        remaining = remaining.get(1..).unwrap_or_default();
+        *pending.lock().unwrap() = pending_messages(remaining);
+        let responses = match (expected, &script.fallback) {
+            (
+                Some(Step::Expect {
+                    description,
+                    predicate,
+                    responses,
+                }),
+                _,
+            ) => {
+                if !predicate(&message) {
+                    return Err(format!("expected {}, got {:?}", description, message));
+                }
+                responses.clone()
+            }
+            (None, Some(handler)) => match handler(&message) {
+                Some(responses) => responses,
+                None => return Err(format!("unexpected message: {:?}", message)),
+            },
+            // A client may say goodbye at any point once the script is finished
+            (None, None) if message == Message::Goodbye => return Ok(()),
+            (None, None) => return Err(format!("expected end of script, got {:?}", message)),
+            (Some(Step::Close), _) => unreachable!(),
+        };
+
+        for response in responses {
+            let chunks: Vec<Bytes> = response.try_into().expect("invalid response message");
+            for mut chunk in chunks {
+                if stream.write_buf(&mut chunk).await.is_err() {
+                    return closed(remaining);
+                }
+            }
+        }
+        if stream.flush().await.is_err() {
+            return closed(remaining);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::net::Shutdown;
+
+    use bolt_proto::message::*;
+    use bolt_proto::Value;
+    use tokio::net::TcpStream;
+
+    use super::*;
+
+    async fn connect(server: &MockServer, proposal: u32) -> (BufStream<TcpStream>, u32) {
+        let mut stream = BufStream::new(TcpStream::connect(server.address()).await.unwrap());
+        stream.write_all(&PREAMBLE).await.unwrap();
+        for &value in &[proposal, 0, 0, 0] {
+            stream.write_u32(value).await.unwrap();
+        }
+        stream.flush().await.unwrap();
+        let response = stream.read_u32().await.unwrap();
+        (stream, response)
+    }
+
+    async fn send(stream: &mut BufStream<TcpStream>, message: Message) {
+        let chunks: Vec<Bytes> = message.try_into().unwrap();
+        for mut chunk in chunks {
+            stream.write_buf(&mut chunk).await.unwrap();
+        }
+        stream.flush().await.unwrap();
+    }
+
+    fn success() -> Message {
+        Message::Success(Success::new(HashMap::new()))
+    }
+
+    #[tokio::test]
+    async fn handshake() {
+        let server = MockServer::start(Script::new(ProtocolVersion::new(4, 1))).await;
+        let (_, response) = connect(&server, 0x0002_0304).await;
+        assert_eq!(response, 0x0000_0104);
+        let (_, response) = connect(&server, 0x0000_0003).await;
+        assert_eq!(response, 0);
+    }
+
+    #[tokio::test]
+    async fn scripted_responses() {
+        let script = Script::new(ProtocolVersion::new(1, 0))
+            .expect(
+                Message::PullAll,
+                vec![
+                    Message::Record(Record::new(vec![Value::from(1_i8)])),
+                    success(),
+                ],
+            )
+            .expect_with("RESET", |m| *m == Message::Reset, vec![success()]);
+        let server = MockServer::start(script).await;
+        let (mut stream, _) = connect(&server, 1).await;
+
+        send(&mut stream, Message::PullAll).await;
+        assert_eq!(
+            Message::from_stream(&mut stream).await.unwrap(),
+            Message::Record(Record::new(vec![Value::from(1_i8)]))
+        );
+        assert_eq!(Message::from_stream(&mut stream).await.unwrap(), success());
+        send(&mut stream, Message::Reset).await;
+        assert_eq!(Message::from_stream(&mut stream).await.unwrap(), success());
+    }
+
+    #[tokio::test]
+    async fn fallback_handler() {
+        let script = Script::new(ProtocolVersion::new(1, 0)).then_respond_with(|m| match m {
+            Message::Reset => Some(vec![success()]),
+            _ => None,
+        });
+        let server = MockServer::start(script).await;
+        let (mut stream, _) = connect(&server, 1).await;
+        for _ in 0..3 {
+            send(&mut stream, Message::Reset).await;
+            assert_eq!(Message::from_stream(&mut stream).await.unwrap(), success());
+        }
+    }
+
+    #[tokio::test]
+    async fn close() {
+        let script = Script::new(ProtocolVersion::new(1, 0))
+            .expect(Message::Reset, vec![Message::Ignored])
+            .close();
+        let server = MockServer::start(script).await;
+        let (mut stream, _) = connect(&server, 1).await;
+        send(&mut stream, Message::Reset).await;
+        assert_eq!(
+            Message::from_stream(&mut stream).await.unwrap(),
+            Message::Ignored
+        );
+        assert!(Message::from_stream(&mut stream).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn mismatch() {
+        let script =
+            Script::new(ProtocolVersion::new(1, 0)).expect(Message::Reset, vec![success()]);
+        let server = MockServer::start(script).await;
+        let (mut stream, _) = connect(&server, 1).await;
+        send(&mut stream, Message::PullAll).await;
+        // The server hangs up on an unexpected message
+        assert!(Message::from_stream(&mut stream).await.is_err());
+        let mismatches = server.mismatches.lock().unwrap().clone();
+        assert_eq!(mismatches, vec!["expected Reset, got PullAll".to_string()]);
+        server.mismatches.lock().unwrap().clear();
+    }
+
+    #[tokio::test]
+    async fn client_stops_early() {
+        let script = Script::new(ProtocolVersion::new(1, 0))
+            .expect(Message::Reset, vec![success()])
+            .expect(Message::PullAll, vec![success()])
+            .expect(Message::DiscardAll, vec![success()]);
+        let server = MockServer::start(script).await;
+        let (mut stream, _) = connect(&server, 1).await;
+        send(&mut stream, Message::Reset).await;
+        assert_eq!(Message::from_stream(&mut stream).await.unwrap(), success());
+        TcpStream::shutdown(stream.get_ref(), Shutdown::Write).unwrap();
+
+        // The server records the mismatch before hanging up
+        assert!(Message::from_stream(&mut stream).await.is_err());
+        let mismatches = server.mismatches.lock().unwrap().clone();
+        assert_eq!(
+            mismatches,
+            vec!["connection closed while expecting PullAll, DiscardAll".to_string()]
+        );
+        server.mismatches.lock().unwrap().clear();
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "connection still open while expecting PullAll")]
+    async fn client_still_connected() {
+        let script = Script::new(ProtocolVersion::new(1, 0))
+            .expect(Message::Reset, vec![success()])
+            .expect(Message::PullAll, vec![success()]);
+        let server = MockServer::start(script).await;
+        let (mut stream, _) = connect(&server, 1).await;
+        send(&mut stream, Message::Reset).await;
+        assert_eq!(Message::from_stream(&mut stream).await.unwrap(), success());
+        server.verify();
+    }
+}